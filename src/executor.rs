@@ -1,5 +1,18 @@
+use crate::metrics::probe_metric::ProbeMetric;
 use crate::ping_error::PingError;
 use async_trait::async_trait;
+use std::time::Duration;
+
+/// 执行报告
+#[derive(Debug, Default)]
+pub struct ExecReport {
+    /// 探测耗时，为None时以执行任务的总耗时代替
+    pub elapsed: Option<Duration>,
+    /// 探测失败的原因，不为None时目标视为不可达，但附加指标仍然会上报
+    pub error: Option<PingError>,
+    /// 附加指标
+    pub metrics: Vec<ProbeMetric>,
+}
 
 /// 执行器
 #[async_trait]
@@ -8,5 +21,5 @@ pub trait Executor {
     fn get_name(&self) -> String;
 
     /// 执行任务
-    async fn exec(&self) -> Result<(), PingError>;
+    async fn exec(&self) -> Result<ExecReport, PingError>;
 }
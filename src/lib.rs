@@ -1,6 +1,7 @@
 pub mod executor;
 pub mod metrics;
 pub mod ping_error;
+pub mod resolver;
 pub mod scheduler;
 pub mod settings;
 pub mod targets;
@@ -4,3 +4,311 @@ pub const HOST_ACTIVITY_PROMETHEUS_METRIC_NAME: &str = "pong_host_activity";
 pub const HOST_ACTIVITY_PROMETHEUS_METRIC_DESC: &str = "host activity status";
 /// 指标标签名
 pub const HOST_ACTIVITY_PROMETHEUS_METRIC_LABEL_NAME: &str = "host";
+/// 统计方式的标签名
+pub const STAT_PROMETHEUS_METRIC_LABEL_NAME: &str = "stat";
+
+/// 地址的标签名
+pub const ADDR_PROMETHEUS_METRIC_LABEL_NAME: &str = "addr";
+/// 目标是否解析成功的指标名称
+pub const TARGET_RESOLVED_PROMETHEUS_METRIC_NAME: &str = "pong_target_resolved";
+/// 目标是否解析成功的指标描述
+pub const TARGET_RESOLVED_PROMETHEUS_METRIC_DESC: &str =
+    "whether the target host was resolved (1 resolved, 0 failed)";
+/// 目标解析出的地址数的指标名称
+pub const TARGET_ADDRESSES_PROMETHEUS_METRIC_NAME: &str = "pong_target_addresses";
+/// 目标解析出的地址数的指标描述
+pub const TARGET_ADDRESSES_PROMETHEUS_METRIC_DESC: &str =
+    "number of resolved addresses probed, after filtering by ip family";
+
+/// ICMP发送包数的指标名称
+pub const ICMP_PACKETS_SENT_PROMETHEUS_METRIC_NAME: &str = "pong_icmp_packets_sent";
+/// ICMP发送包数的指标描述
+pub const ICMP_PACKETS_SENT_PROMETHEUS_METRIC_DESC: &str =
+    "icmp echo requests sent in the last probe";
+/// ICMP接收包数的指标名称
+pub const ICMP_PACKETS_RECEIVED_PROMETHEUS_METRIC_NAME: &str = "pong_icmp_packets_received";
+/// ICMP接收包数的指标描述
+pub const ICMP_PACKETS_RECEIVED_PROMETHEUS_METRIC_DESC: &str =
+    "icmp echo replies received in the last probe";
+/// ICMP丢包率的指标名称
+pub const ICMP_PACKET_LOSS_PROMETHEUS_METRIC_NAME: &str = "pong_icmp_packet_loss_ratio";
+/// ICMP丢包率的指标描述
+pub const ICMP_PACKET_LOSS_PROMETHEUS_METRIC_DESC: &str = "icmp packet loss ratio (0 ~ 1)";
+/// ICMP往返时间的指标名称
+pub const ICMP_RTT_PROMETHEUS_METRIC_NAME: &str = "pong_icmp_rtt_ms";
+/// ICMP往返时间的指标描述
+pub const ICMP_RTT_PROMETHEUS_METRIC_DESC: &str = "icmp round trip time in milliseconds";
+/// ICMP抖动的指标名称
+pub const ICMP_JITTER_PROMETHEUS_METRIC_NAME: &str = "pong_icmp_jitter_ms";
+/// ICMP抖动的指标描述
+pub const ICMP_JITTER_PROMETHEUS_METRIC_DESC: &str = "icmp jitter in milliseconds";
+/// 路径MTU的指标名称
+pub const ICMP_PATH_MTU_PROMETHEUS_METRIC_NAME: &str = "pong_icmp_path_mtu_bytes";
+/// 路径MTU的指标描述
+pub const ICMP_PATH_MTU_PROMETHEUS_METRIC_DESC: &str =
+    "largest packet size in bytes (including ip header) that reaches the target without fragmentation";
+
+/// 跳数的标签名
+pub const HOP_PROMETHEUS_METRIC_LABEL_NAME: &str = "hop";
+/// 跳的地址的标签名
+pub const HOP_ADDR_PROMETHEUS_METRIC_LABEL_NAME: &str = "hop_addr";
+/// 路由追踪跳数的指标名称
+pub const TRACEROUTE_HOPS_PROMETHEUS_METRIC_NAME: &str = "pong_traceroute_hops";
+/// 路由追踪跳数的指标描述
+pub const TRACEROUTE_HOPS_PROMETHEUS_METRIC_DESC: &str = "number of hops to the target";
+/// 路由追踪每一跳丢包率的指标名称
+pub const TRACEROUTE_HOP_LOSS_PROMETHEUS_METRIC_NAME: &str = "pong_traceroute_hop_loss_ratio";
+/// 路由追踪每一跳丢包率的指标描述
+pub const TRACEROUTE_HOP_LOSS_PROMETHEUS_METRIC_DESC: &str = "packet loss ratio of the hop (0 ~ 1)";
+/// 路由追踪每一跳往返时间的指标名称
+pub const TRACEROUTE_HOP_RTT_PROMETHEUS_METRIC_NAME: &str = "pong_traceroute_hop_rtt_ms";
+/// 路由追踪每一跳往返时间的指标描述
+pub const TRACEROUTE_HOP_RTT_PROMETHEUS_METRIC_DESC: &str =
+    "round trip time of the hop in milliseconds";
+
+/// 状态的标签名
+pub const STATE_PROMETHEUS_METRIC_LABEL_NAME: &str = "state";
+/// TCP连接状态的指标名称
+pub const TCP_CONNECT_STATE_PROMETHEUS_METRIC_NAME: &str = "pong_tcp_connect_state";
+/// TCP连接状态的指标描述
+pub const TCP_CONNECT_STATE_PROMETHEUS_METRIC_DESC: &str =
+    "tcp connect outcome, 1 for the current state (connected / refused / timeout / unreachable)";
+/// TCP回复是否匹配期望的指标名称
+pub const TCP_EXPECT_MATCHED_PROMETHEUS_METRIC_NAME: &str = "pong_tcp_expect_matched";
+/// TCP回复是否匹配期望的指标描述
+pub const TCP_EXPECT_MATCHED_PROMETHEUS_METRIC_DESC: &str =
+    "whether the tcp reply matched the expected pattern (1 matched, 0 not matched)";
+
+/// UDP探测结果的指标名称
+pub const UDP_REPLY_STATE_PROMETHEUS_METRIC_NAME: &str = "pong_udp_reply_state";
+/// UDP探测结果的指标描述
+pub const UDP_REPLY_STATE_PROMETHEUS_METRIC_DESC: &str =
+    "udp probe outcome, 1 for the current state (replied / silent / refused / unreachable)";
+/// UDP回复是否匹配期望的指标名称
+pub const UDP_EXPECT_MATCHED_PROMETHEUS_METRIC_NAME: &str = "pong_udp_expect_matched";
+/// UDP回复是否匹配期望的指标描述
+pub const UDP_EXPECT_MATCHED_PROMETHEUS_METRIC_DESC: &str =
+    "whether a udp reply matched the expected pattern (1 matched, 0 not matched)";
+
+/// 参考ID的标签名
+pub const REFERENCE_ID_PROMETHEUS_METRIC_LABEL_NAME: &str = "reference_id";
+/// NTP往返延迟的指标名称
+pub const NTP_DELAY_PROMETHEUS_METRIC_NAME: &str = "pong_ntp_delay_ms";
+/// NTP往返延迟的指标描述
+pub const NTP_DELAY_PROMETHEUS_METRIC_DESC: &str =
+    "ntp round trip delay in milliseconds, excluding server processing time";
+/// NTP时钟偏移的指标名称
+pub const NTP_OFFSET_PROMETHEUS_METRIC_NAME: &str = "pong_ntp_offset_ms";
+/// NTP时钟偏移的指标描述
+pub const NTP_OFFSET_PROMETHEUS_METRIC_DESC: &str =
+    "offset of the local clock relative to the server in milliseconds, positive if the local clock is behind";
+/// NTP层级的指标名称
+pub const NTP_STRATUM_PROMETHEUS_METRIC_NAME: &str = "pong_ntp_stratum";
+/// NTP层级的指标描述
+pub const NTP_STRATUM_PROMETHEUS_METRIC_DESC: &str =
+    "stratum of the server (1 primary, 2-15 secondary, 0 kiss-o'-death)";
+/// NTP闰秒指示的指标名称
+pub const NTP_LEAP_PROMETHEUS_METRIC_NAME: &str = "pong_ntp_leap";
+/// NTP闰秒指示的指标描述
+pub const NTP_LEAP_PROMETHEUS_METRIC_DESC: &str =
+    "leap indicator (0 no warning, 1 last minute has 61s, 2 last minute has 59s, 3 unsynchronized)";
+/// NTP参考ID的指标名称
+pub const NTP_REFERENCE_PROMETHEUS_METRIC_NAME: &str = "pong_ntp_reference_info";
+/// NTP参考ID的指标描述
+pub const NTP_REFERENCE_PROMETHEUS_METRIC_DESC: &str =
+    "reference id of the server, a clock source such as GPS or the upstream server address";
+
+/// gRPC健康状态的指标名称
+pub const GRPC_HEALTH_STATUS_PROMETHEUS_METRIC_NAME: &str = "pong_grpc_health_status";
+/// gRPC健康状态的指标描述
+pub const GRPC_HEALTH_STATUS_PROMETHEUS_METRIC_DESC: &str =
+    "grpc health check status, 1 for the current status (serving / not_serving / unknown / service_unknown)";
+/// gRPC调用状态码的指标名称
+pub const GRPC_STATUS_CODE_PROMETHEUS_METRIC_NAME: &str = "pong_grpc_status_code";
+/// gRPC调用状态码的指标描述
+pub const GRPC_STATUS_CODE_PROMETHEUS_METRIC_DESC: &str =
+    "grpc-status of the health check call (0 OK, 5 NOT_FOUND, 12 UNIMPLEMENTED, 14 UNAVAILABLE, ...)";
+
+/// WebSocket握手响应状态码的指标名称
+pub const WEBSOCKET_STATUS_CODE_PROMETHEUS_METRIC_NAME: &str = "pong_websocket_status_code";
+/// WebSocket握手响应状态码的指标描述
+pub const WEBSOCKET_STATUS_CODE_PROMETHEUS_METRIC_DESC: &str =
+    "http status code of the upgrade response, 101 if the upgrade succeeded";
+/// WebSocket握手耗时的指标名称
+pub const WEBSOCKET_HANDSHAKE_PROMETHEUS_METRIC_NAME: &str = "pong_websocket_handshake_ms";
+/// WebSocket握手耗时的指标描述
+pub const WEBSOCKET_HANDSHAKE_PROMETHEUS_METRIC_DESC: &str =
+    "time to open the websocket in milliseconds, including tcp connect, tls handshake and upgrade";
+/// WebSocket消息往返耗时的指标名称
+pub const WEBSOCKET_ROUND_TRIP_PROMETHEUS_METRIC_NAME: &str = "pong_websocket_round_trip_ms";
+/// WebSocket消息往返耗时的指标描述
+pub const WEBSOCKET_ROUND_TRIP_PROMETHEUS_METRIC_DESC: &str =
+    "time from sending the message or ping to receiving the expected reply or pong in milliseconds";
+
+/// HTTP状态码的指标名称
+pub const HTTP_STATUS_CODE_PROMETHEUS_METRIC_NAME: &str = "pong_http_status_code";
+/// HTTP状态码的指标描述
+pub const HTTP_STATUS_CODE_PROMETHEUS_METRIC_DESC: &str = "http response status code";
+/// HTTP响应体大小的指标名称
+pub const HTTP_BODY_SIZE_PROMETHEUS_METRIC_NAME: &str = "pong_http_body_size_bytes";
+/// HTTP响应体大小的指标描述
+pub const HTTP_BODY_SIZE_PROMETHEUS_METRIC_DESC: &str =
+    "size of the http response body in bytes, reading stops once it exceeds max-body-size";
+
+/// 阶段的标签名
+pub const PHASE_PROMETHEUS_METRIC_LABEL_NAME: &str = "phase";
+/// HTTP请求各阶段耗时的指标名称
+pub const HTTP_DURATION_PROMETHEUS_METRIC_NAME: &str = "pong_http_duration_ms";
+/// HTTP请求各阶段耗时的指标描述
+pub const HTTP_DURATION_PROMETHEUS_METRIC_DESC: &str =
+    "duration of http request by phase (dns / connect / tls / ttfb / transfer) in milliseconds";
+
+/// 步骤的标签名
+pub const STEP_PROMETHEUS_METRIC_LABEL_NAME: &str = "step";
+/// HTTP事务步骤是否通过的指标名称
+pub const HTTP_FLOW_STEP_PASSED_PROMETHEUS_METRIC_NAME: &str = "pong_http_flow_step_passed";
+/// HTTP事务步骤是否通过的指标描述
+pub const HTTP_FLOW_STEP_PASSED_PROMETHEUS_METRIC_DESC: &str =
+    "whether the step of the http flow passed (1 passed, 0 failed or not reached)";
+/// HTTP事务步骤状态码的指标名称
+pub const HTTP_FLOW_STEP_STATUS_CODE_PROMETHEUS_METRIC_NAME: &str =
+    "pong_http_flow_step_status_code";
+/// HTTP事务步骤状态码的指标描述
+pub const HTTP_FLOW_STEP_STATUS_CODE_PROMETHEUS_METRIC_DESC: &str =
+    "http response status code of the step";
+/// HTTP事务步骤各阶段耗时的指标名称
+pub const HTTP_FLOW_STEP_DURATION_PROMETHEUS_METRIC_NAME: &str = "pong_http_flow_step_duration_ms";
+/// HTTP事务步骤各阶段耗时的指标描述
+pub const HTTP_FLOW_STEP_DURATION_PROMETHEUS_METRIC_DESC: &str =
+    "duration of the step by phase (dns / connect / tls / ttfb / transfer) in milliseconds";
+
+/// 数据库探测各阶段耗时的指标名称
+pub const DB_DURATION_PROMETHEUS_METRIC_NAME: &str = "pong_db_duration_ms";
+/// 数据库探测各阶段耗时的指标描述
+pub const DB_DURATION_PROMETHEUS_METRIC_DESC: &str =
+    "duration of the database probe by phase (connect / auth / query) in milliseconds, auth includes the tls handshake";
+
+/// 邮件服务探测步骤是否通过的指标名称
+pub const MAIL_STEP_PASSED_PROMETHEUS_METRIC_NAME: &str = "pong_mail_step_passed";
+/// 邮件服务探测步骤是否通过的指标描述
+pub const MAIL_STEP_PASSED_PROMETHEUS_METRIC_DESC: &str =
+    "whether the step of the mail probe (banner / hello / starttls / auth) passed (1 passed, 0 failed or not reached)";
+/// 邮件服务探测步骤耗时的指标名称
+pub const MAIL_STEP_DURATION_PROMETHEUS_METRIC_NAME: &str = "pong_mail_step_duration_ms";
+/// 邮件服务探测步骤耗时的指标描述
+pub const MAIL_STEP_DURATION_PROMETHEUS_METRIC_DESC: &str =
+    "duration of the passed step of the mail probe in milliseconds, starttls includes the tls handshake";
+/// 邮件服务证书剩余有效期的指标名称
+pub const MAIL_CERT_EXPIRY_PROMETHEUS_METRIC_NAME: &str = "pong_mail_cert_expiry_seconds";
+/// 邮件服务证书剩余有效期的指标描述
+pub const MAIL_CERT_EXPIRY_PROMETHEUS_METRIC_DESC: &str =
+    "seconds until the leaf certificate presented after starttls expires, negative if already expired";
+
+/// 软件版本的标签名
+pub const SOFTWARE_PROMETHEUS_METRIC_LABEL_NAME: &str = "software";
+/// 主机密钥算法的标签名
+pub const HOST_KEY_ALGORITHM_PROMETHEUS_METRIC_LABEL_NAME: &str = "host_key_algorithm";
+/// 指纹的标签名
+pub const FINGERPRINT_PROMETHEUS_METRIC_LABEL_NAME: &str = "fingerprint";
+/// SSH服务器信息的指标名称
+pub const SSH_INFO_PROMETHEUS_METRIC_NAME: &str = "pong_ssh_info";
+/// SSH服务器信息的指标描述
+pub const SSH_INFO_PROMETHEUS_METRIC_DESC: &str =
+    "software version from the identification string, host key algorithm and sha256 fingerprint of the ssh server";
+/// SSH主机密钥指纹是否与配置相符的指标名称
+pub const SSH_FINGERPRINT_MATCH_PROMETHEUS_METRIC_NAME: &str = "pong_ssh_fingerprint_match";
+/// SSH主机密钥指纹是否与配置相符的指标描述
+pub const SSH_FINGERPRINT_MATCH_PROMETHEUS_METRIC_DESC: &str =
+    "whether the host key fingerprint matches the pinned one (1 match, 0 mismatch), only when pinned";
+/// SSH探测各阶段耗时的指标名称
+pub const SSH_DURATION_PROMETHEUS_METRIC_NAME: &str = "pong_ssh_duration_ms";
+/// SSH探测各阶段耗时的指标描述
+pub const SSH_DURATION_PROMETHEUS_METRIC_DESC: &str =
+    "duration of the ssh probe by phase (connect / banner / kex) in milliseconds";
+
+/// MQTT探测各阶段耗时的指标名称
+pub const MQTT_DURATION_PROMETHEUS_METRIC_NAME: &str = "pong_mqtt_duration_ms";
+/// MQTT探测各阶段耗时的指标描述
+pub const MQTT_DURATION_PROMETHEUS_METRIC_DESC: &str =
+    "duration of the mqtt probe by phase (connect / handshake / subscribe / round_trip) in milliseconds, handshake includes the tls handshake";
+
+/// LDAP探测各阶段耗时的指标名称
+pub const LDAP_DURATION_PROMETHEUS_METRIC_NAME: &str = "pong_ldap_duration_ms";
+/// LDAP探测各阶段耗时的指标描述
+pub const LDAP_DURATION_PROMETHEUS_METRIC_DESC: &str =
+    "duration of the ldap probe by phase (connect / bind / search) in milliseconds, bind includes the tls handshake";
+/// LDAP搜索返回条目数的指标名称
+pub const LDAP_ENTRIES_PROMETHEUS_METRIC_NAME: &str = "pong_ldap_entries";
+/// LDAP搜索返回条目数的指标描述
+pub const LDAP_ENTRIES_PROMETHEUS_METRIC_DESC: &str =
+    "number of entries returned by the ldap search";
+
+/// 版本的标签名
+pub const VERSION_PROMETHEUS_METRIC_LABEL_NAME: &str = "version";
+/// 密码套件的标签名
+pub const CIPHER_PROMETHEUS_METRIC_LABEL_NAME: &str = "cipher";
+/// 证书主体的标签名
+pub const SUBJECT_PROMETHEUS_METRIC_LABEL_NAME: &str = "subject";
+/// 证书颁发者的标签名
+pub const ISSUER_PROMETHEUS_METRIC_LABEL_NAME: &str = "issuer";
+/// TLS握手耗时的指标名称
+pub const TLS_HANDSHAKE_PROMETHEUS_METRIC_NAME: &str = "pong_tls_handshake_ms";
+/// TLS握手耗时的指标描述
+pub const TLS_HANDSHAKE_PROMETHEUS_METRIC_DESC: &str =
+    "tls handshake time in milliseconds, excluding tcp connect";
+/// TLS协商结果的指标名称
+pub const TLS_INFO_PROMETHEUS_METRIC_NAME: &str = "pong_tls_info";
+/// TLS协商结果的指标描述
+pub const TLS_INFO_PROMETHEUS_METRIC_DESC: &str = "negotiated tls version and cipher suite";
+/// TLS证书链是否有效的指标名称
+pub const TLS_CHAIN_VALID_PROMETHEUS_METRIC_NAME: &str = "pong_tls_chain_valid";
+/// TLS证书链是否有效的指标描述
+pub const TLS_CHAIN_VALID_PROMETHEUS_METRIC_DESC: &str =
+    "whether the certificate chain is valid (1 valid, 0 invalid)";
+/// TLS证书信息的指标名称
+pub const TLS_CERT_INFO_PROMETHEUS_METRIC_NAME: &str = "pong_tls_cert_info";
+/// TLS证书信息的指标描述
+pub const TLS_CERT_INFO_PROMETHEUS_METRIC_DESC: &str = "subject and issuer of the leaf certificate";
+/// TLS证书剩余有效期的指标名称
+pub const TLS_CERT_EXPIRY_PROMETHEUS_METRIC_NAME: &str = "pong_tls_cert_expiry_seconds";
+/// TLS证书剩余有效期的指标描述
+pub const TLS_CERT_EXPIRY_PROMETHEUS_METRIC_DESC: &str =
+    "seconds until the leaf certificate expires, negative if already expired";
+
+/// DNS响应码的指标名称
+pub const DNS_RCODE_PROMETHEUS_METRIC_NAME: &str = "pong_dns_rcode";
+/// DNS响应码的指标描述
+pub const DNS_RCODE_PROMETHEUS_METRIC_DESC: &str =
+    "dns response code (0 NOERROR, 2 SERVFAIL, 3 NXDOMAIN, 5 REFUSED, ...)";
+/// DNS应答数的指标名称
+pub const DNS_ANSWERS_PROMETHEUS_METRIC_NAME: &str = "pong_dns_answers";
+/// DNS应答数的指标描述
+pub const DNS_ANSWERS_PROMETHEUS_METRIC_DESC: &str = "number of answer records of the queried type";
+
+/// 路径的标签名
+pub const PATH_PROMETHEUS_METRIC_LABEL_NAME: &str = "path";
+/// 多路径路由追踪发现的路径数指标名称
+pub const TRACEROUTE_PATHS_PROMETHEUS_METRIC_NAME: &str = "pong_traceroute_paths";
+/// 多路径路由追踪发现的路径数指标描述
+pub const TRACEROUTE_PATHS_PROMETHEUS_METRIC_DESC: &str =
+    "number of distinct paths discovered by multipath traceroute";
+/// 路径的流数指标名称
+pub const TRACEROUTE_PATH_FLOWS_PROMETHEUS_METRIC_NAME: &str = "pong_traceroute_path_flows";
+/// 路径的流数指标描述
+pub const TRACEROUTE_PATH_FLOWS_PROMETHEUS_METRIC_DESC: &str =
+    "number of flows hashed onto the path";
+/// 路径的跳数指标名称
+pub const TRACEROUTE_PATH_HOPS_PROMETHEUS_METRIC_NAME: &str = "pong_traceroute_path_hops";
+/// 路径的跳数指标描述
+pub const TRACEROUTE_PATH_HOPS_PROMETHEUS_METRIC_DESC: &str = "number of hops of the path";
+/// 路径中每一跳的丢包率指标名称
+pub const TRACEROUTE_PATH_HOP_LOSS_PROMETHEUS_METRIC_NAME: &str =
+    "pong_traceroute_path_hop_loss_ratio";
+/// 路径中每一跳的丢包率指标描述
+pub const TRACEROUTE_PATH_HOP_LOSS_PROMETHEUS_METRIC_DESC: &str =
+    "packet loss ratio of the hop in the path (0 ~ 1)";
+/// 路径中每一跳的RTT指标名称
+pub const TRACEROUTE_PATH_HOP_RTT_PROMETHEUS_METRIC_NAME: &str = "pong_traceroute_path_hop_rtt_ms";
+/// 路径中每一跳的RTT指标描述
+pub const TRACEROUTE_PATH_HOP_RTT_PROMETHEUS_METRIC_DESC: &str =
+    "round trip time of the hop in the path in milliseconds";
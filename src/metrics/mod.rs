@@ -1,2 +1,3 @@
+pub mod metrics_cst;
+pub mod probe_metric;
 pub mod prometheus_metrics;
-pub mod metrics_cst;
\ No newline at end of file
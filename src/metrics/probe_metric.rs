@@ -0,0 +1,45 @@
+use serde::Serialize;
+
+/// 探测指标
+///
+/// 执行器在一次探测中产生的附加指标，会以 `host` 标签加上自身的标签导出到 Prometheus。
+/// 同名指标的标签名必须保持一致。
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ProbeMetric {
+    /// 指标名称
+    pub name: &'static str,
+    /// 指标描述
+    pub desc: &'static str,
+    /// 标签(不含host)
+    pub labels: Vec<(&'static str, String)>,
+    /// 指标值
+    pub value: f64,
+}
+
+impl ProbeMetric {
+    /// 构造函数
+    pub fn new(name: &'static str, desc: &'static str, value: f64) -> Self {
+        Self {
+            name,
+            desc,
+            labels: vec![],
+            value,
+        }
+    }
+
+    /// 添加标签
+    pub fn with_label(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.labels.push((name, value.to_string()));
+        self
+    }
+
+    /// 获取标签名列表
+    pub fn label_names(&self) -> Vec<&'static str> {
+        self.labels.iter().map(|(name, _)| *name).collect()
+    }
+
+    /// 获取标签值列表
+    pub fn label_values(&self) -> Vec<String> {
+        self.labels.iter().map(|(_, value)| value.clone()).collect()
+    }
+}
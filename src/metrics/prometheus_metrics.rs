@@ -2,12 +2,22 @@ use crate::metrics::metrics_cst::{
     HOST_ACTIVITY_PROMETHEUS_METRIC_DESC, HOST_ACTIVITY_PROMETHEUS_METRIC_LABEL_NAME,
     HOST_ACTIVITY_PROMETHEUS_METRIC_NAME,
 };
+use crate::metrics::probe_metric::ProbeMetric;
 use prometheus::proto::MetricFamily;
-use prometheus::{opts, IntGaugeVec, Registry};
+use prometheus::{opts, GaugeVec, IntGaugeVec, Registry};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 指标序列，由指标名称和标签值组成
+type Series = (&'static str, Vec<String>);
 
 pub struct PrometheusMetrics {
     registry: Registry,
     host_activity_gauges: IntGaugeVec,
+    /// 探测指标，首次上报时按指标名称注册
+    probe_gauges: Mutex<HashMap<&'static str, GaugeVec>>,
+    /// 每个主机上一次上报的探测指标序列，用于清除已经消失的序列
+    host_series: Mutex<HashMap<String, Vec<Series>>>,
 }
 
 impl PrometheusMetrics {
@@ -32,6 +42,8 @@ impl PrometheusMetrics {
         Self {
             registry,
             host_activity_gauges,
+            probe_gauges: Mutex::new(HashMap::new()),
+            host_series: Mutex::new(HashMap::new()),
         }
     }
 
@@ -45,6 +57,38 @@ impl PrometheusMetrics {
             .set(elapsed);
     }
 
+    /// 更新探测指标
+    /// # 参数
+    /// `host` - 主机名
+    /// `metrics` - 本次探测的附加指标，上一次上报过而本次没有的序列会被清除
+    pub fn update_probe_metrics(&self, host: &str, metrics: &[ProbeMetric]) {
+        let mut probe_gauges = self.probe_gauges.lock().unwrap();
+        let mut series = Vec::with_capacity(metrics.len());
+        for metric in metrics {
+            let gauges = probe_gauges.entry(metric.name).or_insert_with(|| {
+                let mut label_names = vec![HOST_ACTIVITY_PROMETHEUS_METRIC_LABEL_NAME];
+                label_names.extend(metric.label_names());
+                let gauges = GaugeVec::new(opts!(metric.name, metric.desc), &label_names).unwrap();
+                self.registry.register(Box::new(gauges.clone())).unwrap();
+                gauges
+            });
+            let mut label_values = vec![host.to_string()];
+            label_values.extend(metric.label_values());
+            gauges.with_label_values(&label_values).set(metric.value);
+            series.push((metric.name, label_values));
+        }
+
+        let mut host_series = self.host_series.lock().unwrap();
+        if let Some(old_series) = host_series.insert(host.to_string(), series) {
+            let new_series = &host_series[host];
+            for (name, label_values) in old_series.iter().filter(|s| !new_series.contains(s)) {
+                if let Some(gauges) = probe_gauges.get(name) {
+                    let _ = gauges.remove_label_values(label_values);
+                }
+            }
+        }
+    }
+
     /// 获取指标集
     pub fn gather(&self) -> Vec<MetricFamily> {
         self.registry.gather()
@@ -1,3 +1,4 @@
+use std::net::IpAddr;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -6,8 +7,32 @@ pub enum PingError {
     Io(#[from] std::io::Error),
     #[error("Timeout")]
     Timeout,
+    #[error("Destination unreachable (code {code}) reported by {router}")]
+    DestinationUnreachable { router: IpAddr, code: u8 },
+    #[error("Time exceeded reported by {router}")]
+    TimeExceeded { router: IpAddr },
+    #[error("Packet too big (mtu {mtu}) reported by {router}")]
+    PacketTooBig { router: IpAddr, mtu: u16 },
+    #[error("Connection refused")]
+    ConnectionRefused,
+    #[error("Unreachable: {0}")]
+    Unreachable(std::io::Error),
+    #[error("TLS error: {0}")]
+    Tls(String),
     #[error("Invalid reply: {0}")]
     InvalidReply(String),
     #[error("Request error: {0}")]
     RequestError(#[from] reqwest::Error),
+    #[error("Resolution failed: {0}")]
+    Resolve(String),
+    #[error("Address {addr} failed: {source}")]
+    AddressFailed {
+        addr: IpAddr,
+        source: Box<PingError>,
+    },
+    #[error("Step {step} failed: {source}")]
+    StepFailed {
+        step: String,
+        source: Box<PingError>,
+    },
 }
@@ -0,0 +1,2 @@
+pub mod resolve_settings;
+pub mod target_resolver;
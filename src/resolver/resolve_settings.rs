@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use wheel_rs::serde::duration_option_serde;
+
+/// 解析目标主机名的配置
+///
+/// 默认每次执行任务都重新解析，只探测解析出的第一个地址。
+/// 解析失败时本次探测失败，不会影响下一次执行。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct ResolveSettings {
+    /// 解析结果的缓存时间，未配置时每次执行都重新解析
+    #[serde(with = "duration_option_serde", default)]
+    pub ttl: Option<Duration>,
+    /// 探测的地址族
+    #[serde(default)]
+    pub ip_family: IpFamily,
+    /// 是否探测解析出的所有地址，每个地址的指标以 `addr` 标签区分，任一地址失败时探测失败
+    #[serde(default)]
+    pub all_addresses: bool,
+}
+
+/// 地址族
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    /// 只探测IPv4地址
+    V4,
+    /// 只探测IPv6地址
+    V6,
+    /// IPv4和IPv6地址都探测，按系统解析的顺序排列
+    #[default]
+    Both,
+}
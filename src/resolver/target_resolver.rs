@@ -0,0 +1,200 @@
+use crate::executor::ExecReport;
+use crate::metrics::metrics_cst::{
+    ADDR_PROMETHEUS_METRIC_LABEL_NAME, TARGET_ADDRESSES_PROMETHEUS_METRIC_DESC,
+    TARGET_ADDRESSES_PROMETHEUS_METRIC_NAME, TARGET_RESOLVED_PROMETHEUS_METRIC_DESC,
+    TARGET_RESOLVED_PROMETHEUS_METRIC_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::{IpFamily, ResolveSettings};
+use log::{debug, trace};
+use std::future::Future;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+/// 解析的时间及解析出的地址
+type Resolved = (Instant, Vec<IpAddr>);
+
+/// 目标主机名的解析器
+///
+/// 按配置的缓存时间重新解析，并按地址族过滤解析结果。
+#[derive(Debug, Clone)]
+pub struct TargetResolver {
+    /// 主机名或IP地址
+    host: String,
+    settings: ResolveSettings,
+    /// 缓存的解析结果及解析的时间
+    cache: Arc<Mutex<Option<Resolved>>>,
+}
+
+impl TargetResolver {
+    /// 构造函数
+    /// # 参数
+    /// * `host` - 主机名或IP地址，IPv6地址可以带方括号
+    /// * `settings` - 解析的配置
+    pub fn new(host: &str, settings: ResolveSettings) -> Self {
+        Self {
+            host: host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+            settings,
+            cache: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 解析要探测的地址
+    ///
+    /// # 返回值
+    /// 解析失败或没有配置的地址族的地址时返回 `Resolve` 错误
+    pub async fn resolve(&self) -> Result<Vec<IpAddr>, PingError> {
+        // IP地址不需要解析
+        if let Ok(ip_addr) = IpAddr::from_str(&self.host) {
+            return self.select(vec![ip_addr]);
+        }
+        if let Some(ttl) = self.settings.ttl
+            && let Some((resolved_at, addrs)) = self.cache.lock().unwrap().as_ref()
+            && resolved_at.elapsed() < ttl
+        {
+            return self.select(addrs.clone());
+        }
+
+        trace!("resolve {} ....", self.host);
+        let mut addrs = vec![];
+        for socket_addr in tokio::net::lookup_host((self.host.as_str(), 0))
+            .await
+            .map_err(|e| PingError::Resolve(format!("{}: {}", self.host, e)))?
+        {
+            if !addrs.contains(&socket_addr.ip()) {
+                addrs.push(socket_addr.ip());
+            }
+        }
+        debug!("{} 解析为 {:?}", self.host, addrs);
+        *self.cache.lock().unwrap() = Some((Instant::now(), addrs.clone()));
+        self.select(addrs)
+    }
+
+    /// 按地址族过滤，未配置探测所有地址时只取第一个
+    fn select(&self, addrs: Vec<IpAddr>) -> Result<Vec<IpAddr>, PingError> {
+        let mut addrs: Vec<IpAddr> = addrs
+            .into_iter()
+            .filter(|addr| match self.settings.ip_family {
+                IpFamily::V4 => addr.is_ipv4(),
+                IpFamily::V6 => addr.is_ipv6(),
+                IpFamily::Both => true,
+            })
+            .collect();
+        if addrs.is_empty() {
+            return Err(PingError::Resolve(format!(
+                "{} 没有 {:?} 的地址",
+                self.host, self.settings.ip_family
+            )));
+        }
+        if !self.settings.all_addresses {
+            addrs.truncate(1);
+        }
+        Ok(addrs)
+    }
+
+    /// 解析目标后并发探测每个地址，合并成一个执行报告
+    ///
+    /// 每个地址的指标加上 `addr` 标签，任一地址失败时报告第一个失败的原因，
+    /// 耗时取所有地址中最慢的。解析失败作为单独的探测结果报告。
+    ///
+    /// # 参数
+    /// * `probe` - 探测一个地址
+    pub async fn exec<F, Fut>(&self, probe: F) -> Result<ExecReport, PingError>
+    where
+        F: Fn(IpAddr) -> Fut,
+        Fut: Future<Output = Result<ExecReport, PingError>> + Send + 'static,
+    {
+        let addrs = match self.resolve().await {
+            Ok(addrs) => addrs,
+            Err(e) => {
+                return Ok(ExecReport {
+                    elapsed: None,
+                    error: Some(e),
+                    metrics: resolved_metrics(None),
+                });
+            }
+        };
+        let mut metrics = resolved_metrics(Some(addrs.len()));
+
+        let mut join_set = JoinSet::new();
+        for (index, addr) in addrs.iter().enumerate() {
+            let future = probe(*addr);
+            join_set.spawn(async move { (index, future.await) });
+        }
+        let mut results = join_set.join_all().await;
+        results.sort_by_key(|(index, _)| *index);
+
+        let fan_out = addrs.len() > 1;
+        let mut elapsed = Some(Duration::ZERO);
+        let mut error = None;
+        for ((_, result), addr) in results.into_iter().zip(addrs) {
+            let report = result.unwrap_or_else(|e| ExecReport {
+                error: Some(e),
+                ..Default::default()
+            });
+            metrics.extend(
+                report
+                    .metrics
+                    .into_iter()
+                    .map(|metric| metric.with_label(ADDR_PROMETHEUS_METRIC_LABEL_NAME, addr)),
+            );
+            // 未给出耗时的以执行任务的总耗时代替
+            elapsed = elapsed.zip(report.elapsed).map(|(a, b)| a.max(b));
+            if error.is_none()
+                && let Some(e) = report.error
+            {
+                error = Some(if fan_out {
+                    PingError::AddressFailed {
+                        addr,
+                        source: Box::new(e),
+                    }
+                } else {
+                    e
+                });
+            }
+        }
+        Ok(ExecReport {
+            elapsed,
+            error,
+            metrics,
+        })
+    }
+}
+
+/// 解析结果的探测指标，解析失败时地址数为None
+fn resolved_metrics(addresses: Option<usize>) -> Vec<ProbeMetric> {
+    vec![
+        ProbeMetric::new(
+            TARGET_RESOLVED_PROMETHEUS_METRIC_NAME,
+            TARGET_RESOLVED_PROMETHEUS_METRIC_DESC,
+            if addresses.is_some() { 1.0 } else { 0.0 },
+        ),
+        ProbeMetric::new(
+            TARGET_ADDRESSES_PROMETHEUS_METRIC_NAME,
+            TARGET_ADDRESSES_PROMETHEUS_METRIC_DESC,
+            addresses.unwrap_or_default() as f64,
+        ),
+    ]
+}
+
+/// 将 `主机名:端口号` 拆分成主机名和端口号，IPv6地址须带方括号，如 `[::1]:80`
+///
+/// # 返回值
+/// 缺少端口号或端口号错误时返回错误信息
+pub fn split_host_port(host_port: &str) -> Result<(String, u16), String> {
+    let (host, port) = host_port
+        .rsplit_once(':')
+        .ok_or_else(|| format!("缺少端口号: {}", host_port))?;
+    let port = port
+        .parse()
+        .map_err(|e| format!("错误的端口号({}): {}", host_port, e))?;
+    Ok((host.to_string(), port))
+}
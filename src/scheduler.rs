@@ -1,9 +1,25 @@
-use crate::executor::Executor;
+use crate::executor::{ExecReport, Executor};
 use crate::settings::pong_settings::{TaskGroupSettings, TaskType};
 use crate::targets::TargetStatus;
+use crate::task::dns::dns_executor::DnsExecutor;
+use crate::task::grpc::grpc_executor::GrpcExecutor;
 use crate::task::http::http_executor::HttpExecutor;
+use crate::task::http_flow::http_flow_executor::HttpFlowExecutor;
 use crate::task::icmp::icmp_executor::IcmpExecutor;
+use crate::task::ldap::ldap_executor::LdapExecutor;
+use crate::task::mail::mail_executor::MailExecutor;
+use crate::task::mail::mail_ping::MailProtocol;
+use crate::task::mqtt::mqtt_executor::MqttExecutor;
+use crate::task::mysql::mysql_executor::MysqlExecutor;
+use crate::task::ntp::ntp_executor::NtpExecutor;
+use crate::task::postgres::postgres_executor::PostgresExecutor;
+use crate::task::redis::redis_executor::RedisExecutor;
+use crate::task::ssh::ssh_executor::SshExecutor;
 use crate::task::tcp::tcp_executor::TcpExecutor;
+use crate::task::tls::tls_executor::TlsExecutor;
+use crate::task::traceroute::traceroute_executor::TracerouteExecutor;
+use crate::task::udp::udp_executor::UdpExecutor;
+use crate::task::websocket::websocket_executor::WebsocketExecutor;
 use log::{debug, error, info, trace};
 use std::fmt;
 use std::fmt::{Debug, Formatter};
@@ -14,7 +30,8 @@ use tokio::time::{sleep, Instant};
 /// 代表一个可执行的任务单元
 #[derive(Clone)]
 struct Task {
-    /// 任务类型，目前支持 ICMP / TCP /HTTP
+    /// 任务类型，目前支持 ICMP / TCP / HTTP / TRACEROUTE / TLS / HTTP_FLOW / DNS / UDP / NTP / GRPC / WEBSOCKET /
+    /// POSTGRES / MYSQL / REDIS / SMTP / IMAP / POP3 / SSH / MQTT / LDAP
     task_type: TaskType,
     /// 目标地址，可以是 IP 地址或域名
     target: String,
@@ -67,14 +84,122 @@ impl Scheduler {
                             TaskType::ICMP => Arc::new(IcmpExecutor::new(
                                 task.target.clone(),
                                 task_group.timeout.unwrap(),
+                                task.icmp.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
                             )),
                             TaskType::TCP => Arc::new(TcpExecutor::new(
                                 task.target.clone(),
                                 task_group.timeout.unwrap(),
+                                task.tcp.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
                             )),
                             TaskType::HTTP => Arc::new(HttpExecutor::new(
                                 task.target.clone(),
                                 task_group.timeout.unwrap(),
+                                task.http.clone().unwrap_or_default(),
+                            )),
+                            TaskType::TRACEROUTE => Arc::new(TracerouteExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.traceroute.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::TLS => Arc::new(TlsExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.tls.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::HTTP_FLOW => Arc::new(HttpFlowExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.http_flow.clone().unwrap_or_default(),
+                            )),
+                            TaskType::DNS => Arc::new(DnsExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.dns.clone().unwrap_or_default(),
+                            )),
+                            TaskType::UDP => Arc::new(UdpExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.udp.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::NTP => Arc::new(NtpExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.ntp.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::GRPC => Arc::new(GrpcExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.grpc.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::WEBSOCKET => Arc::new(WebsocketExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.websocket.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::POSTGRES => Arc::new(PostgresExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.postgres.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::MYSQL => Arc::new(MysqlExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.mysql.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::REDIS => Arc::new(RedisExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.redis.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::SMTP => Arc::new(MailExecutor::new(
+                                MailProtocol::Smtp,
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.smtp.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::IMAP => Arc::new(MailExecutor::new(
+                                MailProtocol::Imap,
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.imap.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::POP3 => Arc::new(MailExecutor::new(
+                                MailProtocol::Pop3,
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.pop3.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::SSH => Arc::new(SshExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.ssh.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::MQTT => Arc::new(MqttExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.mqtt.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
+                            )),
+                            TaskType::LDAP => Arc::new(LdapExecutor::new(
+                                task.target.clone(),
+                                task_group.timeout.unwrap(),
+                                task.ldap.clone().unwrap_or_default(),
+                                task.resolve.clone().unwrap_or_default(),
                             )),
                         },
                     })
@@ -84,15 +209,17 @@ impl Scheduler {
             let tasks_clone = Arc::clone(&tasks); // 克隆 Arc 以供异步任务使用
             tokio::spawn(async move {
                 loop {
-                    // let mut handles = vec![];
+                    // 组内的任务并发执行，等全部完成后再进入下一轮
+                    let mut handles = vec![];
                     for task in tasks_clone.iter() {
-                        // handles.push(tokio::spawn(Self::exec_task(task.clone())));
-                        Self::exec_task(task.clone()).await;
+                        handles.push(tokio::spawn(Self::exec_task(task.clone())));
                     }
 
-                    // for handle in handles {
-                    //     handle.await.expect("任务执行失败");
-                    // }
+                    for handle in handles {
+                        if let Err(e) = handle.await {
+                            error!("任务执行失败: {}", e);
+                        }
+                    }
 
                     sleep(duration).await;
                 }
@@ -105,21 +232,36 @@ impl Scheduler {
         let task_desc = format!("{:?}", task);
         let executor_name = task.executor.get_name().clone();
         trace!("开始执行任务: {}:{}", executor_name, task_desc);
-        let elapsed = match task.executor.exec().await {
-            Ok(_) => {
-                let elapsed = start_time.elapsed().as_millis() as i64;
+        let (elapsed, metrics) = match task.executor.exec().await {
+            Ok(ExecReport {
+                elapsed,
+                error: None,
+                metrics,
+            }) => {
+                let elapsed = elapsed.unwrap_or_else(|| start_time.elapsed()).as_millis() as i64;
                 info!(
                     "Ping {} --> {} --> Pong in {} ms",
                     executor_name, task.target, elapsed
                 );
-                elapsed
+                (elapsed, metrics)
+            }
+            Ok(ExecReport {
+                error: Some(e),
+                metrics,
+                ..
+            }) => {
+                error!(
+                    "Ping {} --> {} --> Failed {}",
+                    executor_name, task.target, e
+                );
+                (-1, metrics)
             }
             Err(e) => {
                 error!(
                     "Ping {} --> {} --> Failed {}",
                     executor_name, task.target, e
                 );
-                -1
+                (-1, vec![])
             }
         };
 
@@ -127,6 +269,7 @@ impl Scheduler {
             task_type: task.task_type.clone(),
             target: task.target.clone(),
             elapsed,
+            metrics,
         };
         trace!("更新目标状态: {:?}", target_status);
         task.target_status_tx.send(target_status).unwrap();
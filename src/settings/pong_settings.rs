@@ -1,3 +1,22 @@
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::task::dns::dns_settings::DnsSettings;
+use crate::task::grpc::grpc_settings::GrpcSettings;
+use crate::task::http::http_settings::HttpSettings;
+use crate::task::http_flow::http_flow_settings::HttpFlowSettings;
+use crate::task::icmp::icmp_settings::IcmpSettings;
+use crate::task::ldap::ldap_settings::LdapSettings;
+use crate::task::mail::mail_settings::MailSettings;
+use crate::task::mqtt::mqtt_settings::MqttSettings;
+use crate::task::mysql::mysql_settings::MysqlSettings;
+use crate::task::ntp::ntp_settings::NtpSettings;
+use crate::task::postgres::postgres_settings::PostgresSettings;
+use crate::task::redis::redis_settings::RedisSettings;
+use crate::task::ssh::ssh_settings::SshSettings;
+use crate::task::tcp::tcp_settings::TcpSettings;
+use crate::task::tls::tls_settings::TlsSettings;
+use crate::task::traceroute::traceroute_settings::TracerouteSettings;
+use crate::task::udp::udp_settings::UdpSettings;
+use crate::task::websocket::websocket_settings::WebsocketSettings;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use strum_macros::Display;
@@ -35,11 +54,26 @@ fn timeout_default() -> Option<Duration> {
 
 /// 任务类型枚举，定义了支持的任务类型
 ///
-/// 该枚举包含了三种任务类型：
+/// 该枚举包含了以下任务类型：
 /// - ICMP: 用于网络连通性测试
 /// - TCP: 用于TCP端口连通性测试
 /// - HTTP: 用于HTTP服务可用性测试
-#[derive(Debug, Serialize, Deserialize, Display, Clone)]
+/// - TRACEROUTE: 用于追踪到目标的路径，定位出问题的那一跳
+/// - TLS: 用于TLS握手及证书有效期检查
+/// - HTTP_FLOW: 用于多步骤的HTTP事务测试，如登录后调用接口再登出
+/// - DNS: 用于DNS服务器解析测试
+/// - UDP: 用于UDP服务的请求及回复测试
+/// - NTP: 用于NTP服务器的时钟偏移及层级检查
+/// - GRPC: 用于gRPC服务的健康检查
+/// - WEBSOCKET: 用于WebSocket服务的升级握手及消息收发测试
+/// - POSTGRES: 用于PostgreSQL的登录及查询测试
+/// - MYSQL: 用于MySQL的登录及查询测试
+/// - REDIS: 用于Redis的认证及命令测试
+/// - SMTP / IMAP / POP3: 用于邮件服务的欢迎信息、STARTTLS及认证测试
+/// - SSH: 用于SSH服务的版本标识及主机密钥指纹检查
+/// - MQTT: 用于MQTT服务器的发布订阅往返测试
+/// - LDAP: 用于目录服务的绑定及搜索测试
+#[derive(Debug, Serialize, Deserialize, Display, Clone, PartialEq)]
 pub enum TaskType {
     /// icmp
     #[serde(rename = "icmp")]
@@ -50,6 +84,58 @@ pub enum TaskType {
     /// http
     #[serde(rename = "http")]
     HTTP,
+    /// traceroute
+    #[serde(rename = "traceroute")]
+    TRACEROUTE,
+    /// tls
+    #[serde(rename = "tls")]
+    TLS,
+    /// http-flow
+    #[serde(rename = "http-flow")]
+    #[allow(non_camel_case_types)]
+    HTTP_FLOW,
+    /// dns
+    #[serde(rename = "dns")]
+    DNS,
+    /// udp
+    #[serde(rename = "udp")]
+    UDP,
+    /// ntp
+    #[serde(rename = "ntp")]
+    NTP,
+    /// grpc
+    #[serde(rename = "grpc")]
+    GRPC,
+    /// websocket
+    #[serde(rename = "websocket")]
+    WEBSOCKET,
+    /// postgres
+    #[serde(rename = "postgres")]
+    POSTGRES,
+    /// mysql
+    #[serde(rename = "mysql")]
+    MYSQL,
+    /// redis
+    #[serde(rename = "redis")]
+    REDIS,
+    /// smtp
+    #[serde(rename = "smtp")]
+    SMTP,
+    /// imap
+    #[serde(rename = "imap")]
+    IMAP,
+    /// pop3
+    #[serde(rename = "pop3")]
+    POP3,
+    /// ssh
+    #[serde(rename = "ssh")]
+    SSH,
+    /// mqtt
+    #[serde(rename = "mqtt")]
+    MQTT,
+    /// ldap
+    #[serde(rename = "ldap")]
+    LDAP,
 }
 
 /// 任务属性
@@ -60,4 +146,46 @@ pub struct TaskSettings {
     pub task_type: TaskType,
     /// 目标
     pub target: String,
+    /// 解析目标主机名的配置，仅在任务类型为icmp、tcp、traceroute、tls、udp、ntp、grpc、websocket、postgres、mysql、redis、smtp、imap、pop3、ssh、mqtt和ldap时有效
+    pub resolve: Option<ResolveSettings>,
+    /// ICMP任务的配置，仅在任务类型为icmp时有效
+    pub icmp: Option<IcmpSettings>,
+    /// HTTP任务的配置，仅在任务类型为http时有效
+    pub http: Option<HttpSettings>,
+    /// TCP任务的配置，仅在任务类型为tcp时有效
+    pub tcp: Option<TcpSettings>,
+    /// 路由追踪任务的配置，仅在任务类型为traceroute时有效
+    pub traceroute: Option<TracerouteSettings>,
+    /// TLS任务的配置，仅在任务类型为tls时有效
+    pub tls: Option<TlsSettings>,
+    /// HTTP事务任务的配置，仅在任务类型为http-flow时有效
+    pub http_flow: Option<HttpFlowSettings>,
+    /// DNS任务的配置，仅在任务类型为dns时有效
+    pub dns: Option<DnsSettings>,
+    /// UDP任务的配置，仅在任务类型为udp时有效
+    pub udp: Option<UdpSettings>,
+    /// NTP任务的配置，仅在任务类型为ntp时有效
+    pub ntp: Option<NtpSettings>,
+    /// gRPC任务的配置，仅在任务类型为grpc时有效
+    pub grpc: Option<GrpcSettings>,
+    /// WebSocket任务的配置，仅在任务类型为websocket时有效
+    pub websocket: Option<WebsocketSettings>,
+    /// PostgreSQL任务的配置，仅在任务类型为postgres时有效
+    pub postgres: Option<PostgresSettings>,
+    /// MySQL任务的配置，仅在任务类型为mysql时有效
+    pub mysql: Option<MysqlSettings>,
+    /// Redis任务的配置，仅在任务类型为redis时有效
+    pub redis: Option<RedisSettings>,
+    /// SMTP任务的配置，仅在任务类型为smtp时有效
+    pub smtp: Option<MailSettings>,
+    /// IMAP任务的配置，仅在任务类型为imap时有效
+    pub imap: Option<MailSettings>,
+    /// POP3任务的配置，仅在任务类型为pop3时有效
+    pub pop3: Option<MailSettings>,
+    /// SSH任务的配置，仅在任务类型为ssh时有效
+    pub ssh: Option<SshSettings>,
+    /// MQTT任务的配置，仅在任务类型为mqtt时有效
+    pub mqtt: Option<MqttSettings>,
+    /// LDAP任务的配置，仅在任务类型为ldap时有效
+    pub ldap: Option<LdapSettings>,
 }
@@ -1,4 +1,21 @@
-use crate::settings::pong_settings::PongSettings;
+use crate::resolver::target_resolver::split_host_port;
+use crate::settings::pong_settings::{PongSettings, TaskType};
+use crate::task::database::db_probe::{expect_regex, split_target};
+use crate::task::dns::dns_ping::DnsPing;
+use crate::task::grpc::grpc_ping::GrpcPing;
+use crate::task::http::http_assertions::HttpAssertions;
+use crate::task::http::http_ping::HttpPing;
+use crate::task::http_flow::http_flow_ping::HttpFlowPing;
+use crate::task::ldap::ldap_ping::LdapPing;
+use crate::task::mail::mail_ping::{MailPing, MailProtocol};
+use crate::task::mqtt::mqtt_ping::MqttPing;
+use crate::task::mysql::mysql_ping::MysqlPing;
+use crate::task::postgres::postgres_ping::PostgresPing;
+use crate::task::redis::redis_ping::RedisPing;
+use crate::task::ssh::ssh_host_key::parse_fingerprint;
+use crate::task::ssh::ssh_ping::SshPing;
+use crate::task::tls::tls_ping::TlsPing;
+use crate::task::websocket::websocket_ping::WebsocketPing;
 use log::info;
 use robotech::settings::get_settings;
 use robotech::web_server::WebServerSettings;
@@ -52,6 +69,140 @@ pub fn init_settings(path: Option<String>, port: Option<u16>) {
         if task_group.tasks.is_empty() {
             panic!("任务组尚未配置任务");
         }
+        for task in task_group.tasks.iter() {
+            if let Some(icmp) = &task.icmp
+                && icmp.count == 0
+            {
+                panic!("ICMP任务的count不能为0: {}", task.target);
+            }
+            if let Some(icmp) = &task.icmp
+                && icmp.spacing.is_none()
+            {
+                panic!("ICMP任务的spacing不能为空: {}", task.target);
+            }
+            if let Some(traceroute) = &task.traceroute
+                && (traceroute.max_hops == 0 || traceroute.count == 0 || traceroute.flows == 0)
+            {
+                panic!(
+                    "路由追踪任务的max-hops、count和flows不能为0: {}",
+                    task.target
+                );
+            }
+            if matches!(
+                task.task_type,
+                TaskType::TCP | TaskType::TLS | TaskType::UDP | TaskType::GRPC
+            ) && let Err(e) = split_host_port(&task.target)
+            {
+                panic!("{}任务的目标错误: {}", task.task_type, e);
+            }
+            if let Some(tcp) = &task.tcp
+                && let Err(e) = tcp.send_bytes().and(tcp.expect_regex())
+            {
+                panic!("TCP任务的配置错误({}): {}", task.target, e);
+            }
+            if let Some(udp) = &task.udp
+                && let Err(e) = udp.send_bytes().and(udp.expect_regex())
+            {
+                panic!("UDP任务的配置错误({}): {}", task.target, e);
+            }
+            if let Some(ntp) = &task.ntp
+                && !(1..=4).contains(&ntp.version)
+            {
+                panic!("NTP任务的version必须为1~4: {}", task.target);
+            }
+            if task.task_type == TaskType::GRPC
+                && let Ok((host, _)) = split_host_port(&task.target)
+                && let Err(e) =
+                    GrpcPing::new(&host, &task.target, &task.grpc.clone().unwrap_or_default())
+            {
+                panic!("gRPC任务的配置错误({}): {}", task.target, e);
+            }
+            if task.task_type == TaskType::TLS
+                && let Ok((host, _)) = split_host_port(&task.target)
+                && let Err(e) = TlsPing::new(&host, &task.tls.clone().unwrap_or_default())
+            {
+                panic!("TLS任务的配置错误({}): {}", task.target, e);
+            }
+            if task.task_type == TaskType::WEBSOCKET
+                && let Err(e) =
+                    WebsocketPing::new(&task.target, &task.websocket.clone().unwrap_or_default())
+            {
+                panic!("WebSocket任务的配置错误({}): {}", task.target, e);
+            }
+            if task.task_type == TaskType::LDAP
+                && let Err(e) = LdapPing::new(&task.target, &task.ldap.clone().unwrap_or_default())
+            {
+                panic!("LDAP任务的配置错误({}): {}", task.target, e);
+            }
+            let (host, _) = split_target(&task.target, 0);
+            if let Some(postgres) = &task.postgres
+                && let Err(e) =
+                    PostgresPing::new(&host, postgres).and_then(|_| expect_regex(&postgres.expect))
+            {
+                panic!("PostgreSQL任务的配置错误({}): {}", task.target, e);
+            }
+            if let Some(mysql) = &task.mysql
+                && let Err(e) =
+                    MysqlPing::new(&host, mysql).and_then(|_| expect_regex(&mysql.expect))
+            {
+                panic!("MySQL任务的配置错误({}): {}", task.target, e);
+            }
+            if let Some(redis) = &task.redis
+                && let Err(e) =
+                    RedisPing::new(&host, redis).and_then(|_| expect_regex(&redis.expect))
+            {
+                panic!("Redis任务的配置错误({}): {}", task.target, e);
+            }
+            for (protocol, mail) in [
+                (MailProtocol::Smtp, &task.smtp),
+                (MailProtocol::Imap, &task.imap),
+                (MailProtocol::Pop3, &task.pop3),
+            ] {
+                if let Some(mail) = mail
+                    && let Err(e) = MailPing::new(protocol, &host, mail)
+                {
+                    panic!("{}任务的配置错误({}): {}", protocol, task.target, e);
+                }
+            }
+            if let Some(ssh) = &task.ssh
+                && let Err(e) = SshPing::new(ssh).and_then(|_| {
+                    ssh.fingerprint
+                        .as_deref()
+                        .map(parse_fingerprint)
+                        .transpose()
+                })
+            {
+                panic!("SSH任务的配置错误({}): {}", task.target, e);
+            }
+            if let Some(mqtt) = &task.mqtt
+                && let Err(e) = MqttPing::new(&host, mqtt)
+            {
+                panic!("MQTT任务的配置错误({}): {}", task.target, e);
+            }
+            if task.task_type == TaskType::HTTP {
+                let http = task.http.clone().unwrap_or_default();
+                if let Err(e) = HttpAssertions::new(&http.assertions)
+                    .and_then(|_| HttpPing::new(task.target.clone(), &http))
+                {
+                    panic!("HTTP任务的配置错误({}): {}", task.target, e);
+                }
+            }
+            if task.task_type == TaskType::HTTP_FLOW
+                && let Err(e) =
+                    HttpFlowPing::new(&task.target, &task.http_flow.clone().unwrap_or_default())
+            {
+                panic!("HTTP事务任务的配置错误({}): {}", task.target, e);
+            }
+            if task.task_type == TaskType::DNS {
+                let dns = task.dns.clone().unwrap_or_default();
+                if let Err(e) = dns
+                    .expect_rcode_code()
+                    .and_then(|_| DnsPing::new(&task.target, &dns))
+                {
+                    panic!("DNS任务的配置错误({}): {}", task.target, e);
+                }
+            }
+        }
     }
 
     SETTINGS.set(settings).expect("无法设置配置信息");
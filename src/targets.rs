@@ -1,13 +1,12 @@
+use crate::metrics::probe_metric::ProbeMetric;
 use crate::settings::pong_settings::TaskType;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use std::thread::sleep;
-use std::time::Duration;
 
 /// 目标状态
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Clone, Debug, PartialEq)]
 pub struct TargetStatus {
     /// 任务类型
     pub task_type: TaskType,
@@ -15,6 +14,8 @@ pub struct TargetStatus {
     pub target: String,
     /// 耗时
     pub elapsed: i64,
+    /// 附加指标
+    pub metrics: Vec<ProbeMetric>,
 }
 
 /// 目标管理
@@ -33,17 +34,14 @@ impl Targets {
         let statuses = Arc::new(Mutex::new(HashMap::<String, TargetStatus>::new()));
         let statuses_clone = statuses.clone();
         thread::spawn(move || {
-            loop {
-                {
-                    let new_status = rx.recv().unwrap();
-                    let key = Self::calc_key(&new_status.task_type, &new_status.target);
-                    let mut statuses = statuses_clone.lock().unwrap();
-                    let old_status = statuses.get(&key);
-                    if old_status.is_none() || old_status.unwrap().elapsed != new_status.elapsed {
-                        statuses.insert(key, new_status);
-                    }
+            // 任务是并发执行的，收到状态后要立即更新，不能再限速，否则目标多时状态会积压
+            while let Ok(new_status) = rx.recv() {
+                let key = Self::calc_key(&new_status.task_type, &new_status.target);
+                let mut statuses = statuses_clone.lock().unwrap();
+                let old_status = statuses.get(&key);
+                if old_status.is_none() || old_status.unwrap() != &new_status {
+                    statuses.insert(key, new_status);
                 }
-                sleep(Duration::from_secs(1)); // 注意要放在作用域外，先释放锁
             }
         });
         Self { tx, statuses }
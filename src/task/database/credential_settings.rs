@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+/// 登录数据库、邮件等服务的凭据配置
+///
+/// 用户名和密码可以直接写在配置中，也可以从环境变量读取，
+/// 同时配置时以环境变量为准，避免在配置文件中保存明文密码。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct CredentialSettings {
+    /// 用户名
+    pub username: Option<String>,
+    /// 保存用户名的环境变量名
+    pub username_env: Option<String>,
+    /// 密码
+    pub password: Option<String>,
+    /// 保存密码的环境变量名
+    pub password_env: Option<String>,
+}
+
+/// 登录数据库、邮件等服务的凭据
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    /// 用户名
+    pub username: Option<String>,
+    /// 密码
+    pub password: Option<String>,
+}
+
+impl CredentialSettings {
+    /// 读取凭据，环境变量在此时读取
+    ///
+    /// # 返回值
+    /// 配置的环境变量不存在时返回错误信息
+    pub fn credentials(&self) -> Result<Credentials, String> {
+        Ok(Credentials {
+            username: read(&self.username, &self.username_env)?,
+            password: read(&self.password, &self.password_env)?,
+        })
+    }
+}
+
+/// 读取配置的值，配置了环境变量时从环境变量读取
+fn read(value: &Option<String>, env: &Option<String>) -> Result<Option<String>, String> {
+    match env {
+        Some(env) => std::env::var(env)
+            .map(Some)
+            .map_err(|e| format!("读取环境变量({})失败: {}", env, e)),
+        None => Ok(value.clone()),
+    }
+}
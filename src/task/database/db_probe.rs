@@ -0,0 +1,222 @@
+use crate::executor::ExecReport;
+use crate::metrics::metrics_cst::{
+    DB_DURATION_PROMETHEUS_METRIC_DESC, DB_DURATION_PROMETHEUS_METRIC_NAME,
+    PHASE_PROMETHEUS_METRIC_LABEL_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::resolver::target_resolver::split_host_port;
+use crate::task::http::http_client::client_config;
+use crate::task::tcp::tcp_ping::TcpPing;
+use crate::task::tcp::tcp_settings::TcpSettings;
+use async_trait::async_trait;
+use regex::Regex;
+use rustls::pki_types::ServerName;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+
+/// 数据库探测各阶段的耗时
+#[derive(Debug, Clone, Default)]
+pub struct DbTimings {
+    /// 建立TCP连接
+    pub connect: Duration,
+    /// 从连接建立到认证通过，包括TLS握手，认证未完成时为None
+    pub auth: Option<Duration>,
+    /// 执行查询，查询未完成时为None
+    pub query: Option<Duration>,
+}
+
+impl DbTimings {
+    /// 转换成探测指标，每个完成的阶段一个 `phase` 标签
+    pub fn to_metrics(&self) -> Vec<ProbeMetric> {
+        [
+            ("connect", Some(self.connect)),
+            ("auth", self.auth),
+            ("query", self.query),
+        ]
+        .into_iter()
+        .filter_map(|(phase, duration)| {
+            duration.map(|duration| {
+                ProbeMetric::new(
+                    DB_DURATION_PROMETHEUS_METRIC_NAME,
+                    DB_DURATION_PROMETHEUS_METRIC_DESC,
+                    duration.as_secs_f64() * 1000.0,
+                )
+                .with_label(PHASE_PROMETHEUS_METRIC_LABEL_NAME, phase)
+            })
+        })
+        .collect()
+    }
+
+    /// 各阶段的耗时之和
+    pub fn total(&self) -> Duration {
+        self.connect + self.auth.unwrap_or_default() + self.query.unwrap_or_default()
+    }
+}
+
+/// 数据库探测各阶段的计时器
+#[derive(Debug)]
+pub struct DbTimer {
+    timings: DbTimings,
+    /// 上一个阶段结束的时间
+    mark: Instant,
+}
+
+impl DbTimer {
+    /// 记录认证通过
+    pub fn auth_done(&mut self) {
+        self.timings.auth = Some(self.mark.elapsed());
+        self.mark = Instant::now();
+    }
+
+    /// 记录查询完成
+    pub fn query_done(&mut self) {
+        self.timings.query = Some(self.mark.elapsed());
+        self.mark = Instant::now();
+    }
+}
+
+/// 一次数据库探测的结果
+#[derive(Debug)]
+pub struct DbReply {
+    /// 各阶段的耗时
+    pub timings: DbTimings,
+    /// 查询结果的第一个值，没有结果或为NULL时为None
+    pub value: Option<String>,
+    /// 认证或查询失败的原因
+    pub error: Option<PingError>,
+}
+
+impl DbReply {
+    /// 检查查询结果并转换成执行报告，以各阶段的耗时之和作为耗时
+    ///
+    /// # 参数
+    /// * `expect` - 查询结果须匹配的正则表达式
+    pub fn into_report(self, expect: Option<&Regex>) -> ExecReport {
+        let error = self.error.or_else(|| {
+            let expect = expect?;
+            match &self.value {
+                None => Some(format!("查询没有返回结果，期望匹配 /{}/", expect)),
+                Some(value) if !expect.is_match(value) => {
+                    Some(format!("查询结果 {:?} 未匹配 /{}/", value, expect))
+                }
+                Some(_) => None,
+            }
+            .map(PingError::InvalidReply)
+        });
+        ExecReport {
+            elapsed: Some(self.timings.total()),
+            error,
+            metrics: self.timings.to_metrics(),
+        }
+    }
+}
+
+/// 数据库的协议
+#[async_trait]
+pub trait DbProtocol {
+    /// 在建立的连接上完成认证并执行查询，结束后断开
+    ///
+    /// # 返回值
+    /// 查询结果的第一个值
+    async fn session(
+        &self,
+        stream: TcpStream,
+        timer: &mut DbTimer,
+    ) -> Result<Option<String>, PingError>;
+}
+
+/// 连接数据库并按协议完成认证和查询
+///
+/// # 返回值
+/// 连接失败时返回错误，认证或查询失败记录在结果中
+pub async fn probe<P>(
+    protocol: &P,
+    socket_addr: SocketAddr,
+    timeout: Duration,
+) -> Result<DbReply, PingError>
+where
+    P: DbProtocol + Sync,
+{
+    let start = Instant::now();
+    let (stream, connect) = TcpPing::new(socket_addr, &TcpSettings::default())
+        .connect(timeout)
+        .await?;
+    let mut timer = DbTimer {
+        timings: DbTimings {
+            connect,
+            ..Default::default()
+        },
+        mark: Instant::now(),
+    };
+    let result = tokio::time::timeout(
+        timeout.saturating_sub(start.elapsed()),
+        protocol.session(stream, &mut timer),
+    )
+    .await
+    .unwrap_or(Err(PingError::Timeout));
+    let (value, error) = match result {
+        Ok(value) => (value, None),
+        Err(e) => (None, Some(e)),
+    };
+    Ok(DbReply {
+        timings: timer.timings,
+        value,
+        error,
+    })
+}
+
+/// 拆分目标的主机名和端口号，端口号省略时使用默认的端口号
+pub fn split_target(host_port: &str, default_port: u16) -> (String, u16) {
+    if IpAddr::from_str(host_port).is_ok() {
+        return (host_port.to_string(), default_port);
+    }
+    split_host_port(host_port).unwrap_or_else(|_| (host_port.to_string(), default_port))
+}
+
+/// 编译查询结果须匹配的正则表达式
+pub fn expect_regex(expect: &Option<String>) -> Result<Option<Regex>, String> {
+    expect
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| format!("expect不是合法的正则表达式: {}", e))
+}
+
+/// 连接数据库使用的TLS配置
+#[derive(Clone)]
+pub struct DbTls {
+    connector: TlsConnector,
+    /// 握手时发送的SNI
+    server_name: ServerName<'static>,
+}
+
+impl DbTls {
+    /// 构造函数
+    /// # 参数
+    /// * `host` - 目标的主机名，用于SNI及校验证书
+    /// * `insecure` - 是否跳过证书校验
+    pub fn new(host: &str, insecure: bool) -> Result<Self, String> {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let server_name = ServerName::try_from(host.to_string())
+            .map_err(|e| format!("错误的主机名({}): {}", host, e))?;
+        Ok(Self {
+            connector: TlsConnector::from(Arc::new(client_config(insecure, &[])?)),
+            server_name,
+        })
+    }
+
+    /// 在已建立的连接上完成TLS握手
+    pub async fn connect(&self, stream: TcpStream) -> Result<TlsStream<TcpStream>, PingError> {
+        self.connector
+            .connect(self.server_name.clone(), stream)
+            .await
+            .map_err(|e| PingError::Tls(e.to_string()))
+    }
+}
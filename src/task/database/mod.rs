@@ -0,0 +1,2 @@
+pub mod credential_settings;
+pub mod db_probe;
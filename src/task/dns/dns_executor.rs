@@ -0,0 +1,126 @@
+use crate::executor::{ExecReport, Executor};
+use crate::metrics::metrics_cst::{
+    DNS_ANSWERS_PROMETHEUS_METRIC_DESC, DNS_ANSWERS_PROMETHEUS_METRIC_NAME,
+    DNS_RCODE_PROMETHEUS_METRIC_DESC, DNS_RCODE_PROMETHEUS_METRIC_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::task::dns::dns_ping::{DnsPing, DnsReply};
+use crate::task::dns::dns_settings::{rcode_name, DnsSettings};
+use async_trait::async_trait;
+use log::trace;
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct DnsExecutor {
+    name: String,
+    dns_ping: DnsPing,
+    timeout: Duration,
+    /// 期望的响应码
+    expect_rcode: u16,
+    /// 最少的应答数
+    min_answers: Option<usize>,
+    /// 最多的应答数
+    max_answers: Option<usize>,
+    /// 必须出现在应答中的值，已规范化
+    expect_answers: Vec<String>,
+}
+
+impl DnsExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `name` - 要查询的域名
+    /// * `timeout` - 一个 `Duration`，表示超时时间
+    /// * `settings` - DNS任务配置
+    ///
+    /// # Panics
+    /// 配置错误时会触发panic，配置在加载时已经检查过
+    pub fn new(name: String, timeout: Duration, settings: DnsSettings) -> Self {
+        let dns_ping = DnsPing::new(&name, &settings)
+            .unwrap_or_else(|e| panic!("DNS任务的配置错误({}): {}", name, e));
+        Self {
+            dns_ping,
+            timeout,
+            expect_rcode: settings.expect_rcode_code().unwrap(),
+            min_answers: settings.min_answers,
+            max_answers: settings.max_answers,
+            expect_answers: settings
+                .expect_answers
+                .iter()
+                .map(|answer| normalize(answer))
+                .collect(),
+            name,
+        }
+    }
+
+    /// 检查应答
+    ///
+    /// # 返回值
+    /// 第一个不满足的断言的描述
+    fn check(&self, reply: &DnsReply) -> Result<(), String> {
+        if reply.rcode != self.expect_rcode {
+            return Err(format!(
+                "响应码 {} 不是期望的 {}",
+                rcode_name(reply.rcode),
+                rcode_name(self.expect_rcode)
+            ));
+        }
+        let count = reply.answers.len();
+        if let Some(min_answers) = self.min_answers
+            && count < min_answers
+        {
+            return Err(format!("应答数 {} 少于 {}", count, min_answers));
+        }
+        if let Some(max_answers) = self.max_answers
+            && count > max_answers
+        {
+            return Err(format!("应答数 {} 多于 {}", count, max_answers));
+        }
+        let answers: Vec<String> = reply
+            .answers
+            .iter()
+            .map(|answer| normalize(answer))
+            .collect();
+        if let Some(missing) = self
+            .expect_answers
+            .iter()
+            .find(|expected| !answers.contains(expected))
+        {
+            return Err(format!("应答中没有 {}: {:?}", missing, reply.answers));
+        }
+        Ok(())
+    }
+}
+
+/// 规范化记录的值，以便忽略大小写和末尾的点
+fn normalize(answer: &str) -> String {
+    answer.trim().trim_end_matches('.').to_lowercase()
+}
+
+#[async_trait]
+impl Executor for DnsExecutor {
+    fn get_name(&self) -> String {
+        String::from("DNS")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        trace!("开始执行 DNS 任务: query {}", self.name);
+        let (reply, elapsed) = self.dns_ping.ping(self.timeout).await?;
+        Ok(ExecReport {
+            elapsed: Some(elapsed),
+            error: self.check(&reply).err().map(PingError::InvalidReply),
+            metrics: vec![
+                ProbeMetric::new(
+                    DNS_RCODE_PROMETHEUS_METRIC_NAME,
+                    DNS_RCODE_PROMETHEUS_METRIC_DESC,
+                    reply.rcode as f64,
+                ),
+                ProbeMetric::new(
+                    DNS_ANSWERS_PROMETHEUS_METRIC_NAME,
+                    DNS_ANSWERS_PROMETHEUS_METRIC_DESC,
+                    reply.answers.len() as f64,
+                ),
+            ],
+        })
+    }
+}
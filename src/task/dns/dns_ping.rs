@@ -0,0 +1,382 @@
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::{split_host_port, TargetResolver};
+use crate::task::dns::dns_settings::{DnsProtocol, DnsRecordType, DnsSettings};
+use crate::task::http::http_client::client_config;
+use crate::task::tcp::tcp_ping::TcpPing;
+use crate::task::tcp::tcp_settings::TcpSettings;
+use hickory_proto::op::{Message, MessageType, OpCode, Query};
+use hickory_proto::rr::{Name, RData, RecordType};
+use log::trace;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::Client;
+use rustls::pki_types::ServerName;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::Instant;
+use tokio_rustls::TlsConnector;
+
+/// 查询的ID，每次查询加1
+static QUERY_ID: AtomicU16 = AtomicU16::new(0);
+
+/// DNS报文的媒体类型(RFC 8484)
+const DNS_MESSAGE_MEDIA_TYPE: &str = "application/dns-message";
+
+/// UDP应答的最大字节数，未使用EDNS时应答不超过512字节
+const UDP_REPLY_SIZE: usize = 4096;
+
+/// DNS应答
+#[derive(Debug)]
+pub struct DnsReply {
+    /// 响应码
+    pub rcode: u16,
+    /// 所查询类型的记录的值，不含CNAME链等其它类型的记录
+    pub answers: Vec<String>,
+}
+
+/// 查询所用的传输方式
+#[derive(Clone)]
+enum DnsTransport {
+    Udp(DnsServer),
+    Tcp(DnsServer),
+    Dot(DnsServer, ServerName<'static>, TlsConnector),
+    Doh(Client, String),
+}
+
+/// udp/tcp/dot的服务器地址
+#[derive(Clone)]
+enum DnsServer {
+    /// 配置为IP地址，加载时即确定
+    Addr(SocketAddr),
+    /// 配置为主机名，每次查询时重新解析
+    Host(TargetResolver, u16),
+}
+
+impl DnsServer {
+    /// 本次查询使用的地址
+    ///
+    /// # 返回值
+    /// 主机名解析失败时返回 `Resolve` 错误
+    async fn socket_addr(&self) -> Result<SocketAddr, PingError> {
+        match self {
+            DnsServer::Addr(socket_addr) => Ok(*socket_addr),
+            DnsServer::Host(resolver, port) => {
+                // 解析成功时至少有一个地址
+                let ip_addr = resolver.resolve().await?[0];
+                Ok(SocketAddr::new(ip_addr, *port))
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DnsPing {
+    /// 服务器的描述，用于日志
+    server: String,
+    transport: DnsTransport,
+    /// 查询的域名
+    name: Name,
+    /// 查询的记录类型
+    record_type: RecordType,
+    /// 是否请求递归查询
+    recursion_desired: bool,
+}
+
+impl DnsPing {
+    /// 构造函数
+    /// # 参数
+    /// * `name` - 要查询的域名
+    /// * `settings` - DNS任务配置
+    ///
+    /// # 返回值
+    /// 域名或服务器地址错误时返回错误信息
+    pub fn new(name: &str, settings: &DnsSettings) -> Result<Self, String> {
+        let fqdn = if name.ends_with('.') {
+            name.to_string()
+        } else {
+            format!("{}.", name)
+        };
+        let name = Name::from_ascii(&fqdn).map_err(|e| format!("错误的域名({}): {}", name, e))?;
+
+        let (server, transport) = if settings.protocol == DnsProtocol::Doh {
+            let url = settings
+                .server
+                .clone()
+                .ok_or_else(|| String::from("doh必须配置server"))?;
+            let client = Client::builder().build().map_err(|e| e.to_string())?;
+            (url.clone(), DnsTransport::Doh(client, url))
+        } else {
+            let server = match &settings.server {
+                Some(server) => server.clone(),
+                None => system_nameserver()
+                    .ok_or_else(|| String::from("/etc/resolv.conf 中没有DNS服务器"))?
+                    .to_string(),
+            };
+            let default_port = if settings.protocol == DnsProtocol::Dot {
+                853
+            } else {
+                53
+            };
+            let (dns_server, host) = parse_server(&server, default_port)?;
+            let transport = match settings.protocol {
+                DnsProtocol::Udp => DnsTransport::Udp(dns_server),
+                DnsProtocol::Tcp => DnsTransport::Tcp(dns_server),
+                _ => {
+                    let tls_name = settings.tls_name.as_deref().unwrap_or(&host);
+                    let server_name = ServerName::try_from(tls_name.to_string())
+                        .map_err(|e| format!("错误的tls-name({}): {}", tls_name, e))?;
+                    DnsTransport::Dot(dns_server, server_name, tls_connector()?)
+                }
+            };
+            (server, transport)
+        };
+
+        Ok(Self {
+            server,
+            transport,
+            name,
+            record_type: match settings.record_type {
+                DnsRecordType::A => RecordType::A,
+                DnsRecordType::AAAA => RecordType::AAAA,
+                DnsRecordType::CNAME => RecordType::CNAME,
+                DnsRecordType::MX => RecordType::MX,
+                DnsRecordType::TXT => RecordType::TXT,
+                DnsRecordType::SRV => RecordType::SRV,
+                DnsRecordType::SOA => RecordType::SOA,
+            },
+            recursion_desired: settings.recursion_desired,
+        })
+    }
+
+    /// 发出查询并等待应答
+    ///
+    /// # 返回值
+    /// 成功时返回应答及查询的耗时，包括解析服务器的主机名，dot和doh的耗时还包括建立连接和握手
+    pub async fn ping(&self, timeout: Duration) -> Result<(DnsReply, Duration), PingError> {
+        trace!(
+            "query {} {} from {} ....",
+            self.name,
+            self.record_type,
+            self.server
+        );
+        let start = Instant::now();
+        // DoH建议使用0作为查询的ID，以便缓存(RFC 8484)
+        let id = match self.transport {
+            DnsTransport::Doh(..) => 0,
+            _ => QUERY_ID.fetch_add(1, Ordering::Relaxed),
+        };
+        let mut query = Message::new();
+        query
+            .set_id(id)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(self.recursion_desired)
+            .add_query(Query::query(self.name.clone(), self.record_type));
+        let query = query
+            .to_vec()
+            .map_err(|e| PingError::InvalidReply(format!("无法构造DNS查询: {}", e)))?;
+
+        let reply = match &self.transport {
+            DnsTransport::Udp(server) => {
+                let socket_addr = server.socket_addr().await?;
+                let reply = self.exchange_udp(socket_addr, &query, id, timeout).await?;
+                if reply.truncated() {
+                    // 应答被截断时改用TCP重新查询
+                    trace!("query {} truncated, retry over tcp", self.name);
+                    let timeout = timeout.saturating_sub(start.elapsed());
+                    let (stream, _) = connect(socket_addr, timeout).await?;
+                    parse_reply(&exchange_stream(stream, &query, timeout).await?)?
+                } else {
+                    reply
+                }
+            }
+            DnsTransport::Tcp(server) => {
+                let (stream, connect) = connect(server.socket_addr().await?, timeout).await?;
+                parse_reply(
+                    &exchange_stream(stream, &query, timeout.saturating_sub(connect)).await?,
+                )?
+            }
+            DnsTransport::Dot(server, server_name, connector) => {
+                let (stream, _) = connect(server.socket_addr().await?, timeout).await?;
+                let timeout = timeout.saturating_sub(start.elapsed());
+                let stream =
+                    tokio::time::timeout(timeout, connector.connect(server_name.clone(), stream))
+                        .await
+                        .map_err(|_| PingError::Timeout)?
+                        .map_err(|e| PingError::Tls(e.to_string()))?;
+                let timeout = timeout.saturating_sub(start.elapsed());
+                parse_reply(&exchange_stream(stream, &query, timeout).await?)?
+            }
+            DnsTransport::Doh(client, url) => {
+                let response = client
+                    .post(url)
+                    .header(CONTENT_TYPE, DNS_MESSAGE_MEDIA_TYPE)
+                    .header(ACCEPT, DNS_MESSAGE_MEDIA_TYPE)
+                    .body(query)
+                    .timeout(timeout)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                parse_reply(&response.bytes().await?)?
+            }
+        };
+        if reply.id() != id {
+            return Err(PingError::InvalidReply(format!(
+                "应答的ID {} 与查询的ID {} 不一致",
+                reply.id(),
+                id
+            )));
+        }
+        let elapsed = start.elapsed();
+
+        let reply = DnsReply {
+            rcode: reply.response_code().into(),
+            answers: reply
+                .answers()
+                .iter()
+                .filter(|record| record.record_type() == self.record_type)
+                .map(|record| format_rdata(record.data()))
+                .collect(),
+        };
+        trace!(
+            "query {} {} from {} replied in {:?}: {:?}",
+            self.name,
+            self.record_type,
+            self.server,
+            elapsed,
+            reply
+        );
+        Ok((reply, elapsed))
+    }
+
+    /// 通过UDP发出查询，丢弃ID不一致的应答
+    async fn exchange_udp(
+        &self,
+        server: SocketAddr,
+        query: &[u8],
+        id: u16,
+        timeout: Duration,
+    ) -> Result<Message, PingError> {
+        let local: IpAddr = if server.is_ipv4() {
+            Ipv4Addr::UNSPECIFIED.into()
+        } else {
+            Ipv6Addr::UNSPECIFIED.into()
+        };
+        let socket = UdpSocket::bind(SocketAddr::new(local, 0)).await?;
+        socket.connect(server).await?;
+        socket.send(query).await?;
+        tokio::time::timeout(timeout, async {
+            let mut buf = vec![0; UDP_REPLY_SIZE];
+            loop {
+                let size = socket.recv(&mut buf).await?;
+                match parse_reply(&buf[..size]) {
+                    Ok(reply) if reply.id() == id => return Ok(reply),
+                    _ => trace!("query {} ignored an unexpected reply", self.name),
+                }
+            }
+        })
+        .await
+        .map_err(|_| PingError::Timeout)?
+    }
+}
+
+/// 建立TCP连接
+async fn connect(
+    socket_addr: SocketAddr,
+    timeout: Duration,
+) -> Result<(TcpStream, Duration), PingError> {
+    TcpPing::new(socket_addr, &TcpSettings::default())
+        .connect(timeout)
+        .await
+}
+
+/// 通过TCP或TLS连接发出查询，报文前加两个字节的长度(RFC 1035)
+async fn exchange_stream<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    query: &[u8],
+    timeout: Duration,
+) -> Result<Vec<u8>, PingError> {
+    tokio::time::timeout(timeout, async {
+        let mut message = Vec::with_capacity(query.len() + 2);
+        message.extend_from_slice(&(query.len() as u16).to_be_bytes());
+        message.extend_from_slice(query);
+        stream.write_all(&message).await?;
+        stream.flush().await?;
+        let size = stream.read_u16().await?;
+        let mut reply = vec![0; size as usize];
+        stream.read_exact(&mut reply).await?;
+        Ok(reply)
+    })
+    .await
+    .map_err(|_| PingError::Timeout)?
+}
+
+/// 解析应答
+fn parse_reply(reply: &[u8]) -> Result<Message, PingError> {
+    let reply = Message::from_vec(reply)
+        .map_err(|e| PingError::InvalidReply(format!("无法解析DNS应答: {}", e)))?;
+    if reply.message_type() != MessageType::Response {
+        return Err(PingError::InvalidReply(String::from("收到的不是DNS应答")));
+    }
+    Ok(reply)
+}
+
+/// 记录的值，TXT记录取各段文本拼接的结果
+fn format_rdata(rdata: &RData) -> String {
+    match rdata {
+        RData::TXT(txt) => txt
+            .txt_data()
+            .iter()
+            .map(|data| String::from_utf8_lossy(data))
+            .collect(),
+        rdata => rdata.to_string(),
+    }
+}
+
+/// 解析服务器地址，主机名留到查询时再解析
+///
+/// # 返回值
+/// 服务器的地址，及用作默认SNI的主机名或IP地址
+fn parse_server(server: &str, default_port: u16) -> Result<(DnsServer, String), String> {
+    if let Ok(socket_addr) = SocketAddr::from_str(server) {
+        return Ok((DnsServer::Addr(socket_addr), socket_addr.ip().to_string()));
+    }
+    if let Ok(ip_addr) = IpAddr::from_str(server) {
+        return Ok((
+            DnsServer::Addr(SocketAddr::new(ip_addr, default_port)),
+            server.to_string(),
+        ));
+    }
+    let (host, port) = match server.rsplit_once(':') {
+        Some(_) => {
+            split_host_port(server).map_err(|e| format!("错误的DNS服务器({}): {}", server, e))?
+        }
+        None => (server.to_string(), default_port),
+    };
+    if host.is_empty() || host.contains(['[', ']', ':']) {
+        return Err(format!("错误的DNS服务器({}): 错误的主机名", server));
+    }
+    Ok((
+        DnsServer::Host(TargetResolver::new(&host, ResolveSettings::default()), port),
+        host,
+    ))
+}
+
+/// /etc/resolv.conf 中的第一个DNS服务器
+fn system_nameserver() -> Option<IpAddr> {
+    std::fs::read_to_string("/etc/resolv.conf")
+        .ok()?
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .find_map(|ip| IpAddr::from_str(ip.trim()).ok())
+}
+
+/// DoT使用的TLS连接器
+fn tls_connector() -> Result<TlsConnector, String> {
+    Ok(TlsConnector::from(Arc::new(client_config(false, &[])?)))
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+
+/// DNS任务配置
+///
+/// 目标为要查询的域名，查询结果的响应码、应答数和应答值都可以配置断言，
+/// 默认只要求响应码为 `NOERROR`。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct DnsSettings {
+    /// 查询的记录类型
+    #[serde(default)]
+    pub record_type: DnsRecordType,
+    /// 查询使用的协议
+    #[serde(default)]
+    pub protocol: DnsProtocol,
+    /// DNS服务器，udp/tcp/dot为 `IP[:端口]` 或 `主机名[:端口]`，主机名在每次查询时重新解析，
+    /// 默认为 /etc/resolv.conf 中的第一个服务器；
+    /// doh为查询的URL，如 `https://dns.google/dns-query`，必须配置
+    pub server: Option<String>,
+    /// DoT握手时的SNI，也用于校验证书，默认为服务器的主机名或IP地址
+    pub tls_name: Option<String>,
+    /// 是否请求递归查询
+    #[serde(default = "recursion_desired_default")]
+    pub recursion_desired: bool,
+    /// 期望的响应码，如 `NOERROR`、`NXDOMAIN`
+    #[serde(default = "expect_rcode_default")]
+    pub expect_rcode: String,
+    /// 最少的应答数，只统计所查询类型的记录
+    pub min_answers: Option<usize>,
+    /// 最多的应答数，只统计所查询类型的记录
+    pub max_answers: Option<usize>,
+    /// 必须出现在应答中的值，如 `93.184.216.34`、`10 mail.example.com`，比较时忽略大小写和末尾的点
+    #[serde(default)]
+    pub expect_answers: Vec<String>,
+}
+
+/// 响应码的名称，下标即响应码(RFC 1035, RFC 2136)
+const RCODE_NAMES: [&str; 11] = [
+    "NOERROR", "FORMERR", "SERVFAIL", "NXDOMAIN", "NOTIMP", "REFUSED", "YXDOMAIN", "YXRRSET",
+    "NXRRSET", "NOTAUTH", "NOTZONE",
+];
+
+/// 响应码的名称，未知的响应码以数字表示
+pub fn rcode_name(rcode: u16) -> String {
+    RCODE_NAMES
+        .get(rcode as usize)
+        .map_or_else(|| rcode.to_string(), |name| name.to_string())
+}
+
+impl DnsSettings {
+    /// 期望的响应码
+    ///
+    /// # 返回值
+    /// 既不是响应码的名称也不是数字时返回错误信息
+    pub fn expect_rcode_code(&self) -> Result<u16, String> {
+        let rcode = self.expect_rcode.to_uppercase();
+        RCODE_NAMES
+            .iter()
+            .position(|name| *name == rcode)
+            .map(|code| code as u16)
+            .or_else(|| rcode.parse().ok())
+            .ok_or_else(|| format!("错误的响应码: {}", self.expect_rcode))
+    }
+}
+
+fn recursion_desired_default() -> bool {
+    true // 默认请求递归查询
+}
+fn expect_rcode_default() -> String {
+    String::from("NOERROR") // 默认要求查询成功
+}
+
+impl Default for DnsSettings {
+    fn default() -> Self {
+        Self {
+            record_type: DnsRecordType::default(),
+            protocol: DnsProtocol::default(),
+            server: None,
+            tls_name: None,
+            recursion_desired: recursion_desired_default(),
+            expect_rcode: expect_rcode_default(),
+            min_answers: None,
+            max_answers: None,
+            expect_answers: vec![],
+        }
+    }
+}
+
+/// 查询的记录类型
+#[derive(Debug, Serialize, Deserialize, Display, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DnsRecordType {
+    #[default]
+    A,
+    AAAA,
+    CNAME,
+    MX,
+    TXT,
+    SRV,
+    SOA,
+}
+
+/// 查询使用的协议
+#[derive(Debug, Serialize, Deserialize, Display, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum DnsProtocol {
+    /// DNS over UDP，应答被截断时改用TCP重新查询
+    #[default]
+    Udp,
+    /// DNS over TCP
+    Tcp,
+    /// DNS over TLS(RFC 7858)
+    Dot,
+    /// DNS over HTTPS(RFC 8484)
+    Doh,
+}
@@ -0,0 +1,3 @@
+pub mod dns_executor;
+pub mod dns_ping;
+pub mod dns_settings;
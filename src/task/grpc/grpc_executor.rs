@@ -0,0 +1,86 @@
+use crate::executor::{ExecReport, Executor};
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::{split_host_port, TargetResolver};
+use crate::task::grpc::grpc_ping::{GrpcHealthStatus, GrpcPing, GrpcReply};
+use crate::task::grpc::grpc_settings::GrpcSettings;
+use async_trait::async_trait;
+use log::trace;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[derive(Clone)]
+pub struct GrpcExecutor {
+    resolver: TargetResolver,
+    port: u16,
+    grpc_ping: GrpcPing,
+    timeout: Duration,
+}
+
+impl GrpcExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `host_port` - gRPC服务的主机名及端口号
+    /// * `timeout` - 一个 `Duration`，表示超时时间
+    /// * `settings` - gRPC任务配置
+    /// * `resolve` - 解析主机名的配置
+    ///
+    /// # Panics
+    /// 目标缺少端口号或配置错误时会触发panic，配置在加载时已经检查过
+    pub fn new(
+        host_port: String,
+        timeout: Duration,
+        settings: GrpcSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        let (host, port) = split_host_port(&host_port).unwrap();
+        Self {
+            resolver: TargetResolver::new(&host, resolve),
+            port,
+            grpc_ping: GrpcPing::new(&host, &host_port, &settings).unwrap(),
+            timeout,
+        }
+    }
+
+    /// 检查调用是否成功及服务的状态
+    fn check(reply: &GrpcReply) -> Result<(), String> {
+        match reply.health {
+            Some(GrpcHealthStatus::Serving) => Ok(()),
+            Some(health) if reply.grpc_status == 0 => Err(format!("服务状态为 {}", health)),
+            _ => Err(format!(
+                "调用失败 grpc-status {}: {}",
+                reply.grpc_status, reply.grpc_message
+            )),
+        }
+    }
+
+    /// 探测一个地址，以建立连接到收到响应的时间作为耗时
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        let socket_addr = SocketAddr::new(ip_addr, self.port);
+        trace!("开始执行 gRPC 任务: health check {}", socket_addr);
+        let start = Instant::now();
+        let reply = self.grpc_ping.ping(socket_addr, self.timeout).await?;
+        Ok(ExecReport {
+            elapsed: Some(start.elapsed()),
+            error: Self::check(&reply).err().map(PingError::InvalidReply),
+            metrics: reply.to_metrics(),
+        })
+    }
+}
+
+#[async_trait]
+impl Executor for GrpcExecutor {
+    fn get_name(&self) -> String {
+        String::from("gRPC")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move { executor.exec_addr(ip_addr).await }
+            })
+            .await
+    }
+}
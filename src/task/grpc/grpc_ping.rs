@@ -0,0 +1,492 @@
+use crate::metrics::metrics_cst::{
+    GRPC_HEALTH_STATUS_PROMETHEUS_METRIC_DESC, GRPC_HEALTH_STATUS_PROMETHEUS_METRIC_NAME,
+    GRPC_STATUS_CODE_PROMETHEUS_METRIC_DESC, GRPC_STATUS_CODE_PROMETHEUS_METRIC_NAME,
+    STATE_PROMETHEUS_METRIC_LABEL_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::task::grpc::grpc_settings::GrpcSettings;
+use crate::task::http::http_client::client_config;
+use crate::task::tcp::tcp_ping::TcpPing;
+use crate::task::tcp::tcp_settings::TcpSettings;
+use bytes::{BufMut, Bytes, BytesMut};
+use h2::client::SendRequest;
+use http::{HeaderMap, Method, Request, StatusCode};
+use log::trace;
+use rustls::pki_types::ServerName;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use strum_macros::Display;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
+use tokio_rustls::TlsConnector;
+
+/// 健康检查接口的路径
+const HEALTH_CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+/// 最多读取的响应字节数，健康检查的响应只有几个字节
+const MAX_RESPONSE_SIZE: usize = 1024;
+/// gRPC状态码 NOT_FOUND，服务器不认识请求的服务名时返回
+const GRPC_STATUS_NOT_FOUND: u32 = 5;
+
+/// 服务的健康状态
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum GrpcHealthStatus {
+    /// 状态未知
+    Unknown,
+    /// 正在提供服务
+    Serving,
+    /// 未提供服务
+    NotServing,
+    /// 服务器不认识请求的服务名
+    ServiceUnknown,
+}
+
+impl GrpcHealthStatus {
+    /// 所有的状态
+    const ALL: [GrpcHealthStatus; 4] = [
+        GrpcHealthStatus::Unknown,
+        GrpcHealthStatus::Serving,
+        GrpcHealthStatus::NotServing,
+        GrpcHealthStatus::ServiceUnknown,
+    ];
+
+    /// 根据响应消息中的状态值转换，不认识的值按 UNKNOWN 处理
+    fn from_code(code: u64) -> Self {
+        match code {
+            1 => GrpcHealthStatus::Serving,
+            2 => GrpcHealthStatus::NotServing,
+            3 => GrpcHealthStatus::ServiceUnknown,
+            _ => GrpcHealthStatus::Unknown,
+        }
+    }
+
+    /// 转换成探测指标，当前状态为1，其余状态为0
+    pub fn to_metrics(self) -> Vec<ProbeMetric> {
+        Self::ALL
+            .iter()
+            .map(|status| {
+                ProbeMetric::new(
+                    GRPC_HEALTH_STATUS_PROMETHEUS_METRIC_NAME,
+                    GRPC_HEALTH_STATUS_PROMETHEUS_METRIC_DESC,
+                    if *status == self { 1.0 } else { 0.0 },
+                )
+                .with_label(STATE_PROMETHEUS_METRIC_LABEL_NAME, status)
+            })
+            .collect()
+    }
+}
+
+/// 一次健康检查的结果
+#[derive(Debug)]
+pub struct GrpcReply {
+    /// 调用的状态码，0为成功
+    pub grpc_status: u32,
+    /// 调用失败时服务器返回的错误信息
+    pub grpc_message: String,
+    /// 服务的健康状态，调用失败且无法判断状态时为None
+    pub health: Option<GrpcHealthStatus>,
+}
+
+impl GrpcReply {
+    /// 转换成探测指标
+    pub fn to_metrics(&self) -> Vec<ProbeMetric> {
+        let mut metrics = vec![ProbeMetric::new(
+            GRPC_STATUS_CODE_PROMETHEUS_METRIC_NAME,
+            GRPC_STATUS_CODE_PROMETHEUS_METRIC_DESC,
+            self.grpc_status as f64,
+        )];
+        if let Some(health) = self.health {
+            metrics.extend(health.to_metrics());
+        }
+        metrics
+    }
+}
+
+#[derive(Clone)]
+pub struct GrpcPing {
+    /// 请求的URI，包含协议及 `:authority`
+    uri: String,
+    /// 请求消息，已经加上了gRPC的消息头
+    request: Bytes,
+    /// TLS连接器及握手时发送的SNI，明文连接时为None
+    tls: Option<(TlsConnector, ServerName<'static>)>,
+}
+
+impl GrpcPing {
+    /// 构造函数
+    /// # 参数
+    /// * `host` - 目标的主机名，也是默认的SNI
+    /// * `authority` - 请求的 `:authority`，即目标的主机名及端口号
+    /// * `settings` - gRPC任务配置
+    ///
+    /// # 返回值
+    /// SNI格式错误时返回错误信息
+    pub fn new(host: &str, authority: &str, settings: &GrpcSettings) -> Result<Self, String> {
+        let tls = if settings.tls {
+            let tls_name = settings
+                .tls_name
+                .as_deref()
+                .unwrap_or_else(|| host.trim_start_matches('[').trim_end_matches(']'));
+            let server_name = ServerName::try_from(tls_name.to_string())
+                .map_err(|e| format!("错误的tls-name({}): {}", tls_name, e))?;
+            Some((tls_connector(settings.insecure)?, server_name))
+        } else {
+            None
+        };
+        Ok(Self {
+            uri: format!(
+                "{}://{}{}",
+                if settings.tls { "https" } else { "http" },
+                authority,
+                HEALTH_CHECK_PATH
+            ),
+            request: encode_request(&settings.service),
+            tls,
+        })
+    }
+
+    /// 连接目标并调用健康检查接口，调用结束后断开
+    ///
+    /// # 返回值
+    /// 调用失败(`grpc-status` 不为0)不会返回错误，状态码记录在结果中
+    pub async fn ping(
+        &self,
+        socket_addr: SocketAddr,
+        timeout: Duration,
+    ) -> Result<GrpcReply, PingError> {
+        trace!("grpc health check {} ....", socket_addr);
+        let start = Instant::now();
+        let (stream, _) = TcpPing::new(socket_addr, &TcpSettings::default())
+            .connect(timeout)
+            .await?;
+        let reply = match &self.tls {
+            Some((connector, server_name)) => {
+                let stream = tokio::time::timeout(
+                    timeout.saturating_sub(start.elapsed()),
+                    connector.connect(server_name.clone(), stream),
+                )
+                .await
+                .map_err(|_| PingError::Timeout)?
+                .map_err(|e| PingError::Tls(e.to_string()))?;
+                self.call(stream, timeout.saturating_sub(start.elapsed()))
+                    .await?
+            }
+            None => {
+                self.call(stream, timeout.saturating_sub(start.elapsed()))
+                    .await?
+            }
+        };
+        trace!("grpc health check {} replied {:?}", socket_addr, reply);
+        Ok(reply)
+    }
+
+    /// 在已建立的连接上完成HTTP/2握手并发出调用
+    async fn call<T>(&self, io: T, timeout: Duration) -> Result<GrpcReply, PingError>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+    {
+        tokio::time::timeout(timeout, async {
+            let (send_request, connection) =
+                h2::client::handshake(io).await.map_err(map_h2_error)?;
+            // 连接需要同时驱动才能收发数据，调用结束后随之断开
+            tokio::select! {
+                reply = self.check(send_request) => reply,
+                result = connection => Err(result.err().map_or_else(
+                    || PingError::InvalidReply(String::from("连接已被关闭")),
+                    map_h2_error,
+                )),
+            }
+        })
+        .await
+        .map_err(|_| PingError::Timeout)?
+    }
+
+    /// 发出健康检查的请求并读取响应
+    async fn check(&self, send_request: SendRequest<Bytes>) -> Result<GrpcReply, PingError> {
+        let mut send_request = send_request.ready().await.map_err(map_h2_error)?;
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.uri)
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(())
+            .map_err(|e| PingError::InvalidReply(e.to_string()))?;
+        let (response, mut send_stream) = send_request
+            .send_request(request, false)
+            .map_err(map_h2_error)?;
+        send_stream
+            .send_data(self.request.clone(), true)
+            .map_err(map_h2_error)?;
+
+        let (parts, mut body) = response.await.map_err(map_h2_error)?.into_parts();
+        if parts.status != StatusCode::OK {
+            return Err(PingError::InvalidReply(format!(
+                "HTTP状态码为 {}",
+                parts.status
+            )));
+        }
+        // 调用失败时服务器可以只回复响应头，其中带有 grpc-status (Trailers-Only)
+        let mut message = BytesMut::new();
+        let trailers = if parts.headers.contains_key("grpc-status") {
+            parts.headers
+        } else {
+            while let Some(chunk) = body.data().await {
+                let chunk = chunk.map_err(map_h2_error)?;
+                let _ = body.flow_control().release_capacity(chunk.len());
+                if message.len() + chunk.len() > MAX_RESPONSE_SIZE {
+                    return Err(PingError::InvalidReply(format!(
+                        "响应超过 {} 字节",
+                        MAX_RESPONSE_SIZE
+                    )));
+                }
+                message.extend_from_slice(&chunk);
+            }
+            body.trailers()
+                .await
+                .map_err(map_h2_error)?
+                .unwrap_or_default()
+        };
+
+        let (grpc_status, grpc_message) = parse_status(&trailers)?;
+        let health = match grpc_status {
+            0 => Some(decode_response(&message)?),
+            GRPC_STATUS_NOT_FOUND => Some(GrpcHealthStatus::ServiceUnknown),
+            _ => None,
+        };
+        Ok(GrpcReply {
+            grpc_status,
+            grpc_message,
+            health,
+        })
+    }
+}
+
+/// 构造TLS连接器，通过ALPN协商HTTP/2
+fn tls_connector(insecure: bool) -> Result<TlsConnector, String> {
+    Ok(TlsConnector::from(Arc::new(client_config(
+        insecure,
+        &[b"h2"],
+    )?)))
+}
+
+/// HTTP/2的错误转换成探测错误，底层的IO错误保持原样
+fn map_h2_error(e: h2::Error) -> PingError {
+    if e.is_io() {
+        PingError::Io(e.into_io().unwrap())
+    } else {
+        PingError::InvalidReply(format!("HTTP/2错误: {}", e))
+    }
+}
+
+/// 编码请求消息 `HealthCheckRequest { string service = 1; }`，并加上gRPC的消息头
+fn encode_request(service: &str) -> Bytes {
+    let mut message = BytesMut::new();
+    if !service.is_empty() {
+        message.put_u8(0x0a); // 字段1，长度分隔类型
+        encode_varint(service.len() as u64, &mut message);
+        message.put_slice(service.as_bytes());
+    }
+    let mut frame = BytesMut::with_capacity(5 + message.len());
+    frame.put_u8(0); // 不压缩
+    frame.put_u32(message.len() as u32);
+    frame.put_slice(&message);
+    frame.freeze()
+}
+
+/// 解码响应消息 `HealthCheckResponse { ServingStatus status = 1; }`
+fn decode_response(frame: &[u8]) -> Result<GrpcHealthStatus, PingError> {
+    let error = |reason: &str| PingError::InvalidReply(format!("错误的健康检查响应: {}", reason));
+    if frame.len() < 5 {
+        return Err(error("缺少消息头"));
+    }
+    if frame[0] != 0 {
+        return Err(error("不支持压缩的消息"));
+    }
+    let size = u32::from_be_bytes([frame[1], frame[2], frame[3], frame[4]]) as usize;
+    let message = frame
+        .get(5..5 + size)
+        .ok_or_else(|| error("消息长度不符"))?;
+
+    // 状态为默认值 UNKNOWN 时字段会被省略
+    let mut status = 0;
+    let mut pos = 0;
+    while pos < message.len() {
+        let key = decode_varint(message, &mut pos).ok_or_else(|| error("字段格式错误"))?;
+        let skip = match key & 0x07 {
+            0 => {
+                let value =
+                    decode_varint(message, &mut pos).ok_or_else(|| error("字段格式错误"))?;
+                if key >> 3 == 1 {
+                    status = value;
+                }
+                0
+            }
+            1 => 8,
+            2 => decode_varint(message, &mut pos).ok_or_else(|| error("字段格式错误"))? as usize,
+            5 => 4,
+            _ => return Err(error("不支持的字段类型")),
+        };
+        pos = pos
+            .checked_add(skip)
+            .filter(|pos| *pos <= message.len())
+            .ok_or_else(|| error("字段长度超出消息"))?;
+    }
+    Ok(GrpcHealthStatus::from_code(status))
+}
+
+/// 读取响应尾部中的 grpc-status 及 grpc-message
+fn parse_status(trailers: &HeaderMap) -> Result<(u32, String), PingError> {
+    let grpc_status = trailers
+        .get("grpc-status")
+        .ok_or_else(|| PingError::InvalidReply(String::from("响应中没有grpc-status")))?;
+    let grpc_status = grpc_status
+        .to_str()
+        .ok()
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| PingError::InvalidReply(format!("错误的grpc-status: {:?}", grpc_status)))?;
+    let grpc_message = trailers
+        .get("grpc-message")
+        .map(|message| String::from_utf8_lossy(message.as_bytes()).into_owned())
+        .unwrap_or_default();
+    Ok((grpc_status, grpc_message))
+}
+
+/// 编码protobuf的varint
+fn encode_varint(mut value: u64, buf: &mut BytesMut) {
+    while value >= 0x80 {
+        buf.put_u8((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buf.put_u8(value as u8);
+}
+
+/// 解码protobuf的varint，格式错误时返回None
+fn decode_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 解码失败时的原因
+    fn decode_error(frame: &[u8]) -> String {
+        match decode_response(frame) {
+            Err(PingError::InvalidReply(reason)) => reason,
+            other => panic!("期望解码失败: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn request() {
+        assert_eq!(encode_request("")[..], [0, 0, 0, 0, 0]);
+        assert_eq!(
+            encode_request("svc")[..],
+            [0, 0, 0, 0, 5, 0x0a, 3, b's', b'v', b'c']
+        );
+        // 超过127字节的服务名长度占两个字节
+        let service = "a".repeat(200);
+        let request = encode_request(&service);
+        assert_eq!(request[..7], [0, 0, 0, 0, 203, 0x0a, 0xc8]);
+        assert_eq!(request[7], 0x01);
+        assert_eq!(&request[8..], service.as_bytes());
+    }
+
+    #[test]
+    fn response_status() {
+        let status = |frame: &[u8]| decode_response(frame).unwrap();
+        assert_eq!(status(&[0, 0, 0, 0, 2, 0x08, 1]), GrpcHealthStatus::Serving);
+        assert_eq!(
+            status(&[0, 0, 0, 0, 2, 0x08, 2]),
+            GrpcHealthStatus::NotServing
+        );
+        assert_eq!(
+            status(&[0, 0, 0, 0, 2, 0x08, 3]),
+            GrpcHealthStatus::ServiceUnknown
+        );
+        // UNKNOWN 是默认值，字段被省略
+        assert_eq!(status(&[0, 0, 0, 0, 0]), GrpcHealthStatus::Unknown);
+        // 不认识的状态值
+        assert_eq!(status(&[0, 0, 0, 0, 2, 0x08, 9]), GrpcHealthStatus::Unknown);
+    }
+
+    #[test]
+    fn response_skips_unknown_fields() {
+        let frame = [
+            0, 0, 0, 0, 20, //
+            0x12, 2, b'a', b'b', // 字段2，长度分隔
+            0x19, 1, 2, 3, 4, 5, 6, 7, 8, // 字段3，64位
+            0x25, 1, 2, 3, 4, // 字段4，32位
+            0x08, 1,
+        ];
+        assert_eq!(decode_response(&frame).unwrap(), GrpcHealthStatus::Serving);
+    }
+
+    #[test]
+    fn invalid_response() {
+        let error = |reason: &str| format!("错误的健康检查响应: {}", reason);
+        assert_eq!(decode_error(&[0, 0, 0]), error("缺少消息头"));
+        assert_eq!(
+            decode_error(&[1, 0, 0, 0, 2, 0x08, 1]),
+            error("不支持压缩的消息")
+        );
+        // 消息头中的长度超过实际收到的数据
+        assert_eq!(
+            decode_error(&[0, 0, 0, 0, 5, 0x08, 1]),
+            error("消息长度不符")
+        );
+        // 字段类型3(group)不支持
+        assert_eq!(
+            decode_error(&[0, 0, 0, 0, 1, 0x0b]),
+            error("不支持的字段类型")
+        );
+        // 长度分隔的字段超出消息
+        assert_eq!(
+            decode_error(&[0, 0, 0, 0, 3, 0x12, 10, b'a']),
+            error("字段长度超出消息")
+        );
+        assert_eq!(
+            decode_error(&[0, 0, 0, 0, 2, 0x08, 0x80]),
+            error("字段格式错误")
+        );
+        let mut frame = vec![0, 0, 0, 0, 11];
+        frame.extend([0x80; 10]);
+        frame.push(0);
+        assert_eq!(decode_error(&frame), error("字段格式错误"));
+    }
+
+    #[test]
+    fn varint() {
+        let decode = |buf: &[u8]| {
+            let mut pos = 0;
+            decode_varint(buf, &mut pos).map(|value| (value, pos))
+        };
+        assert_eq!(decode(&[0x00]), Some((0, 1)));
+        assert_eq!(decode(&[0x96, 0x01, 0xff]), Some((150, 2)));
+        assert_eq!(
+            decode(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+            Some((u64::MAX, 10))
+        );
+        // 缺少结束字节
+        assert_eq!(decode(&[0x80]), None);
+        assert_eq!(decode(&[]), None);
+        // 超过10个字节
+        assert_eq!(decode(&[0x80; 11]), None);
+        for value in [0, 1, 127, 128, 300, 16384, u32::MAX as u64, u64::MAX] {
+            let mut buf = BytesMut::new();
+            encode_varint(value, &mut buf);
+            assert_eq!(decode(&buf), Some((value, buf.len())));
+        }
+    }
+}
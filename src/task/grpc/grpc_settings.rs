@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// gRPC任务配置
+///
+/// 调用标准的健康检查接口 `grpc.health.v1.Health/Check`，
+/// 服务状态为 SERVING 时探测成功，其它状态或调用失败时探测失败。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct GrpcSettings {
+    /// 要检查的服务名，为空时检查服务器的整体状态
+    #[serde(default)]
+    pub service: String,
+    /// 是否使用TLS连接，默认为明文的HTTP/2(h2c)
+    #[serde(default)]
+    pub tls: bool,
+    /// TLS握手时发送的SNI，默认为目标的主机名
+    pub tls_name: Option<String>,
+    /// 是否跳过证书校验，用于自签名证书的服务
+    #[serde(default)]
+    pub insecure: bool,
+}
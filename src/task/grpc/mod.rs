@@ -0,0 +1,3 @@
+pub mod grpc_executor;
+pub mod grpc_ping;
+pub mod grpc_settings;
@@ -0,0 +1,274 @@
+use crate::task::http::http_settings::{ExpectStatus, HttpAssertionSettings};
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName};
+use reqwest::StatusCode;
+use serde_json_path::JsonPath;
+use std::ops::RangeInclusive;
+
+/// 合法的状态码范围
+const STATUS_CODES: RangeInclusive<u16> = 100..=599;
+
+/// 编译后的HTTP响应断言
+#[derive(Debug, Clone)]
+pub struct HttpAssertions {
+    /// 允许的状态码范围
+    status: Vec<RangeInclusive<u16>>,
+    /// 响应体必须匹配的正则表达式
+    body_matches: Vec<Regex>,
+    /// 响应体不能匹配的正则表达式
+    body_not_matches: Vec<Regex>,
+    /// JSONPath表达式及期望的值
+    json_path: Vec<(JsonPath, Option<serde_json::Value>)>,
+    /// 必须存在的响应头及其值匹配的正则表达式
+    headers: Vec<(HeaderName, Option<Regex>)>,
+    /// 响应体的最大字节数
+    max_body_size: Option<usize>,
+}
+
+impl HttpAssertions {
+    /// 根据配置编译断言
+    ///
+    /// # 返回值
+    /// 状态码、响应头名称、正则表达式或JSONPath格式错误时返回错误信息
+    pub fn new(settings: &HttpAssertionSettings) -> Result<Self, String> {
+        let status = if settings.expect_status.is_empty() {
+            vec![200..=299]
+        } else {
+            settings
+                .expect_status
+                .iter()
+                .map(parse_status)
+                .collect::<Result<_, _>>()?
+        };
+        let json_path = settings
+            .json_path
+            .iter()
+            .map(|assertion| {
+                JsonPath::parse(&assertion.path)
+                    .map(|path| (path, assertion.value.clone()))
+                    .map_err(|e| format!("错误的JSONPath({}): {}", assertion.path, e))
+            })
+            .collect::<Result<_, _>>()?;
+        let headers = settings
+            .expect_headers
+            .iter()
+            .map(|(name, value)| {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|e| format!("错误的响应头名称({}): {}", name, e))?;
+                let regex = (!value.is_empty()).then(|| compile(value)).transpose()?;
+                Ok((name, regex))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            status,
+            body_matches: settings
+                .body_matches
+                .iter()
+                .map(|r| compile(r))
+                .collect::<Result<_, _>>()?,
+            body_not_matches: settings
+                .body_not_matches
+                .iter()
+                .map(|r| compile(r))
+                .collect::<Result<_, _>>()?,
+            json_path,
+            headers,
+            max_body_size: settings.max_body_size,
+        })
+    }
+
+    /// 响应体的最大字节数
+    pub fn max_body_size(&self) -> Option<usize> {
+        self.max_body_size
+    }
+
+    /// 检查响应
+    ///
+    /// # 参数
+    /// * `body_size` - 响应体的实际字节数，超过最大字节数时 `body` 只包含读到的部分
+    ///
+    /// # 返回值
+    /// 第一个不满足的断言的描述
+    pub fn check(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+        body_size: usize,
+    ) -> Result<(), String> {
+        if !self
+            .status
+            .iter()
+            .any(|range| range.contains(&status.as_u16()))
+        {
+            return Err(format!("状态码 {} 不在允许的范围内", status));
+        }
+        if let Some(max_body_size) = self.max_body_size
+            && body_size > max_body_size
+        {
+            return Err(format!("响应体超过 {} 字节", max_body_size));
+        }
+        for (name, regex) in self.headers.iter() {
+            let value = headers
+                .get(name)
+                .ok_or_else(|| format!("缺少响应头 {}", name))?;
+            if let Some(regex) = regex
+                && !regex.is_match(&String::from_utf8_lossy(value.as_bytes()))
+            {
+                return Err(format!("响应头 {}: {:?} 未匹配 /{}/", name, value, regex));
+            }
+        }
+
+        let text = String::from_utf8_lossy(body);
+        if let Some(regex) = self
+            .body_matches
+            .iter()
+            .find(|regex| !regex.is_match(&text))
+        {
+            return Err(format!("响应体未匹配 /{}/", regex));
+        }
+        if let Some(regex) = self
+            .body_not_matches
+            .iter()
+            .find(|regex| regex.is_match(&text))
+        {
+            return Err(format!("响应体匹配了 /{}/", regex));
+        }
+        if !self.json_path.is_empty() {
+            let json: serde_json::Value =
+                serde_json::from_slice(body).map_err(|e| format!("响应体不是合法的JSON: {}", e))?;
+            for (path, expected) in self.json_path.iter() {
+                let nodes = path.query(&json).all();
+                match (nodes.first(), expected) {
+                    (None, _) => return Err(format!("JSONPath {} 没有匹配到任何节点", path)),
+                    (Some(actual), Some(expected)) if *actual != expected => {
+                        return Err(format!(
+                            "JSONPath {} 的值为 {}，期望为 {}",
+                            path, actual, expected
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 编译正则表达式
+fn compile(regex: &str) -> Result<Regex, String> {
+    Regex::new(regex).map_err(|e| format!("错误的正则表达式({}): {}", regex, e))
+}
+
+/// 解析允许的状态码，如 `204`、`200-399` 或 `2xx`，状态码只能在100到599之间
+fn parse_status(status: &ExpectStatus) -> Result<RangeInclusive<u16>, String> {
+    let status = match status {
+        ExpectStatus::Code(code) => {
+            return check_status(*code..=*code).ok_or_else(|| format!("错误的状态码: {}", code));
+        }
+        ExpectStatus::Pattern(pattern) => pattern.trim(),
+    };
+    let error = || format!("错误的状态码: {}", status);
+    if let Some(class) = status
+        .strip_suffix("xx")
+        .or_else(|| status.strip_suffix("XX"))
+    {
+        // 只有1xx到5xx，其它的值乘以100可能溢出
+        let class: u16 = class
+            .parse()
+            .ok()
+            .filter(|class| (1..=5).contains(class))
+            .ok_or_else(error)?;
+        return Ok(class * 100..=class * 100 + 99);
+    }
+    match status.split_once('-') {
+        Some((start, end)) => {
+            let start = start.trim().parse().map_err(|_| error())?;
+            let end = end.trim().parse().map_err(|_| error())?;
+            check_status(start..=end).ok_or_else(error)
+        }
+        None => {
+            let code = status.parse().map_err(|_| error())?;
+            check_status(code..=code).ok_or_else(error)
+        }
+    }
+}
+
+/// 检查状态码范围，起止颠倒或超出100到599时返回 `None`
+fn check_status(range: RangeInclusive<u16>) -> Option<RangeInclusive<u16>> {
+    (range.start() <= range.end()
+        && STATUS_CODES.contains(range.start())
+        && STATUS_CODES.contains(range.end()))
+    .then_some(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(status: &str) -> Result<RangeInclusive<u16>, String> {
+        parse_status(&ExpectStatus::Pattern(status.to_string()))
+    }
+
+    #[test]
+    fn status_patterns() {
+        assert_eq!(parse_status(&ExpectStatus::Code(204)), Ok(204..=204));
+        assert_eq!(pattern("204"), Ok(204..=204));
+        assert_eq!(pattern(" 200 - 399 "), Ok(200..=399));
+        assert_eq!(pattern("1xx"), Ok(100..=199));
+        assert_eq!(pattern("5XX"), Ok(500..=599));
+    }
+
+    #[test]
+    fn invalid_status_class() {
+        for status in ["999xx", "656xx", "0xx", "6xx", "xx", "-1xx", "2x"] {
+            assert_eq!(pattern(status), Err(format!("错误的状态码: {}", status)));
+        }
+    }
+
+    #[test]
+    fn invalid_status_range() {
+        for status in ["399-200", "0-299", "200-600", "99", "600", "200-", "-"] {
+            assert_eq!(pattern(status), Err(format!("错误的状态码: {}", status)));
+        }
+        assert_eq!(pattern("100-599"), Ok(100..=599));
+        for code in [0, 99, 600, 999] {
+            assert_eq!(
+                parse_status(&ExpectStatus::Code(code)),
+                Err(format!("错误的状态码: {}", code))
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_header_name() {
+        for name in ["", "X Trace", "X-Trace:", "Ümlaut"] {
+            let settings = HttpAssertionSettings {
+                expect_headers: [(name.to_string(), String::new())].into(),
+                ..Default::default()
+            };
+            let error = HttpAssertions::new(&settings).unwrap_err();
+            assert!(
+                error.starts_with(&format!("错误的响应头名称({})", name)),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn header_name_case_insensitive() {
+        let settings = HttpAssertionSettings {
+            expect_headers: [("X-Trace-Id".to_string(), "^[0-9a-f]+$".to_string())].into(),
+            ..Default::default()
+        };
+        let assertions = HttpAssertions::new(&settings).unwrap();
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            assertions.check(StatusCode::OK, &headers, b"", 0),
+            Err("缺少响应头 x-trace-id".to_string())
+        );
+        headers.insert("x-trace-id", "00ff".parse().unwrap());
+        assert_eq!(assertions.check(StatusCode::OK, &headers, b"", 0), Ok(()));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::task::http::http_settings::HttpClientSettings;
+use crate::task::http::http_timing::{
+    SharedRecorder, TimingLayer, TimingResolver, TimingSessionStore,
+};
+use reqwest::redirect::Policy;
+use reqwest::{Client, ClientBuilder, Proxy};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::{Resumption, WantsClientCert};
+use rustls::crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, ConfigBuilder, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::sync::Arc;
+
+/// 不校验证书的校验器，握手消息的签名仍然校验
+#[derive(Debug)]
+pub struct InsecureVerifier {
+    provider: Arc<CryptoProvider>,
+}
+
+impl InsecureVerifier {
+    /// 构造函数
+    /// # 参数
+    /// * `provider` - 校验握手消息签名使用的加密库
+    pub fn new(provider: Arc<CryptoProvider>) -> Self {
+        Self { provider }
+    }
+}
+
+impl ServerCertVerifier for InsecureVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// 构造还未配置客户端证书的TLS配置
+///
+/// # 参数
+/// * `insecure` - 是否跳过证书校验
+/// * `roots` - 校验证书使用的根证书，跳过证书校验时不使用
+fn config_builder(
+    insecure: bool,
+    roots: RootCertStore,
+) -> Result<ConfigBuilder<ClientConfig, WantsClientCert>, String> {
+    let provider = Arc::new(ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?;
+    Ok(if insecure {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(InsecureVerifier::new(provider)))
+    } else {
+        builder.with_root_certificates(roots)
+    })
+}
+
+/// 构造不带客户端证书的TLS配置，以内置的根证书校验证书
+///
+/// # 参数
+/// * `insecure` - 是否跳过证书校验
+/// * `alpn` - 通过ALPN协商的协议，为空时不协商
+pub fn client_config(insecure: bool, alpn: &[&[u8]]) -> Result<ClientConfig, String> {
+    let mut config = config_builder(insecure, webpki_roots())?.with_no_client_auth();
+    config.alpn_protocols = alpn.iter().map(|protocol| protocol.to_vec()).collect();
+    Ok(config)
+}
+
+/// 内置的根证书
+fn webpki_roots() -> RootCertStore {
+    RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    }
+}
+
+/// 构造TLS配置
+///
+/// 会话缓存用于记录TLS握手开始的时间，同时禁用了会话复用
+fn build_tls_config(
+    settings: &HttpClientSettings,
+    recorder: &SharedRecorder,
+) -> Result<ClientConfig, String> {
+    let mut roots = webpki_roots();
+    if !settings.insecure
+        && let Some(ca_file) = &settings.ca_file
+    {
+        let certs = CertificateDer::pem_file_iter(ca_file)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("读取CA证书文件({})失败: {}", ca_file, e))?;
+        let (_, ignored) = roots.add_parsable_certificates(certs);
+        if ignored > 0 {
+            return Err(format!("CA证书文件({})中有无法解析的证书", ca_file));
+        }
+    }
+    let builder = config_builder(settings.insecure, roots)?;
+
+    let mut config = match (&settings.client_cert, &settings.client_key) {
+        (Some(cert_file), Some(key_file)) => {
+            let certs = CertificateDer::pem_file_iter(cert_file)
+                .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("读取客户端证书文件({})失败: {}", cert_file, e))?;
+            let key = PrivateKeyDer::from_pem_file(key_file)
+                .map_err(|e| format!("读取客户端私钥文件({})失败: {}", key_file, e))?;
+            builder
+                .with_client_auth_cert(certs, key)
+                .map_err(|e| format!("客户端证书或私钥错误: {}", e))?
+        }
+        (None, None) => builder.with_no_client_auth(),
+        _ => return Err(String::from("client-cert和client-key须同时配置")),
+    };
+    config.resumption = Resumption::store(Arc::new(TimingSessionStore::new(recorder.clone())));
+    Ok(config)
+}
+
+/// 构造HTTP客户端
+///
+/// 客户端不复用连接，每次请求都重新解析、连接和握手，各阶段的耗时才有意义
+///
+/// # 参数
+/// * `settings` - 客户端配置
+/// * `recorder` - 建立连接各阶段的打点记录
+///
+/// # 返回值
+/// 证书文件、代理地址等配置错误时返回错误信息
+pub fn client_builder(
+    settings: &HttpClientSettings,
+    recorder: &SharedRecorder,
+) -> Result<ClientBuilder, String> {
+    let redirect = if settings.follow_redirects {
+        Policy::limited(settings.max_redirects)
+    } else {
+        Policy::none()
+    };
+    let mut builder = Client::builder()
+        .pool_max_idle_per_host(0)
+        .redirect(redirect)
+        .dns_resolver(Arc::new(TimingResolver::new(recorder.clone())))
+        .connector_layer(TimingLayer::new(recorder.clone()))
+        .use_preconfigured_tls(build_tls_config(settings, recorder)?);
+    if let Some(proxy) = &settings.proxy {
+        builder = builder
+            .proxy(Proxy::all(proxy).map_err(|e| format!("错误的代理地址({}): {}", proxy, e))?);
+    }
+    Ok(builder)
+}
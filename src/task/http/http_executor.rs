@@ -1,6 +1,13 @@
-use crate::executor::Executor;
+use crate::executor::{ExecReport, Executor};
+use crate::metrics::metrics_cst::{
+    HTTP_BODY_SIZE_PROMETHEUS_METRIC_DESC, HTTP_BODY_SIZE_PROMETHEUS_METRIC_NAME,
+    HTTP_STATUS_CODE_PROMETHEUS_METRIC_DESC, HTTP_STATUS_CODE_PROMETHEUS_METRIC_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
 use crate::ping_error::PingError;
+use crate::task::http::http_assertions::HttpAssertions;
 use crate::task::http::http_ping::HttpPing;
+use crate::task::http::http_settings::HttpSettings;
 use async_trait::async_trait;
 use log::trace;
 use std::time::Duration;
@@ -9,6 +16,7 @@ use std::time::Duration;
 pub struct HttpExecutor {
     urn: String,
     http_ping: HttpPing,
+    assertions: HttpAssertions,
     timeout: Duration,
 }
 
@@ -17,11 +25,17 @@ impl HttpExecutor {
     /// # 参数
     /// * `urn` - 要请求的URN地址，格式为 `<method>:<url>`，例如: `GET:http://127.0.0.1:8080`
     /// * `timeout` - 一个 `Duration`，表示超时时间
-    pub fn new(urn: String, timeout: Duration) -> Self {
-        let http_ping = HttpPing::new(urn.clone());
+    /// * `settings` - HTTP任务配置
+    ///
+    /// # Panics
+    /// 配置错误时会触发panic，配置在加载时已经检查过
+    pub fn new(urn: String, timeout: Duration, settings: HttpSettings) -> Self {
+        let http_ping = HttpPing::new(urn.clone(), &settings)
+            .unwrap_or_else(|e| panic!("HTTP任务的配置错误({}): {}", urn, e));
 
         Self {
             http_ping,
+            assertions: HttpAssertions::new(&settings.assertions).unwrap(),
             urn,
             timeout,
         }
@@ -34,8 +48,34 @@ impl Executor for HttpExecutor {
         String::from("HTTP")
     }
 
-    async fn exec(&self) -> Result<(), PingError> {
+    async fn exec(&self) -> Result<ExecReport, PingError> {
         trace!("开始执行 HTTP 任务: ping {}", self.urn);
-        self.http_ping.ping(self.timeout).await
+        let reply = self
+            .http_ping
+            .ping(self.timeout, self.assertions.max_body_size())
+            .await?;
+        let mut metrics = vec![
+            ProbeMetric::new(
+                HTTP_STATUS_CODE_PROMETHEUS_METRIC_NAME,
+                HTTP_STATUS_CODE_PROMETHEUS_METRIC_DESC,
+                reply.status.as_u16() as f64,
+            ),
+            ProbeMetric::new(
+                HTTP_BODY_SIZE_PROMETHEUS_METRIC_NAME,
+                HTTP_BODY_SIZE_PROMETHEUS_METRIC_DESC,
+                reply.body_size as f64,
+            ),
+        ];
+        metrics.extend(reply.timings.to_metrics());
+        let error = self
+            .assertions
+            .check(reply.status, &reply.headers, &reply.body, reply.body_size)
+            .err()
+            .map(PingError::InvalidReply);
+        Ok(ExecReport {
+            elapsed: None,
+            error,
+            metrics,
+        })
     }
 }
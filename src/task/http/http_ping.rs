@@ -1,46 +1,198 @@
 use crate::ping_error::PingError;
+use crate::task::http::http_client::client_builder;
+use crate::task::http::http_settings::{HttpRequestSettings, HttpSettings};
+use crate::task::http::http_timing::{HttpTimings, PhaseRecorder, SharedRecorder};
 use log::trace;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::Client;
 use reqwest::Method;
+use reqwest::RequestBuilder;
+use reqwest::StatusCode;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::time::Instant;
 use wheel_rs::urn_utils::Urn;
 
+/// HTTP响应
+#[derive(Debug)]
+pub struct HttpReply {
+    /// 状态码
+    pub status: StatusCode,
+    /// 响应头
+    pub headers: HeaderMap,
+    /// 响应体，超过最大字节数时只包含读到的部分
+    pub body: Vec<u8>,
+    /// 读到的响应体字节数，超过最大字节数时停止读取
+    pub body_size: usize,
+    /// 各阶段的耗时
+    pub timings: HttpTimings,
+}
+
+/// 预先准备好的请求内容
+#[derive(Debug, Clone, Default)]
+pub struct PreparedRequest {
+    /// 请求头
+    headers: HeaderMap,
+    /// 请求体
+    body: Option<Vec<u8>>,
+    /// Basic认证的用户名和密码
+    basic_auth: Option<(String, Option<String>)>,
+    /// Bearer认证的令牌
+    bearer_token: Option<String>,
+}
+
+impl PreparedRequest {
+    /// 根据配置准备请求内容，请求体文件在此时读取
+    ///
+    /// # 返回值
+    /// 请求头格式错误或请求体文件读取失败时返回错误信息
+    pub fn new(settings: &HttpRequestSettings) -> Result<Self, String> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in settings.headers.iter() {
+            headers.insert(
+                HeaderName::from_str(name).map_err(|e| format!("错误的请求头({}): {}", name, e))?,
+                HeaderValue::from_str(value)
+                    .map_err(|e| format!("错误的请求头({}): {}", name, e))?,
+            );
+        }
+        let body = match (&settings.body, &settings.body_file) {
+            (Some(_), Some(_)) => return Err(String::from("body和body-file不能同时配置")),
+            (Some(body), None) => Some(body.clone().into_bytes()),
+            (None, Some(file)) => Some(
+                std::fs::read(file).map_err(|e| format!("读取请求体文件({})失败: {}", file, e))?,
+            ),
+            (None, None) => None,
+        };
+        Ok(Self {
+            headers,
+            body,
+            basic_auth: settings
+                .basic_auth
+                .as_ref()
+                .map(|auth| (auth.username.clone(), auth.password.clone())),
+            bearer_token: settings.bearer_token.clone(),
+        })
+    }
+
+    /// 将请求内容应用到请求上
+    pub fn apply(&self, mut request: RequestBuilder) -> RequestBuilder {
+        request = request.headers(self.headers.clone());
+        if let Some(body) = &self.body {
+            request = request.body(body.clone());
+        }
+        if let Some((username, password)) = &self.basic_auth {
+            request = request.basic_auth(username, password.as_ref());
+        }
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+        request
+    }
+}
+
 #[derive(Clone)]
 pub struct HttpPing {
     client: Client,
     method: Method,
     url: String,
+    /// 请求内容
+    request: PreparedRequest,
+    /// 建立连接各阶段的打点记录
+    recorder: SharedRecorder,
 }
 impl HttpPing {
-    pub fn new(urn: String) -> Self {
+    /// 构造函数
+    /// # 参数
+    /// * `urn` - 要请求的URN地址
+    /// * `settings` - HTTP任务配置
+    ///
+    /// # 返回值
+    /// 证书文件、代理地址、请求头等配置错误时返回错误信息
+    pub fn new(urn: String, settings: &HttpSettings) -> Result<Self, String> {
         let urn = Urn::new(urn);
-        HttpPing {
-            client: Client::builder().build().unwrap(),
+        let recorder: SharedRecorder = Arc::new(Mutex::new(PhaseRecorder::default()));
+        let client = client_builder(&settings.client, &recorder)?
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(HttpPing {
+            client,
             method: Method::from_str(urn.method.to_string().as_str()).unwrap(),
             url: urn.url,
-        }
+            request: PreparedRequest::new(&settings.request)?,
+            recorder,
+        })
     }
 
-    pub async fn ping(&self, timeout: Duration) -> Result<(), PingError> {
+    /// 发出请求并读取响应
+    ///
+    /// # 参数
+    /// * `timeout` - 包括读取响应体在内的超时时间
+    /// * `max_body_size` - 最多读取的响应体字节数，读到超过它时停止读取
+    pub async fn ping(
+        &self,
+        timeout: Duration,
+        max_body_size: Option<usize>,
+    ) -> Result<HttpReply, PingError> {
         trace!("ping {}:{} ....", self.method, self.url);
+        let request = self
+            .request
+            .apply(self.client.request(self.method.clone(), &self.url))
+            .timeout(timeout);
+        let reply = fetch(request, &self.recorder, max_body_size).await?;
+        trace!(
+            "ping {}:{} responded {} {:?}",
+            self.method,
+            self.url,
+            reply.status,
+            reply.timings
+        );
+        Ok(reply)
+    }
+}
+
+/// 发出请求并读取响应，同时统计各阶段的耗时
+///
+/// # 参数
+/// * `request` - 要发出的请求，其客户端须使用 `recorder` 打点
+/// * `recorder` - 建立连接各阶段的打点记录
+/// * `max_body_size` - 最多读取的响应体字节数，读到超过它时停止读取
+pub async fn fetch(
+    request: RequestBuilder,
+    recorder: &SharedRecorder,
+    max_body_size: Option<usize>,
+) -> Result<HttpReply, PingError> {
+    recorder.lock().unwrap().reset();
+
+    let start = Instant::now();
+    let mut response = request.send().await?;
+    let headers_elapsed = start.elapsed();
+    let status = response.status();
+    let headers = response.headers().clone();
 
-        // 发出Http请求，并判断返回状态是否是200
-        let response = self
-            .client
-            .request(self.method.clone(), &self.url)
-            .timeout(timeout)
-            .send()
-            .await?;
-
-        if response.status().is_success() {
-            trace!("ping {}:{} success", self.method, self.url);
-            Ok(())
-        } else {
-            Err(PingError::InvalidReply(format!(
-                "HTTP request failed with status: {}",
-                response.status()
-            )))
+    // 逐块读取响应体，超过最大字节数时不再继续读取
+    let mut body = vec![];
+    let mut body_size = 0;
+    while let Some(chunk) = response.chunk().await? {
+        body_size += chunk.len();
+        if let Some(max_body_size) = max_body_size
+            && body_size > max_body_size
+        {
+            break;
         }
+        body.extend_from_slice(&chunk);
     }
+
+    // 收到响应头之前的时间扣除建立连接的耗时，就是首字节时间
+    let mut timings = recorder.lock().unwrap().timings();
+    timings.ttfb = headers_elapsed.saturating_sub(timings.dns + timings.connect + timings.tls);
+    timings.transfer = start.elapsed() - headers_elapsed;
+
+    Ok(HttpReply {
+        status,
+        headers,
+        body,
+        body_size,
+        timings,
+    })
 }
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// HTTP任务配置
+///
+/// 除了客户端和请求的配置，默认只要求状态码为2xx，可以另外配置对状态码、响应体和响应头的断言，
+/// 任一断言不满足时探测失败，如健康检查接口返回200但响应体为 `{"status":"DOWN"}`。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct HttpSettings {
+    /// 客户端的配置
+    #[serde(flatten)]
+    pub client: HttpClientSettings,
+    /// 请求的配置
+    #[serde(flatten)]
+    pub request: HttpRequestSettings,
+    /// 对响应的断言
+    #[serde(flatten)]
+    pub assertions: HttpAssertionSettings,
+}
+
+/// HTTP响应的断言配置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct HttpAssertionSettings {
+    /// 允许的状态码，支持单个状态码(`204`)、范围(`200-399`)和类别(`2xx`)，默认为 `2xx`
+    #[serde(default)]
+    pub expect_status: Vec<ExpectStatus>,
+    /// 响应体必须匹配的正则表达式
+    #[serde(default)]
+    pub body_matches: Vec<String>,
+    /// 响应体不能匹配的正则表达式
+    #[serde(default)]
+    pub body_not_matches: Vec<String>,
+    /// 对JSON响应体的断言
+    #[serde(default)]
+    pub json_path: Vec<JsonPathAssertion>,
+    /// 必须存在的响应头及其值匹配的正则表达式，值为空时只要求响应头存在
+    #[serde(default)]
+    pub expect_headers: HashMap<String, String>,
+    /// 响应体的最大字节数，超过时探测失败
+    pub max_body_size: Option<usize>,
+}
+
+/// HTTP客户端配置，对任务的所有请求生效
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct HttpClientSettings {
+    /// 是否跟随重定向
+    #[serde(default = "follow_redirects_default")]
+    pub follow_redirects: bool,
+    /// 最多跟随的重定向次数
+    #[serde(default = "max_redirects_default")]
+    pub max_redirects: usize,
+    /// 代理地址，支持 `http://`、`https://`、`socks5://` 和 `socks5h://`
+    pub proxy: Option<String>,
+    /// 额外信任的CA证书文件(PEM格式)，可以包含多个证书
+    pub ca_file: Option<String>,
+    /// 客户端证书文件(PEM格式)，用于双向TLS认证，须同时配置 `client-key`
+    pub client_cert: Option<String>,
+    /// 客户端证书的私钥文件(PEM格式)
+    pub client_key: Option<String>,
+    /// 是否跳过服务端证书的校验
+    #[serde(default)]
+    pub insecure: bool,
+}
+
+fn follow_redirects_default() -> bool {
+    true // 默认跟随重定向
+}
+fn max_redirects_default() -> usize {
+    10 // 默认最多 10 次
+}
+
+impl Default for HttpClientSettings {
+    fn default() -> Self {
+        Self {
+            follow_redirects: follow_redirects_default(),
+            max_redirects: max_redirects_default(),
+            proxy: None,
+            ca_file: None,
+            client_cert: None,
+            client_key: None,
+            insecure: false,
+        }
+    }
+}
+
+/// HTTP请求配置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct HttpRequestSettings {
+    /// 请求头
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// 请求体
+    pub body: Option<String>,
+    /// 从文件读取请求体，与 `body` 二选一
+    pub body_file: Option<String>,
+    /// Basic认证
+    pub basic_auth: Option<BasicAuth>,
+    /// Bearer认证的令牌
+    pub bearer_token: Option<String>,
+}
+
+/// Basic认证
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct BasicAuth {
+    /// 用户名
+    pub username: String,
+    /// 密码
+    pub password: Option<String>,
+}
+
+/// 允许的状态码
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum ExpectStatus {
+    /// 单个状态码，如 `204`
+    Code(u16),
+    /// 范围或类别，如 `200-399`、`2xx`
+    Pattern(String),
+}
+
+/// JSONPath断言
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct JsonPathAssertion {
+    /// JSONPath表达式(RFC 9535)，如 `$.status`
+    pub path: String,
+    /// 期望的值，为空时只要求表达式能匹配到节点
+    pub value: Option<serde_json::Value>,
+}
@@ -0,0 +1,247 @@
+use crate::metrics::metrics_cst::{
+    HTTP_DURATION_PROMETHEUS_METRIC_DESC, HTTP_DURATION_PROMETHEUS_METRIC_NAME,
+    PHASE_PROMETHEUS_METRIC_LABEL_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use rustls::client::{ClientSessionStore, Tls12ClientSessionValue, Tls13ClientSessionValue};
+use rustls::pki_types::ServerName;
+use rustls::NamedGroup;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Instant;
+use tower::{Layer, Service};
+
+/// HTTP请求各阶段的耗时
+///
+/// 有重定向时各阶段的耗时是所有请求的累加
+#[derive(Debug, Clone, Default)]
+pub struct HttpTimings {
+    /// DNS解析
+    pub dns: Duration,
+    /// 建立TCP连接
+    pub connect: Duration,
+    /// TLS握手
+    pub tls: Duration,
+    /// 从连接就绪到收到响应头(首字节时间)
+    pub ttfb: Duration,
+    /// 读取响应体
+    pub transfer: Duration,
+}
+
+impl HttpTimings {
+    /// 各阶段的名称及耗时
+    pub fn phases(&self) -> [(&'static str, Duration); 5] {
+        [
+            ("dns", self.dns),
+            ("connect", self.connect),
+            ("tls", self.tls),
+            ("ttfb", self.ttfb),
+            ("transfer", self.transfer),
+        ]
+    }
+
+    /// 转换成探测指标，每个阶段一个 `phase` 标签
+    pub fn to_metrics(&self) -> Vec<ProbeMetric> {
+        self.phases()
+            .into_iter()
+            .map(|(phase, duration)| {
+                ProbeMetric::new(
+                    HTTP_DURATION_PROMETHEUS_METRIC_NAME,
+                    HTTP_DURATION_PROMETHEUS_METRIC_DESC,
+                    duration.as_secs_f64() * 1000.0,
+                )
+                .with_label(PHASE_PROMETHEUS_METRIC_LABEL_NAME, phase)
+            })
+            .collect()
+    }
+}
+
+/// 建立连接过程中各个时间点的记录
+///
+/// reqwest 没有暴露建立连接的各个阶段，这里分别在DNS解析器、连接器和TLS会话缓存中打点：
+/// 连接器包住了整个建立连接的过程，DNS解析器给出解析的起止时间，
+/// TLS会话缓存在构造 ClientHello 时被查询，标志着TCP连接完成、TLS握手开始。
+#[derive(Debug, Default)]
+pub struct PhaseRecorder {
+    /// 已经累计的耗时
+    timings: HttpTimings,
+    /// 当前连接开始建立的时间
+    connect_start: Option<Instant>,
+    /// 当前连接DNS解析完成的时间
+    dns_end: Option<Instant>,
+    /// 当前连接TLS握手开始的时间
+    tls_start: Option<Instant>,
+}
+
+impl PhaseRecorder {
+    /// 清空记录，每次请求前调用
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 取出累计的建立连接的耗时，响应相关的耗时由调用者填写
+    pub fn timings(&self) -> HttpTimings {
+        self.timings.clone()
+    }
+
+    /// 上一个阶段结束的时间
+    fn last_mark(&self) -> Option<Instant> {
+        self.dns_end.or(self.connect_start)
+    }
+
+    fn on_connect_start(&mut self) {
+        self.connect_start = Some(Instant::now());
+        self.dns_end = None;
+        self.tls_start = None;
+    }
+
+    fn on_dns(&mut self, start: Instant) {
+        let now = Instant::now();
+        self.timings.dns += now - start;
+        self.dns_end = Some(now);
+    }
+
+    fn on_tls_start(&mut self) {
+        let now = Instant::now();
+        if let Some(mark) = self.last_mark() {
+            self.timings.connect += now - mark;
+        }
+        self.tls_start = Some(now);
+    }
+
+    fn on_connect_end(&mut self) {
+        let now = Instant::now();
+        match (self.tls_start, self.last_mark()) {
+            (Some(tls_start), _) => self.timings.tls += now - tls_start,
+            (None, Some(mark)) => self.timings.connect += now - mark,
+            (None, None) => {}
+        }
+        self.connect_start = None;
+    }
+}
+
+/// 共享的打点记录
+pub type SharedRecorder = Arc<Mutex<PhaseRecorder>>;
+
+/// 记录解析耗时的DNS解析器，使用系统的解析器
+#[derive(Debug, Clone)]
+pub struct TimingResolver {
+    recorder: SharedRecorder,
+}
+
+impl TimingResolver {
+    pub fn new(recorder: SharedRecorder) -> Self {
+        Self { recorder }
+    }
+}
+
+impl Resolve for TimingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let recorder = self.recorder.clone();
+        Box::pin(async move {
+            let start = Instant::now();
+            let result = tokio::net::lookup_host((name.as_str(), 0)).await;
+            recorder.lock().unwrap().on_dns(start);
+            let addrs: Vec<SocketAddr> = result?.collect();
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// 记录建立连接起止时间的连接器层
+#[derive(Debug, Clone)]
+pub struct TimingLayer {
+    recorder: SharedRecorder,
+}
+
+impl TimingLayer {
+    pub fn new(recorder: SharedRecorder) -> Self {
+        Self { recorder }
+    }
+}
+
+impl<S> Layer<S> for TimingLayer {
+    type Service = TimingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimingService {
+            inner,
+            recorder: self.recorder.clone(),
+        }
+    }
+}
+
+/// 记录建立连接起止时间的连接器
+#[derive(Debug, Clone)]
+pub struct TimingService<S> {
+    inner: S,
+    recorder: SharedRecorder,
+}
+
+impl<S, Request> Service<Request> for TimingService<S>
+where
+    S: Service<Request>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        self.recorder.lock().unwrap().on_connect_start();
+        let recorder = self.recorder.clone();
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let result = future.await;
+            recorder.lock().unwrap().on_connect_end();
+            result
+        })
+    }
+}
+
+/// 记录TLS握手开始时间的会话缓存
+///
+/// 不保存任何会话，每次都完整握手，以便测出真实的握手耗时
+#[derive(Debug)]
+pub struct TimingSessionStore {
+    recorder: SharedRecorder,
+}
+
+impl TimingSessionStore {
+    pub fn new(recorder: SharedRecorder) -> Self {
+        Self { recorder }
+    }
+}
+
+impl ClientSessionStore for TimingSessionStore {
+    fn set_kx_hint(&self, _: ServerName<'static>, _: NamedGroup) {}
+
+    /// 构造 ClientHello 时第一个被查询的方法
+    fn kx_hint(&self, _: &ServerName<'_>) -> Option<NamedGroup> {
+        self.recorder.lock().unwrap().on_tls_start();
+        None
+    }
+
+    fn set_tls12_session(&self, _: ServerName<'static>, _: Tls12ClientSessionValue) {}
+
+    fn tls12_session(&self, _: &ServerName<'_>) -> Option<Tls12ClientSessionValue> {
+        None
+    }
+
+    fn remove_tls12_session(&self, _: &ServerName<'static>) {}
+
+    fn insert_tls13_ticket(&self, _: ServerName<'static>, _: Tls13ClientSessionValue) {}
+
+    fn take_tls13_ticket(&self, _: &ServerName<'static>) -> Option<Tls13ClientSessionValue> {
+        None
+    }
+}
@@ -1,2 +1,6 @@
+pub mod http_assertions;
+pub mod http_client;
 pub mod http_executor;
 pub mod http_ping;
+pub mod http_settings;
+pub mod http_timing;
@@ -0,0 +1,103 @@
+use crate::executor::{ExecReport, Executor};
+use crate::metrics::metrics_cst::{
+    HTTP_FLOW_STEP_DURATION_PROMETHEUS_METRIC_DESC, HTTP_FLOW_STEP_DURATION_PROMETHEUS_METRIC_NAME,
+    HTTP_FLOW_STEP_PASSED_PROMETHEUS_METRIC_DESC, HTTP_FLOW_STEP_PASSED_PROMETHEUS_METRIC_NAME,
+    HTTP_FLOW_STEP_STATUS_CODE_PROMETHEUS_METRIC_DESC,
+    HTTP_FLOW_STEP_STATUS_CODE_PROMETHEUS_METRIC_NAME, PHASE_PROMETHEUS_METRIC_LABEL_NAME,
+    STEP_PROMETHEUS_METRIC_LABEL_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::task::http_flow::http_flow_ping::HttpFlowPing;
+use crate::task::http_flow::http_flow_settings::HttpFlowSettings;
+use async_trait::async_trait;
+use log::trace;
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct HttpFlowExecutor {
+    target: String,
+    http_flow_ping: HttpFlowPing,
+    timeout: Duration,
+}
+
+impl HttpFlowExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `target` - 目标地址，步骤中的相对地址基于它解析，例如: `http://127.0.0.1:8080`
+    /// * `timeout` - 一个 `Duration`，表示整个事务的超时时间
+    /// * `settings` - HTTP事务任务配置
+    ///
+    /// # Panics
+    /// 配置错误时会触发panic，配置在加载时已经检查过
+    pub fn new(target: String, timeout: Duration, settings: HttpFlowSettings) -> Self {
+        let http_flow_ping = HttpFlowPing::new(&target, &settings)
+            .unwrap_or_else(|e| panic!("HTTP事务任务的配置错误({}): {}", target, e));
+        Self {
+            target,
+            http_flow_ping,
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl Executor for HttpFlowExecutor {
+    fn get_name(&self) -> String {
+        String::from("HTTP-FLOW")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        trace!("开始执行 HTTP-FLOW 任务: {}", self.target);
+        let mut results = self.http_flow_ping.ping(self.timeout).await?.into_iter();
+
+        // 没有执行到的步骤视为未通过
+        let mut metrics = vec![];
+        let mut error = None;
+        for step in self.http_flow_ping.steps() {
+            let result = results.next();
+            let passed = result.as_ref().is_some_and(|result| result.error.is_none());
+            metrics.push(
+                ProbeMetric::new(
+                    HTTP_FLOW_STEP_PASSED_PROMETHEUS_METRIC_NAME,
+                    HTTP_FLOW_STEP_PASSED_PROMETHEUS_METRIC_DESC,
+                    if passed { 1.0 } else { 0.0 },
+                )
+                .with_label(STEP_PROMETHEUS_METRIC_LABEL_NAME, &step.name),
+            );
+            let Some(result) = result else {
+                continue;
+            };
+            if let Some(reply) = &result.reply {
+                metrics.push(
+                    ProbeMetric::new(
+                        HTTP_FLOW_STEP_STATUS_CODE_PROMETHEUS_METRIC_NAME,
+                        HTTP_FLOW_STEP_STATUS_CODE_PROMETHEUS_METRIC_DESC,
+                        reply.status.as_u16() as f64,
+                    )
+                    .with_label(STEP_PROMETHEUS_METRIC_LABEL_NAME, &step.name),
+                );
+                metrics.extend(reply.timings.phases().into_iter().map(|(phase, duration)| {
+                    ProbeMetric::new(
+                        HTTP_FLOW_STEP_DURATION_PROMETHEUS_METRIC_NAME,
+                        HTTP_FLOW_STEP_DURATION_PROMETHEUS_METRIC_DESC,
+                        duration.as_secs_f64() * 1000.0,
+                    )
+                    .with_label(STEP_PROMETHEUS_METRIC_LABEL_NAME, &step.name)
+                    .with_label(PHASE_PROMETHEUS_METRIC_LABEL_NAME, phase)
+                }));
+            }
+            if let Some(e) = result.error {
+                error = Some(PingError::StepFailed {
+                    step: step.name.clone(),
+                    source: Box::new(e),
+                });
+            }
+        }
+        Ok(ExecReport {
+            elapsed: None,
+            error,
+            metrics,
+        })
+    }
+}
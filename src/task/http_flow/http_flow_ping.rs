@@ -0,0 +1,115 @@
+use crate::ping_error::PingError;
+use crate::task::http::http_client::client_builder;
+use crate::task::http::http_settings::HttpClientSettings;
+use crate::task::http::http_timing::{PhaseRecorder, SharedRecorder};
+use crate::task::http_flow::http_flow_settings::HttpFlowSettings;
+use crate::task::http_flow::http_flow_step::{HttpFlowStep, StepResult};
+use log::trace;
+use reqwest::Url;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct HttpFlowPing {
+    /// 解析相对地址的基准地址
+    base: Url,
+    /// 客户端的配置
+    client: HttpClientSettings,
+    /// 按顺序执行的步骤
+    steps: Vec<HttpFlowStep>,
+}
+
+impl HttpFlowPing {
+    /// 构造函数
+    /// # 参数
+    /// * `target` - 任务的目标地址，步骤中的相对地址基于它解析
+    /// * `settings` - HTTP事务任务配置
+    ///
+    /// # 返回值
+    /// 没有步骤、步骤重名、引用了前面步骤没有提取的变量或其它配置错误时返回错误信息
+    pub fn new(target: &str, settings: &HttpFlowSettings) -> Result<Self, String> {
+        let base = Url::parse(target).map_err(|e| format!("错误的目标地址: {}", e))?;
+        if settings.steps.is_empty() {
+            return Err(String::from("steps不能为空"));
+        }
+        // 提前构造一次客户端，检查证书文件、代理地址等配置
+        client_builder(
+            &settings.client,
+            &Arc::new(Mutex::new(PhaseRecorder::default())),
+        )?
+        .build()
+        .map_err(|e| e.to_string())?;
+
+        let mut names = HashSet::new();
+        let mut variables = HashSet::new();
+        let mut steps = vec![];
+        for step in settings.steps.iter() {
+            if !names.insert(step.name.as_str()) {
+                return Err(format!("步骤名称重复: {}", step.name));
+            }
+            let step = HttpFlowStep::new(step)
+                .map_err(|e| format!("步骤 {} 的配置错误: {}", step.name, e))?;
+            if let Some(var) = step
+                .referenced_variables()
+                .into_iter()
+                .find(|var| !variables.contains(var))
+            {
+                return Err(format!("步骤 {} 引用了未定义的变量: {}", step.name, var));
+            }
+            variables.extend(step.extracted_variables().cloned());
+            steps.push(step);
+        }
+        Ok(Self {
+            base,
+            client: settings.client.clone(),
+            steps,
+        })
+    }
+
+    /// 按顺序执行的步骤
+    pub fn steps(&self) -> &[HttpFlowStep] {
+        &self.steps
+    }
+
+    /// 按顺序执行各个步骤，任一步骤失败时不再执行后面的步骤
+    ///
+    /// # 参数
+    /// * `timeout` - 整个事务的超时时间
+    ///
+    /// # 返回值
+    /// 已执行的各个步骤的结果，只有最后一个可能失败
+    pub async fn ping(&self, timeout: Duration) -> Result<Vec<StepResult>, PingError> {
+        // 每次执行都使用新的客户端，Cookie不会带到下一次执行
+        let recorder: SharedRecorder = Arc::new(Mutex::new(PhaseRecorder::default()));
+        let client = client_builder(&self.client, &recorder)
+            .map_err(io::Error::other)?
+            .cookie_store(true)
+            .build()?;
+
+        let deadline = Instant::now() + timeout;
+        let mut variables = HashMap::new();
+        let mut results = vec![];
+        for step in self.steps.iter() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let result = if remaining.is_zero() {
+                StepResult {
+                    reply: None,
+                    error: Some(PingError::Timeout),
+                }
+            } else {
+                trace!("执行HTTP事务的步骤: {} ....", step.name);
+                step.run(&client, &recorder, &self.base, &mut variables, remaining)
+                    .await
+            };
+            let failed = result.error.is_some();
+            results.push(result);
+            if failed {
+                break;
+            }
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,69 @@
+use crate::task::http::http_settings::{
+    HttpAssertionSettings, HttpClientSettings, HttpRequestSettings,
+};
+use serde::{Deserialize, Serialize};
+
+/// HTTP事务任务配置
+///
+/// 按顺序执行多个请求，所有请求共享同一个Cookie，每次执行都从空的Cookie开始。
+/// 前面步骤的响应中提取的变量，可以在后面步骤的地址、请求头、请求体和认证信息中以 `${变量名}` 引用，
+/// 任一步骤失败时不再执行后面的步骤。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct HttpFlowSettings {
+    /// 客户端的配置，对所有步骤生效
+    #[serde(flatten)]
+    pub client: HttpClientSettings,
+    /// 按顺序执行的步骤
+    #[serde(default)]
+    pub steps: Vec<HttpStepSettings>,
+}
+
+/// HTTP事务中一个步骤的配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct HttpStepSettings {
+    /// 步骤名称，在任务内唯一，作为指标的 `step` 标签
+    pub name: String,
+    /// 请求方法
+    #[serde(default = "method_default")]
+    pub method: String,
+    /// 请求地址，相对地址基于任务的目标地址
+    pub url: String,
+    /// 请求的配置
+    #[serde(flatten)]
+    pub request: HttpRequestSettings,
+    /// 对响应的断言
+    #[serde(flatten)]
+    pub assertions: HttpAssertionSettings,
+    /// 从响应中提取的变量
+    #[serde(default)]
+    pub extract: Vec<ExtractSettings>,
+}
+
+fn method_default() -> String {
+    String::from("GET") // 默认 GET
+}
+
+/// 从响应中提取变量的配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExtractSettings {
+    /// 变量名
+    pub var: String,
+    /// 提取的来源
+    #[serde(flatten)]
+    pub source: ExtractSource,
+}
+
+/// 变量的来源
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExtractSource {
+    /// JSONPath表达式，必须恰好匹配到一个节点，字符串取其内容，其它类型取JSON文本
+    JsonPath(String),
+    /// 正则表达式，有捕获组时取第一个捕获组，否则取整个匹配
+    Regex(String),
+    /// 响应头的名称
+    Header(String),
+}
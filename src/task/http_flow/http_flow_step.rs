@@ -0,0 +1,247 @@
+use crate::ping_error::PingError;
+use crate::task::http::http_assertions::HttpAssertions;
+use crate::task::http::http_ping::{fetch, HttpReply, PreparedRequest};
+use crate::task::http::http_settings::HttpRequestSettings;
+use crate::task::http::http_timing::SharedRecorder;
+use crate::task::http_flow::http_flow_settings::{ExtractSource, HttpStepSettings};
+use regex::Regex;
+use reqwest::header::HeaderName;
+use reqwest::{Client, Method, Url};
+use serde_json_path::JsonPath;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// 变量引用，如 `${token}`
+static VARIABLE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$\{([^}]+)\}").unwrap());
+
+/// 编译后的变量提取器
+#[derive(Debug, Clone)]
+enum Extractor {
+    JsonPath(JsonPath),
+    Regex(Regex),
+    Header(HeaderName),
+}
+
+impl Extractor {
+    fn new(source: &ExtractSource) -> Result<Self, String> {
+        Ok(match source {
+            ExtractSource::JsonPath(path) => Extractor::JsonPath(
+                JsonPath::parse(path).map_err(|e| format!("错误的JSONPath({}): {}", path, e))?,
+            ),
+            ExtractSource::Regex(regex) => Extractor::Regex(
+                Regex::new(regex).map_err(|e| format!("错误的正则表达式({}): {}", regex, e))?,
+            ),
+            ExtractSource::Header(name) => Extractor::Header(
+                HeaderName::from_str(name).map_err(|e| format!("错误的响应头({}): {}", name, e))?,
+            ),
+        })
+    }
+
+    /// 从响应中提取变量的值
+    fn extract(&self, reply: &HttpReply) -> Result<String, String> {
+        match self {
+            Extractor::JsonPath(path) => {
+                let json: serde_json::Value = serde_json::from_slice(&reply.body)
+                    .map_err(|e| format!("响应体不是合法的JSON: {}", e))?;
+                let node = path
+                    .query(&json)
+                    .exactly_one()
+                    .map_err(|e| format!("JSONPath {} 没有匹配到唯一的节点: {}", path, e))?;
+                Ok(match node {
+                    serde_json::Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                })
+            }
+            Extractor::Regex(regex) => {
+                let body = String::from_utf8_lossy(&reply.body);
+                let captures = regex
+                    .captures(&body)
+                    .ok_or_else(|| format!("响应体没有匹配正则表达式 {}", regex))?;
+                Ok(captures
+                    .get(1)
+                    .or_else(|| captures.get(0))
+                    .map(|m| m.as_str().to_string())
+                    .unwrap_or_default())
+            }
+            Extractor::Header(name) => reply
+                .headers
+                .get(name)
+                .ok_or_else(|| format!("缺少响应头 {}", name))?
+                .to_str()
+                .map(|value| value.to_string())
+                .map_err(|e| format!("响应头 {} 的值不是合法的字符串: {}", name, e)),
+        }
+    }
+}
+
+/// 步骤的执行结果
+#[derive(Debug)]
+pub struct StepResult {
+    /// 收到的响应，请求失败时为None
+    pub reply: Option<HttpReply>,
+    /// 步骤失败的原因
+    pub error: Option<PingError>,
+}
+
+/// 编译后的HTTP事务步骤
+#[derive(Debug, Clone)]
+pub struct HttpFlowStep {
+    /// 步骤名称
+    pub name: String,
+    /// 请求方法
+    method: Method,
+    /// 请求地址的模板
+    url: String,
+    /// 请求的模板，请求体文件已经读入请求体
+    request: HttpRequestSettings,
+    /// 对响应的断言
+    assertions: HttpAssertions,
+    /// 变量名及其提取器
+    extract: Vec<(String, Extractor)>,
+}
+
+impl HttpFlowStep {
+    /// 根据配置编译步骤，请求体文件在此时读取
+    ///
+    /// # 返回值
+    /// 请求方法、请求头、断言或提取器的配置错误时返回错误信息
+    pub fn new(settings: &HttpStepSettings) -> Result<Self, String> {
+        let method = Method::from_str(&settings.method.to_uppercase())
+            .map_err(|e| format!("错误的请求方法({}): {}", settings.method, e))?;
+        // 提前检查请求头等配置，并读入请求体文件，以便替换其中的变量
+        PreparedRequest::new(&settings.request)?;
+        let mut request = settings.request.clone();
+        if let Some(file) = request.body_file.take() {
+            let body = std::fs::read_to_string(&file)
+                .map_err(|e| format!("读取请求体文件({})失败: {}", file, e))?;
+            request.body = Some(body);
+        }
+        let extract = settings
+            .extract
+            .iter()
+            .map(|extract| Ok((extract.var.clone(), Extractor::new(&extract.source)?)))
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            name: settings.name.clone(),
+            method,
+            url: settings.url.clone(),
+            request,
+            assertions: HttpAssertions::new(&settings.assertions)?,
+            extract,
+        })
+    }
+
+    /// 请求中引用的变量名
+    pub fn referenced_variables(&self) -> Vec<String> {
+        let auth = self.request.basic_auth.as_ref();
+        [Some(&self.url), self.request.body.as_ref()]
+            .into_iter()
+            .chain([auth.map(|auth| &auth.username)])
+            .chain([auth.and_then(|auth| auth.password.as_ref())])
+            .chain([self.request.bearer_token.as_ref()])
+            .flatten()
+            .chain(self.request.headers.values())
+            .flat_map(|template| VARIABLE_REGEX.captures_iter(template))
+            .map(|captures| captures[1].to_string())
+            .collect()
+    }
+
+    /// 提取的变量名
+    pub fn extracted_variables(&self) -> impl Iterator<Item = &String> {
+        self.extract.iter().map(|(var, _)| var)
+    }
+
+    /// 执行步骤：替换变量后发出请求，检查响应，再从响应中提取变量
+    ///
+    /// # 参数
+    /// * `client` - 共享Cookie的客户端，须使用 `recorder` 打点
+    /// * `recorder` - 建立连接各阶段的打点记录
+    /// * `base` - 解析相对地址的基准地址
+    /// * `variables` - 前面步骤提取的变量，本步骤提取的变量也会加入其中
+    /// * `timeout` - 包括读取响应体在内的超时时间
+    pub async fn run(
+        &self,
+        client: &Client,
+        recorder: &SharedRecorder,
+        base: &Url,
+        variables: &mut HashMap<String, String>,
+        timeout: Duration,
+    ) -> StepResult {
+        let request = match self.prepare(client, base, variables) {
+            Ok(request) => request.timeout(timeout),
+            Err(e) => {
+                return StepResult {
+                    reply: None,
+                    error: Some(PingError::InvalidReply(e)),
+                };
+            }
+        };
+        let reply = match fetch(request, recorder, self.assertions.max_body_size()).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                return StepResult {
+                    reply: None,
+                    error: Some(e),
+                };
+            }
+        };
+        let error = self
+            .assertions
+            .check(reply.status, &reply.headers, &reply.body, reply.body_size)
+            .and_then(|_| {
+                for (var, extractor) in self.extract.iter() {
+                    variables.insert(var.clone(), extractor.extract(&reply)?);
+                }
+                Ok(())
+            })
+            .err()
+            .map(PingError::InvalidReply);
+        StepResult {
+            reply: Some(reply),
+            error,
+        }
+    }
+
+    /// 替换变量，构造请求
+    ///
+    /// # 返回值
+    /// 替换变量后的地址或请求头不合法时返回错误信息，变量的值来自前面步骤的响应
+    fn prepare(
+        &self,
+        client: &Client,
+        base: &Url,
+        variables: &HashMap<String, String>,
+    ) -> Result<reqwest::RequestBuilder, String> {
+        let substitute = |template: &String| substitute(template, variables);
+        let url = substitute(&self.url);
+        let url = base
+            .join(&url)
+            .map_err(|e| format!("错误的请求地址({}): {}", url, e))?;
+        let mut request = self.request.clone();
+        request
+            .headers
+            .values_mut()
+            .for_each(|value| *value = substitute(value));
+        request.body = request.body.as_ref().map(substitute);
+        request.bearer_token = request.bearer_token.as_ref().map(substitute);
+        if let Some(auth) = request.basic_auth.as_mut() {
+            auth.username = substitute(&auth.username);
+            auth.password = auth.password.as_ref().map(substitute);
+        }
+        Ok(PreparedRequest::new(&request)?.apply(client.request(self.method.clone(), url)))
+    }
+}
+
+/// 将模板中的 `${变量名}` 替换成变量的值，未定义的变量保持原样
+fn substitute(template: &str, variables: &HashMap<String, String>) -> String {
+    VARIABLE_REGEX
+        .replace_all(template, |captures: &regex::Captures| {
+            variables
+                .get(&captures[1])
+                .cloned()
+                .unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
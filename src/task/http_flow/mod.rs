@@ -0,0 +1,4 @@
+pub mod http_flow_executor;
+pub mod http_flow_ping;
+pub mod http_flow_settings;
+pub mod http_flow_step;
@@ -1,16 +1,22 @@
-use crate::executor::Executor;
+use crate::executor::{ExecReport, Executor};
 use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::TargetResolver;
 use crate::task::icmp::icmp_ping::IcmpPing;
+use crate::task::icmp::icmp_pmtu::PmtuDiscovery;
+use crate::task::icmp::icmp_settings::IcmpSettings;
+use crate::task::icmp::icmp_stats::IcmpStats;
 use async_trait::async_trait;
-use log::trace;
+use log::{debug, trace};
 use std::net::IpAddr;
 use std::time::Duration;
-use wheel_rs::dns_utils::parse_host;
+use tokio::time::sleep;
 
 #[derive(Clone)]
 pub struct IcmpExecutor {
-    ip_addr: IpAddr,
+    resolver: TargetResolver,
     timeout: Duration,
+    settings: IcmpSettings,
     icmp_ping: IcmpPing,
 }
 
@@ -18,13 +24,19 @@ impl IcmpExecutor {
     /// 构造函数
     /// # 参数
     /// * `host` - 要ping的主机名或 IP 地址
-    /// * `timeout` - 一个 `Duration`，表示超时时间
-    pub fn new(host: String, timeout: Duration) -> Self {
-        // 解析主机的字符串成IP地址
-        let ip_addr = parse_host(&host).unwrap();
+    /// * `timeout` - 一个 `Duration`，表示每个包等待回包的超时时间
+    /// * `settings` - ICMP任务配置
+    /// * `resolve` - 解析主机名的配置
+    pub fn new(
+        host: String,
+        timeout: Duration,
+        settings: IcmpSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
         Self {
-            ip_addr,
+            resolver: TargetResolver::new(&host, resolve),
             timeout,
+            settings,
             icmp_ping: IcmpPing::new(),
         }
     }
@@ -36,8 +48,80 @@ impl Executor for IcmpExecutor {
         String::from("ICMP")
     }
 
-    async fn exec(&self) -> Result<(), PingError> {
-        trace!("开始执行 ICMP 任务: ping {}", self.ip_addr);
-        self.icmp_ping.ping(self.ip_addr, self.timeout)
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move {
+                    if executor.settings.pmtu_discovery {
+                        executor.exec_pmtu_discovery(ip_addr).await
+                    } else {
+                        executor.exec_addr(ip_addr).await
+                    }
+                }
+            })
+            .await
+    }
+}
+
+impl IcmpExecutor {
+    /// 探测一个地址
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        trace!(
+            "开始执行 ICMP 任务: ping {} x {}",
+            ip_addr,
+            self.settings.count
+        );
+        let mut rtts = Vec::with_capacity(self.settings.count as usize);
+        let mut last_error = None;
+        for i in 0..self.settings.count {
+            if i > 0 {
+                // spacing在加载配置时已经检查过不为空
+                sleep(self.settings.spacing.unwrap()).await;
+            }
+            match self
+                .icmp_ping
+                .ping(
+                    ip_addr,
+                    self.timeout,
+                    self.settings.payload_size,
+                    self.settings.socket_mode,
+                    self.settings.dont_fragment,
+                )
+                .await
+            {
+                Ok(rtt) => rtts.push(rtt),
+                Err(e) => {
+                    debug!("ping {} 第{}个包失败: {}", ip_addr, i + 1, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        // 只要收到一个回包就认为目标可达，丢包体现在丢包率上
+        let stats = IcmpStats::new(self.settings.count, &rtts);
+        Ok(ExecReport {
+            elapsed: stats.avg,
+            error: if rtts.is_empty() {
+                Some(last_error.unwrap_or(PingError::Timeout))
+            } else {
+                None
+            },
+            metrics: stats.to_metrics(),
+        })
+    }
+
+    /// 以路径MTU发现模式执行，以最大包的RTT作为耗时
+    async fn exec_pmtu_discovery(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        trace!("开始执行 ICMP 任务: 查找 {} 的路径MTU", ip_addr);
+        let pmtu = PmtuDiscovery::new(&self.icmp_ping, ip_addr, self.timeout, &self.settings)
+            .discover()
+            .await?;
+        debug!("{} 的路径MTU为 {} 字节", ip_addr, pmtu.mtu);
+        Ok(ExecReport {
+            elapsed: Some(pmtu.rtt),
+            error: None,
+            metrics: pmtu.to_metrics(),
+        })
     }
 }
@@ -0,0 +1,579 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// ICMP协议号
+pub const IP_PROTOCOL_ICMP: u8 = 1;
+/// TCP协议号
+pub const IP_PROTOCOL_TCP: u8 = 6;
+/// UDP协议号
+pub const IP_PROTOCOL_UDP: u8 = 17;
+/// ICMPv6协议号
+pub const IP_PROTOCOL_ICMPV6: u8 = 58;
+
+/// ICMPv4 Echo Reply 类型
+const ICMP_V4_ECHO_REPLY: u8 = 0;
+/// ICMPv4 目标不可达类型
+const ICMP_V4_DESTINATION_UNREACHABLE: u8 = 3;
+/// ICMPv4 目标不可达中"需要分片但设置了DF位"的代码
+pub const ICMP_V4_FRAGMENTATION_NEEDED: u8 = 4;
+/// ICMPv4 Echo Request 类型
+pub const ICMP_V4_ECHO_REQUEST: u8 = 8;
+/// ICMPv4 超时类型
+const ICMP_V4_TIME_EXCEEDED: u8 = 11;
+/// ICMPv6 目标不可达类型
+const ICMP_V6_DESTINATION_UNREACHABLE: u8 = 1;
+/// ICMPv6 包过大类型
+const ICMP_V6_PACKET_TOO_BIG: u8 = 2;
+/// ICMPv6 超时类型
+const ICMP_V6_TIME_EXCEEDED: u8 = 3;
+/// ICMPv6 Echo Request 类型
+pub const ICMP_V6_ECHO_REQUEST: u8 = 128;
+/// ICMPv6 Echo Reply 类型
+const ICMP_V6_ECHO_REPLY: u8 = 129;
+
+/// IPv4最小头部长度
+pub const IP_V4_MIN_HEADER_LENGTH: usize = 20;
+/// IPv6头部长度
+pub const IP_V6_HEADER_LENGTH: usize = 40;
+/// ICMP头部长度
+pub const ICMP_HEADER_LENGTH: usize = 8;
+
+/// 按 16 位分组做反码求和（RFC 1071），处理奇数长度数据，不取反
+/// # 参数
+/// * `sum` - 已有的累加值，可以分段累加(如先累加伪头部)
+/// * `buf` - 要累加的数据
+pub fn ones_complement_sum(mut sum: u32, buf: &[u8]) -> u16 {
+    for chunk in buf.chunks(2) {
+        let word = u16::from_be_bytes([chunk[0], chunk.get(1).copied().unwrap_or(0)]);
+        sum = sum.wrapping_add(word as u32);
+        // 及时折叠，避免分段累加时溢出
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    while (sum >> 16) != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum as u16
+}
+
+/// 计算 ICMP 校验和（RFC 1071）
+/// 按 16 位分组累加，处理奇数长度数据，最后取反
+pub fn checksum_v4(buf: &[u8]) -> u16 {
+    !ones_complement_sum(0, buf)
+}
+
+/// 累加 ICMPv6 伪头部(RFC 8200)，用于计算 ICMPv6 校验和
+pub fn pseudo_header_sum_v6(src: &Ipv6Addr, dst: &Ipv6Addr, length: u32) -> u16 {
+    let mut pseudo_header = Vec::with_capacity(40);
+    pseudo_header.extend_from_slice(&src.octets());
+    pseudo_header.extend_from_slice(&dst.octets());
+    pseudo_header.extend_from_slice(&length.to_be_bytes());
+    pseudo_header.extend_from_slice(&[0, 0, 0, IP_PROTOCOL_ICMPV6]);
+    ones_complement_sum(0, &pseudo_header)
+}
+
+/// 差错报文中引用的原始数据包
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotedPacket {
+    /// 原始数据包的目标地址
+    pub dst: IpAddr,
+    /// 原始数据包的上层协议号
+    pub protocol: u8,
+    /// 原始数据包上层协议头部的前8个字节，RFC 792 保证至少引用这么多
+    pub header: [u8; 8],
+}
+
+/// 解析后的ICMP消息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IcmpMessage {
+    /// 回显应答
+    EchoReply {
+        /// 标识符
+        id: u16,
+        /// 序列号
+        seq: u16,
+    },
+    /// 目标不可达
+    DestinationUnreachable {
+        /// 代码
+        code: u8,
+        /// 引用的原始数据包
+        quoted: QuotedPacket,
+    },
+    /// 超时(TTL耗尽)
+    TimeExceeded {
+        /// 代码
+        code: u8,
+        /// 引用的原始数据包
+        quoted: QuotedPacket,
+    },
+    /// 包过大，ICMPv4的"需要分片"或ICMPv6的"包过大"
+    PacketTooBig {
+        /// 路由器报告的下一跳MTU，旧的路由器可能报告为0
+        mtu: u16,
+        /// 引用的原始数据包
+        quoted: QuotedPacket,
+    },
+}
+
+impl QuotedPacket {
+    /// 如果原始数据包是 Echo Request，返回其(标识符, 序列号)
+    pub fn echo_request_id_seq(&self) -> Option<(u16, u16)> {
+        let is_echo_request = match self.protocol {
+            IP_PROTOCOL_ICMP => self.header[0] == ICMP_V4_ECHO_REQUEST,
+            IP_PROTOCOL_ICMPV6 => self.header[0] == ICMP_V6_ECHO_REQUEST,
+            _ => false,
+        };
+        is_echo_request.then(|| {
+            (
+                u16::from_be_bytes([self.header[4], self.header[5]]),
+                u16::from_be_bytes([self.header[6], self.header[7]]),
+            )
+        })
+    }
+
+    /// 原始数据包是UDP或TCP时，返回其(源端口, 目标端口)
+    pub fn ports(&self) -> Option<(u16, u16)> {
+        matches!(self.protocol, IP_PROTOCOL_TCP | IP_PROTOCOL_UDP).then(|| {
+            (
+                u16::from_be_bytes([self.header[0], self.header[1]]),
+                u16::from_be_bytes([self.header[2], self.header[3]]),
+            )
+        })
+    }
+}
+
+/// 解析 IPv4 头部
+///
+/// # 返回值
+/// (上层协议号, 目标地址, 上层数据)，上层数据按IP头部的总长度截取，去掉链路层的填充
+fn parse_ip_v4_header(packet: &[u8]) -> Option<(u8, Ipv4Addr, &[u8])> {
+    if packet.len() < IP_V4_MIN_HEADER_LENGTH || packet[0] >> 4 != 4 {
+        return None;
+    }
+    let header_length = ((packet[0] & 0x0f) as usize) * 4;
+    if header_length < IP_V4_MIN_HEADER_LENGTH || packet.len() < header_length {
+        return None;
+    }
+    let total_length = u16::from_be_bytes([packet[2], packet[3]]) as usize;
+    // 部分系统的原始套接字会改写总长度字段，不可信时就用实际收到的长度
+    let end = if (header_length..=packet.len()).contains(&total_length) {
+        total_length
+    } else {
+        packet.len()
+    };
+    let dst = Ipv4Addr::new(packet[16], packet[17], packet[18], packet[19]);
+    Some((packet[9], dst, &packet[header_length..end]))
+}
+
+/// 解析差错报文中引用的 IPv4 数据包
+fn parse_quoted_v4(data: &[u8]) -> Option<QuotedPacket> {
+    // 引用的数据包可能被截断，不能按总长度截取
+    if data.len() < IP_V4_MIN_HEADER_LENGTH || data[0] >> 4 != 4 {
+        return None;
+    }
+    let header_length = ((data[0] & 0x0f) as usize) * 4;
+    let header = data.get(header_length..header_length + 8)?;
+    Some(QuotedPacket {
+        dst: IpAddr::V4(Ipv4Addr::new(data[16], data[17], data[18], data[19])),
+        protocol: data[9],
+        header: header.try_into().ok()?,
+    })
+}
+
+/// 解析差错报文中引用的 IPv6 数据包，不处理扩展头部
+fn parse_quoted_v6(data: &[u8]) -> Option<QuotedPacket> {
+    if data.len() < IP_V6_HEADER_LENGTH || data[0] >> 4 != 6 {
+        return None;
+    }
+    let dst: [u8; 16] = data[24..40].try_into().ok()?;
+    let header = data.get(IP_V6_HEADER_LENGTH..IP_V6_HEADER_LENGTH + 8)?;
+    Some(QuotedPacket {
+        dst: IpAddr::V6(Ipv6Addr::from(dst)),
+        protocol: data[6],
+        header: header.try_into().ok()?,
+    })
+}
+
+/// 解析 ICMPv4 消息
+///
+/// # 参数
+/// * `packet` - 收到的数据包
+/// * `has_ip_header` - 是否包含IP头部，原始套接字收到的包含，数据报套接字收到的不包含
+///
+/// # 返回值
+/// 不是ICMP、校验和错误以及不关心的消息类型返回None
+pub fn parse_icmp_v4(packet: &[u8], has_ip_header: bool) -> Option<IcmpMessage> {
+    let icmp = if has_ip_header {
+        let (protocol, _, icmp) = parse_ip_v4_header(packet)?;
+        if protocol != IP_PROTOCOL_ICMP {
+            return None;
+        }
+        icmp
+    } else {
+        packet
+    };
+    if icmp.len() < ICMP_HEADER_LENGTH || checksum_v4(icmp) != 0 {
+        return None;
+    }
+
+    let (icmp_type, code) = (icmp[0], icmp[1]);
+    match icmp_type {
+        ICMP_V4_ECHO_REPLY if code == 0 => Some(IcmpMessage::EchoReply {
+            id: u16::from_be_bytes([icmp[4], icmp[5]]),
+            seq: u16::from_be_bytes([icmp[6], icmp[7]]),
+        }),
+        ICMP_V4_DESTINATION_UNREACHABLE if code == ICMP_V4_FRAGMENTATION_NEEDED => {
+            Some(IcmpMessage::PacketTooBig {
+                mtu: u16::from_be_bytes([icmp[6], icmp[7]]),
+                quoted: parse_quoted_v4(&icmp[ICMP_HEADER_LENGTH..])?,
+            })
+        }
+        ICMP_V4_DESTINATION_UNREACHABLE => Some(IcmpMessage::DestinationUnreachable {
+            code,
+            quoted: parse_quoted_v4(&icmp[ICMP_HEADER_LENGTH..])?,
+        }),
+        ICMP_V4_TIME_EXCEEDED => Some(IcmpMessage::TimeExceeded {
+            code,
+            quoted: parse_quoted_v4(&icmp[ICMP_HEADER_LENGTH..])?,
+        }),
+        _ => None,
+    }
+}
+
+/// 解析 ICMPv6 消息
+///
+/// ICMPv6 套接字收到的包不含IP头部。校验和包含伪头部，
+/// 由内核在投递到原始套接字之前校验(RFC 3542)，此处不再校验。
+///
+/// # 返回值
+/// 不关心的消息类型返回None
+pub fn parse_icmp_v6(icmp: &[u8]) -> Option<IcmpMessage> {
+    if icmp.len() < ICMP_HEADER_LENGTH {
+        return None;
+    }
+
+    let (icmp_type, code) = (icmp[0], icmp[1]);
+    match icmp_type {
+        ICMP_V6_ECHO_REPLY if code == 0 => Some(IcmpMessage::EchoReply {
+            id: u16::from_be_bytes([icmp[4], icmp[5]]),
+            seq: u16::from_be_bytes([icmp[6], icmp[7]]),
+        }),
+        ICMP_V6_DESTINATION_UNREACHABLE => Some(IcmpMessage::DestinationUnreachable {
+            code,
+            quoted: parse_quoted_v6(&icmp[ICMP_HEADER_LENGTH..])?,
+        }),
+        ICMP_V6_TIME_EXCEEDED => Some(IcmpMessage::TimeExceeded {
+            code,
+            quoted: parse_quoted_v6(&icmp[ICMP_HEADER_LENGTH..])?,
+        }),
+        ICMP_V6_PACKET_TOO_BIG => Some(IcmpMessage::PacketTooBig {
+            mtu: u32::from_be_bytes([icmp[4], icmp[5], icmp[6], icmp[7]]).min(u16::MAX as u32)
+                as u16,
+            quoted: parse_quoted_v6(&icmp[ICMP_HEADER_LENGTH..])?,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 8.8.8.8 回复给 192.168.1.10 的 Echo Reply，标识符 0x1234，序列号 1
+    const ECHO_REPLY_V4: [u8; 36] = [
+        0x45, 0x00, 0x00, 0x24, 0x1c, 0x46, 0x40, 0x00, 0x75, 0x01, 0x17, 0xd1, 0x08, 0x08, 0x08,
+        0x08, 0xc0, 0xa8, 0x01, 0x0a, 0x00, 0x00, 0x6e, 0x60, 0x12, 0x34, 0x00, 0x01, 0x70, 0x6f,
+        0x6e, 0x67, 0x2d, 0x72, 0x73, 0x21,
+    ];
+    /// 同上，IP头部带4字节的选项(IHL为6)，序列号 2
+    const ECHO_REPLY_V4_OPTIONS: [u8; 40] = [
+        0x46, 0x00, 0x00, 0x28, 0x1c, 0x46, 0x40, 0x00, 0x75, 0x01, 0x14, 0xcc, 0x08, 0x08, 0x08,
+        0x08, 0xc0, 0xa8, 0x01, 0x0a, 0x01, 0x01, 0x01, 0x00, 0x00, 0x00, 0x6e, 0x5f, 0x12, 0x34,
+        0x00, 0x02, 0x70, 0x6f, 0x6e, 0x67, 0x2d, 0x72, 0x73, 0x21,
+    ];
+    /// 路由器 10.0.0.1 的超时消息，引用发往 8.8.8.8 的 Echo Request
+    const TIME_EXCEEDED_V4: [u8; 56] = [
+        0x45, 0x00, 0x00, 0x38, 0x1c, 0x46, 0x40, 0x00, 0xff, 0x01, 0x93, 0xcb, 0x0a, 0x00, 0x00,
+        0x01, 0xc0, 0xa8, 0x01, 0x0a, 0x0b, 0x00, 0x74, 0x6a, 0x00, 0x00, 0x00, 0x00, 0x45, 0x00,
+        0x00, 0x24, 0x1c, 0x46, 0x40, 0x00, 0x01, 0x01, 0x8b, 0xd1, 0xc0, 0xa8, 0x01, 0x0a, 0x08,
+        0x08, 0x08, 0x08, 0x08, 0x00, 0x66, 0x60, 0x12, 0x34, 0x00, 0x01,
+    ];
+    /// 8.8.8.8 的端口不可达消息，引用 40000 -> 33434 的UDP数据包
+    const PORT_UNREACHABLE_V4: [u8; 56] = [
+        0x45, 0x00, 0x00, 0x38, 0x1c, 0x46, 0x40, 0x00, 0x40, 0x01, 0x4c, 0xbd, 0x08, 0x08, 0x08,
+        0x08, 0xc0, 0xa8, 0x01, 0x0a, 0x03, 0x03, 0xde, 0x11, 0x00, 0x00, 0x00, 0x00, 0x45, 0x00,
+        0x00, 0x24, 0x1c, 0x46, 0x40, 0x00, 0x40, 0x11, 0x4c, 0xc1, 0xc0, 0xa8, 0x01, 0x0a, 0x08,
+        0x08, 0x08, 0x08, 0x9c, 0x40, 0x82, 0x9a, 0x00, 0x10, 0x00, 0x00,
+    ];
+    /// 路由器 10.0.0.1 的需要分片消息，MTU 1400，引用 40001 -> 443 的TCP数据包
+    const FRAGMENTATION_NEEDED_V4: [u8; 56] = [
+        0x45, 0x00, 0x00, 0x38, 0x1c, 0x46, 0x40, 0x00, 0x40, 0x01, 0x52, 0xcc, 0x0a, 0x00, 0x00,
+        0x01, 0xc0, 0xa8, 0x01, 0x0a, 0x03, 0x04, 0x55, 0x81, 0x00, 0x00, 0x05, 0x78, 0x45, 0x00,
+        0x00, 0x28, 0x1c, 0x46, 0x40, 0x00, 0x40, 0x06, 0x4c, 0xc8, 0xc0, 0xa8, 0x01, 0x0a, 0x08,
+        0x08, 0x08, 0x08, 0x9c, 0x41, 0x01, 0xbb, 0x01, 0x02, 0x03, 0x04,
+    ];
+    /// ICMPv6 Echo Reply，标识符 0x1234，序列号 3
+    const ECHO_REPLY_V6: [u8; 16] = [
+        0x81, 0x00, 0x9c, 0x5a, 0x12, 0x34, 0x00, 0x03, 0x70, 0x6f, 0x6e, 0x67, 0x2d, 0x72, 0x73,
+        0x21,
+    ];
+    /// ICMPv6 超时消息，引用发往 2001:4860:4860::8888 的 Echo Request
+    const TIME_EXCEEDED_V6: [u8; 64] = [
+        0x03, 0x00, 0x4b, 0x2d, 0x00, 0x00, 0x00, 0x00, 0x60, 0x00, 0x00, 0x00, 0x00, 0x10, 0x3a,
+        0x01, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x10, 0x20, 0x01, 0x48, 0x60, 0x48, 0x60, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x88, 0x88, 0x80, 0x00, 0x00, 0x00, 0x12, 0x34, 0x00, 0x03, 0x70, 0x6f, 0x6e, 0x67,
+        0x2d, 0x72, 0x73, 0x21,
+    ];
+    /// ICMPv6 包过大消息，MTU 1280，引用 40000 -> 33434 的UDP数据包
+    const PACKET_TOO_BIG_V6: [u8; 64] = [
+        0x02, 0x00, 0x1f, 0x0e, 0x00, 0x00, 0x05, 0x00, 0x60, 0x00, 0x00, 0x00, 0x00, 0x10, 0x11,
+        0x40, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x10, 0x20, 0x01, 0x48, 0x60, 0x48, 0x60, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x88, 0x88, 0x9c, 0x40, 0x82, 0x9a, 0x00, 0x10, 0x00, 0x00, 0x70, 0x6f, 0x6e, 0x67,
+        0x2d, 0x72, 0x73, 0x21,
+    ];
+
+    /// 重新计算ICMPv4消息的校验和
+    fn with_checksum(mut icmp: Vec<u8>) -> Vec<u8> {
+        icmp[2..4].fill(0);
+        let checksum = checksum_v4(&icmp);
+        icmp[2..4].copy_from_slice(&checksum.to_be_bytes());
+        icmp
+    }
+
+    fn google_v4() -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8))
+    }
+
+    fn google_v6() -> IpAddr {
+        IpAddr::V6("2001:4860:4860::8888".parse().unwrap())
+    }
+
+    #[test]
+    fn checksum_v4_rfc1071_example() {
+        let data = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+        assert_eq!(ones_complement_sum(0, &data), 0xddf2);
+        assert_eq!(checksum_v4(&data), 0x220d);
+    }
+
+    #[test]
+    fn checksum_v4_odd_length_and_segments() {
+        assert_eq!(ones_complement_sum(0, &[0x01]), 0x0100);
+        // 分段累加与一次累加结果相同
+        let icmp = &ECHO_REPLY_V4[20..];
+        let first = ones_complement_sum(0, &icmp[..8]);
+        assert_eq!(
+            ones_complement_sum(first as u32, &icmp[8..]),
+            ones_complement_sum(0, icmp)
+        );
+    }
+
+    #[test]
+    fn checksum_v4_of_valid_header_is_zero() {
+        assert_eq!(checksum_v4(&ECHO_REPLY_V4[..20]), 0);
+        assert_eq!(checksum_v4(&ECHO_REPLY_V4[20..]), 0);
+        assert_eq!(checksum_v4(&ECHO_REPLY_V4_OPTIONS[..24]), 0);
+    }
+
+    #[test]
+    fn echo_reply_v4() {
+        let expected = Some(IcmpMessage::EchoReply { id: 0x1234, seq: 1 });
+        assert_eq!(parse_icmp_v4(&ECHO_REPLY_V4, true), expected);
+        // 数据报套接字收到的不含IP头部
+        assert_eq!(parse_icmp_v4(&ECHO_REPLY_V4[20..], false), expected);
+    }
+
+    #[test]
+    fn echo_reply_v4_with_ip_options() {
+        assert_eq!(
+            parse_icmp_v4(&ECHO_REPLY_V4_OPTIONS, true),
+            Some(IcmpMessage::EchoReply { id: 0x1234, seq: 2 })
+        );
+    }
+
+    #[test]
+    fn echo_reply_v4_ignores_link_layer_padding() {
+        let mut packet = ECHO_REPLY_V4.to_vec();
+        packet.extend_from_slice(&[0xff; 10]);
+        assert_eq!(
+            parse_icmp_v4(&packet, true),
+            Some(IcmpMessage::EchoReply { id: 0x1234, seq: 1 })
+        );
+    }
+
+    #[test]
+    fn invalid_ihl_v4() {
+        for ihl in [0x40, 0x44] {
+            let mut packet = ECHO_REPLY_V4;
+            packet[0] = ihl;
+            assert_eq!(parse_icmp_v4(&packet, true), None);
+        }
+        // 头部长度超过数据包长度
+        let mut packet = ECHO_REPLY_V4;
+        packet[0] = 0x4f;
+        assert_eq!(parse_icmp_v4(&packet, true), None);
+    }
+
+    #[test]
+    fn not_icmp_v4() {
+        let mut packet = ECHO_REPLY_V4;
+        packet[9] = IP_PROTOCOL_UDP;
+        assert_eq!(parse_icmp_v4(&packet, true), None);
+        let mut packet = ECHO_REPLY_V4;
+        packet[0] = 0x65;
+        assert_eq!(parse_icmp_v4(&packet, true), None);
+    }
+
+    #[test]
+    fn bad_checksum_v4() {
+        let mut packet = ECHO_REPLY_V4;
+        packet[35] ^= 0x01;
+        assert_eq!(parse_icmp_v4(&packet, true), None);
+        let mut packet = TIME_EXCEEDED_V4;
+        packet[22] ^= 0x80;
+        assert_eq!(parse_icmp_v4(&packet, true), None);
+    }
+
+    #[test]
+    fn truncated_v4() {
+        for len in [0, 19, 20, 27] {
+            assert_eq!(parse_icmp_v4(&ECHO_REPLY_V4[..len], true), None);
+        }
+        assert_eq!(parse_icmp_v4(&ECHO_REPLY_V4[20..27], false), None);
+        // 引用的数据包不足8字节的上层头部，校验和仍然正确
+        let icmp = with_checksum(TIME_EXCEEDED_V4[20..55].to_vec());
+        assert_eq!(parse_icmp_v4(&icmp, false), None);
+        // 引用的IP头部不完整
+        let icmp = with_checksum(TIME_EXCEEDED_V4[20..40].to_vec());
+        assert_eq!(parse_icmp_v4(&icmp, false), None);
+    }
+
+    #[test]
+    fn time_exceeded_v4() {
+        let Some(IcmpMessage::TimeExceeded { code, quoted }) =
+            parse_icmp_v4(&TIME_EXCEEDED_V4, true)
+        else {
+            panic!("应解析为超时消息");
+        };
+        assert_eq!(code, 0);
+        assert_eq!(quoted.dst, google_v4());
+        assert_eq!(quoted.protocol, IP_PROTOCOL_ICMP);
+        assert_eq!(quoted.echo_request_id_seq(), Some((0x1234, 1)));
+        assert_eq!(quoted.ports(), None);
+    }
+
+    #[test]
+    fn time_exceeded_v4_quoting_ip_options() {
+        // 引用的数据包带IP选项时按其IHL跳过头部
+        let mut icmp = TIME_EXCEEDED_V4[20..48].to_vec();
+        icmp[8] = 0x46;
+        icmp.extend_from_slice(&[0x01, 0x01, 0x01, 0x00]);
+        icmp.extend_from_slice(&TIME_EXCEEDED_V4[48..]);
+        let Some(IcmpMessage::TimeExceeded { quoted, .. }) =
+            parse_icmp_v4(&with_checksum(icmp), false)
+        else {
+            panic!("应解析为超时消息");
+        };
+        assert_eq!(quoted.echo_request_id_seq(), Some((0x1234, 1)));
+    }
+
+    #[test]
+    fn destination_unreachable_v4() {
+        let Some(IcmpMessage::DestinationUnreachable { code, quoted }) =
+            parse_icmp_v4(&PORT_UNREACHABLE_V4, true)
+        else {
+            panic!("应解析为目标不可达消息");
+        };
+        assert_eq!(code, 3);
+        assert_eq!(quoted.dst, google_v4());
+        assert_eq!(quoted.protocol, IP_PROTOCOL_UDP);
+        assert_eq!(quoted.ports(), Some((40000, 33434)));
+        assert_eq!(quoted.echo_request_id_seq(), None);
+    }
+
+    #[test]
+    fn fragmentation_needed_v4() {
+        let Some(IcmpMessage::PacketTooBig { mtu, quoted }) =
+            parse_icmp_v4(&FRAGMENTATION_NEEDED_V4, true)
+        else {
+            panic!("应解析为包过大消息");
+        };
+        assert_eq!(mtu, 1400);
+        assert_eq!(quoted.dst, google_v4());
+        assert_eq!(quoted.protocol, IP_PROTOCOL_TCP);
+        assert_eq!(quoted.ports(), Some((40001, 443)));
+    }
+
+    #[test]
+    fn echo_reply_v6() {
+        assert_eq!(
+            parse_icmp_v6(&ECHO_REPLY_V6),
+            Some(IcmpMessage::EchoReply { id: 0x1234, seq: 3 })
+        );
+    }
+
+    #[test]
+    fn time_exceeded_v6() {
+        let Some(IcmpMessage::TimeExceeded { code, quoted }) = parse_icmp_v6(&TIME_EXCEEDED_V6)
+        else {
+            panic!("应解析为超时消息");
+        };
+        assert_eq!(code, 0);
+        assert_eq!(quoted.dst, google_v6());
+        assert_eq!(quoted.protocol, IP_PROTOCOL_ICMPV6);
+        assert_eq!(quoted.echo_request_id_seq(), Some((0x1234, 3)));
+    }
+
+    #[test]
+    fn destination_unreachable_v6() {
+        let mut icmp = PACKET_TOO_BIG_V6;
+        icmp[0] = ICMP_V6_DESTINATION_UNREACHABLE;
+        icmp[1] = 4;
+        let Some(IcmpMessage::DestinationUnreachable { code, quoted }) = parse_icmp_v6(&icmp)
+        else {
+            panic!("应解析为目标不可达消息");
+        };
+        assert_eq!(code, 4);
+        assert_eq!(quoted.ports(), Some((40000, 33434)));
+    }
+
+    #[test]
+    fn packet_too_big_v6() {
+        let Some(IcmpMessage::PacketTooBig { mtu, quoted }) = parse_icmp_v6(&PACKET_TOO_BIG_V6)
+        else {
+            panic!("应解析为包过大消息");
+        };
+        assert_eq!(mtu, 1280);
+        assert_eq!(quoted.dst, google_v6());
+        assert_eq!(quoted.protocol, IP_PROTOCOL_UDP);
+        assert_eq!(quoted.ports(), Some((40000, 33434)));
+    }
+
+    #[test]
+    fn packet_too_big_v6_caps_mtu() {
+        let mut icmp = PACKET_TOO_BIG_V6;
+        icmp[4..8].copy_from_slice(&100_000u32.to_be_bytes());
+        assert!(matches!(
+            parse_icmp_v6(&icmp),
+            Some(IcmpMessage::PacketTooBig { mtu: u16::MAX, .. })
+        ));
+    }
+
+    #[test]
+    fn truncated_v6() {
+        for len in [0, 7] {
+            assert_eq!(parse_icmp_v6(&ECHO_REPLY_V6[..len]), None);
+        }
+        // 引用的IPv6头部不完整或上层头部不足8字节
+        for len in [47, 55] {
+            assert_eq!(parse_icmp_v6(&TIME_EXCEEDED_V6[..len]), None);
+        }
+    }
+
+    #[test]
+    fn unknown_type_v6() {
+        let mut icmp = ECHO_REPLY_V6;
+        icmp[0] = ICMP_V6_ECHO_REQUEST;
+        assert_eq!(parse_icmp_v6(&icmp), None);
+    }
+}
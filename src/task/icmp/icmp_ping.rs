@@ -1,64 +1,49 @@
 use crate::ping_error::PingError;
+use crate::task::icmp::icmp_packet::{
+    checksum_v4, IcmpMessage, ICMP_V4_ECHO_REQUEST, ICMP_V6_ECHO_REQUEST,
+};
+use crate::task::icmp::icmp_settings::IcmpSocketMode;
+use crate::task::icmp::icmp_socket::{IcmpSocket, ProbeKey};
 use log::trace;
-use socket2::{Domain, Protocol, SockAddr, Socket, Type};
-use std::mem::MaybeUninit;
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
-use std::sync::atomic::{AtomicU16, Ordering};
+use std::net::IpAddr;
 use std::time::Duration;
+use tokio::time::Instant;
 
-/// ICMPv4 头部长度
-const ICMP_V4_HEADER_LENGTH: usize = 20;
-
-/// 计算 ICMP 校验和（RFC 1071）
-/// 按 16 位分组累加，处理奇数长度数据，最后取反
-fn checksum_v4(buf: &[u8]) -> u16 {
-    let mut sum: u32 = 0;
-    for chunk in buf.chunks(2) {
-        let word = u16::from_be_bytes([chunk[0], chunk.get(1).copied().unwrap_or(0)]);
-        sum = sum.wrapping_add(word as u32);
-    }
-    while (sum >> 16) != 0 {
-        sum = (sum & 0xffff) + (sum >> 16);
+/// 填充负载，与 `ping` 一样使用递增的字节序列
+fn fill_payload(buf: &mut [u8]) {
+    for (i, b) in buf.iter_mut().enumerate() {
+        *b = i as u8;
     }
-    !(sum as u16)
 }
 
 /// 构造 ICMPv4 Echo Request 包
-fn build_icmp_v4_echo(id: u16, seq: u16) -> Vec<u8> {
-    let mut buf = vec![0; 8];
-    buf[0] = 8; // type = Echo Request
+pub fn build_icmp_v4_echo(id: u16, seq: u16, payload_size: usize) -> Vec<u8> {
+    let mut buf = vec![0; 8 + payload_size];
+    buf[0] = ICMP_V4_ECHO_REQUEST;
     buf[1] = 0; // code = 0
     buf[4..6].copy_from_slice(&id.to_be_bytes());
     buf[6..8].copy_from_slice(&seq.to_be_bytes());
+    fill_payload(&mut buf[8..]);
     let checksum = checksum_v4(&buf);
     buf[2..4].copy_from_slice(&checksum.to_be_bytes());
     buf
 }
 
 /// 构造 ICMPv6 Echo Request 包
-fn build_icmp_v6_echo(id: u16, seq: u16) -> Vec<u8> {
-    let mut buf = vec![0; 8];
-    buf[0] = 128; // type = Echo Request
+pub fn build_icmp_v6_echo(id: u16, seq: u16, payload_size: usize) -> Vec<u8> {
+    let mut buf = vec![0; 8 + payload_size];
+    buf[0] = ICMP_V6_ECHO_REQUEST;
     buf[1] = 0; // code = 0
     buf[4..6].copy_from_slice(&id.to_be_bytes());
     buf[6..8].copy_from_slice(&seq.to_be_bytes());
+    fill_payload(&mut buf[8..]);
     // ICMPv6 的校验和计算需包含伪头部（IPv6源/目的地址等），但操作系统内核通常自动处理，此处省略
     buf
 }
 
+#[derive(Clone)]
 pub struct IcmpPing {
     id: u16,
-    seq: AtomicU16,
-}
-
-// 手动实现 Clone（可选）
-impl Clone for IcmpPing {
-    fn clone(&self) -> Self {
-        Self {
-            id: self.id,
-            seq: AtomicU16::new(self.seq.load(Ordering::Relaxed)),
-        }
-    }
 }
 
 impl IcmpPing {
@@ -66,84 +51,72 @@ impl IcmpPing {
         Self {
             // XXX u16范围是 0~65,535，PID 最大值默认为 32,767(通过 /proc/sys/kernel/pid_max 可调整)
             id: std::process::id() as u16,
-            seq: 0.into(),
         }
     }
 
-    pub fn ping(&self, dst_ip: IpAddr, timeout: Duration) -> Result<(), PingError> {
+    /// 发送一个 Echo Request 并等待回包
+    /// # 参数
+    /// * `dst_ip` - 目标IP地址
+    /// * `timeout` - 超时时间
+    /// * `payload_size` - 负载大小(字节)
+    /// * `socket_mode` - 套接字模式
+    /// * `dont_fragment` - 是否设置DF位，不允许分片
+    ///
+    /// # 返回值
+    /// 成功时返回往返时间(RTT)
+    pub async fn ping(
+        &self,
+        dst_ip: IpAddr,
+        timeout: Duration,
+        payload_size: usize,
+        socket_mode: IcmpSocketMode,
+        dont_fragment: bool,
+    ) -> Result<Duration, PingError> {
         trace!("ping {} ....", dst_ip);
-        let (domain, protocol, src_ip) = match dst_ip {
-            IpAddr::V4(_) => (
-                Domain::IPV4,
-                Protocol::ICMPV4,
-                IpAddr::V4(Ipv4Addr::UNSPECIFIED),
-            ),
-            IpAddr::V6(_) => (
-                Domain::IPV6,
-                Protocol::ICMPV6,
-                IpAddr::V6(Ipv6Addr::UNSPECIFIED),
-            ),
-        };
-
-        // 创建原始套接字
-        let sock = Socket::new(domain, Type::RAW, Some(protocol))?;
-        sock.set_read_timeout(Some(timeout))?;
-        sock.set_write_timeout(Some(timeout))?;
-
-        // 绑定到本地 0.0.0.0 / :: 让内核选源地址
-        let src_addr = SockAddr::from(SocketAddr::new(src_ip, 0));
-        sock.bind(&src_addr)?;
+        let socket = IcmpSocket::get(&dst_ip, socket_mode)?;
+        let seq = IcmpSocket::next_seq();
 
-        let dst_addr = SockAddr::from(SocketAddr::new(dst_ip, 0));
-        // 序列号使用 u16，在达到最大值后会自然回绕，这符合 ICMP 协议规范
-        let seq = self.seq.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
-
-        // 构造并发送
+        // 构造并发送，先登记再发送，以免错过很快到达的回包
         let packet = match dst_ip {
-            IpAddr::V4(_) => build_icmp_v4_echo(self.id, seq),
-            IpAddr::V6(_) => build_icmp_v6_echo(self.id, seq),
+            IpAddr::V4(_) => build_icmp_v4_echo(self.id, seq, payload_size),
+            IpAddr::V6(_) => build_icmp_v6_echo(self.id, seq, payload_size),
         };
-        sock.send_to(&packet, &dst_addr)?;
-
-        // 接收
-        let mut buf: [MaybeUninit<u8>; 1024] = unsafe { MaybeUninit::uninit().assume_init() };
-        let (len, _) = sock.recv_from(&mut buf)?;
+        let mut pending = socket.register(ProbeKey::Echo {
+            id: socket.probe_id(self.id),
+            seq,
+            addr: dst_ip,
+        });
+        let send_time = Instant::now();
+        socket.send_to(&packet, dst_ip, None, dont_fragment).await?;
 
-        // 校验回包
-        let reply = unsafe { std::slice::from_raw_parts(buf.as_ptr() as *const u8, len) };
-        match dst_ip {
-            IpAddr::V4(_) => {
-                if reply.len() != 28 {
-                    return Err(PingError::InvalidReply(format!(
-                        "ICMPv4 reply length expect 28 bytes but {} bytes",
-                        reply.len()
-                    )));
-                }
-                if &reply[(ICMP_V4_HEADER_LENGTH + 4)..(ICMP_V4_HEADER_LENGTH + 8)] != &packet[4..8]
-                {
-                    return Err(PingError::InvalidReply(format!(
-                        "ICMPv4 reply data is not correct! send-{:?} received-{:?}",
-                        packet, reply
-                    )));
-                }
+        // 等待接收任务分发过来的回包，不属于本探测的包已经被接收任务过滤掉
+        let reply = tokio::time::timeout(timeout, pending.recv())
+            .await
+            .map_err(|_| PingError::Timeout)?
+            .ok_or(PingError::Timeout)?;
+        let rtt = reply.recv_time.duration_since(send_time);
+        match reply.message {
+            IcmpMessage::EchoReply { .. } => {}
+            IcmpMessage::DestinationUnreachable { code, .. } => {
+                return Err(PingError::DestinationUnreachable {
+                    router: reply.source,
+                    code,
+                });
+            }
+            IcmpMessage::TimeExceeded { .. } => {
+                return Err(PingError::TimeExceeded {
+                    router: reply.source,
+                });
             }
-            IpAddr::V6(_) => {
-                if reply.len() != 8 {
-                    return Err(PingError::InvalidReply(format!(
-                        "ICMPv6 reply length expect 8 bytes but {} bytes",
-                        reply.len(),
-                    )));
-                }
-                if &reply[4..8] != &packet[4..8] {
-                    return Err(PingError::InvalidReply(format!(
-                        "ICMPv6 reply data is not correct! send-{:?} received-{:?}",
-                        packet, reply
-                    )));
-                }
+            IcmpMessage::PacketTooBig { mtu, .. } => {
+                return Err(PingError::PacketTooBig {
+                    router: reply.source,
+                    mtu,
+                });
             }
         }
 
-        trace!("ping {} success", dst_ip);
-        Ok(())
+        trace!("ping {} success in {:?}", dst_ip, rtt);
+        Ok(rtt)
     }
 }
@@ -0,0 +1,138 @@
+use crate::metrics::metrics_cst::{
+    ICMP_PATH_MTU_PROMETHEUS_METRIC_DESC, ICMP_PATH_MTU_PROMETHEUS_METRIC_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::task::icmp::icmp_packet::{
+    ICMP_HEADER_LENGTH, IP_V4_MIN_HEADER_LENGTH, IP_V6_HEADER_LENGTH,
+};
+use crate::task::icmp::icmp_ping::IcmpPing;
+use crate::task::icmp::icmp_settings::IcmpSettings;
+use log::{debug, trace};
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// 路径MTU发现的结果
+#[derive(Debug, Clone)]
+pub struct PathMtu {
+    /// 能到达目标的最大包大小(字节)，含IP头部
+    pub mtu: usize,
+    /// 最大包的往返时间
+    pub rtt: Duration,
+}
+
+impl PathMtu {
+    /// 转换成探测指标
+    pub fn to_metrics(&self) -> Vec<ProbeMetric> {
+        vec![ProbeMetric::new(
+            ICMP_PATH_MTU_PROMETHEUS_METRIC_NAME,
+            ICMP_PATH_MTU_PROMETHEUS_METRIC_DESC,
+            self.mtu as f64,
+        )]
+    }
+}
+
+/// 路径MTU发现
+///
+/// 设置DF位发送不同大小的 Echo Request，二分查找能到达目标的最大包。
+/// 先试探上限，大多数路径一个包就能得出结果；上限不通时再试探最小的包确认目标可达，
+/// 之后在两者之间二分，路由器回复包过大时直接用它报告的MTU收窄上界。
+/// 隧道等设备静默丢弃大包时，小包能通而大包超时，同样能被找出来。
+pub struct PmtuDiscovery<'a> {
+    icmp_ping: &'a IcmpPing,
+    dst_ip: IpAddr,
+    timeout: Duration,
+    settings: &'a IcmpSettings,
+}
+
+impl<'a> PmtuDiscovery<'a> {
+    /// 构造函数
+    pub fn new(
+        icmp_ping: &'a IcmpPing,
+        dst_ip: IpAddr,
+        timeout: Duration,
+        settings: &'a IcmpSettings,
+    ) -> Self {
+        Self {
+            icmp_ping,
+            dst_ip,
+            timeout,
+            settings,
+        }
+    }
+
+    /// 查找路径MTU
+    ///
+    /// # 返回值
+    /// 最小的包也无法到达目标时返回最后一次探测的错误
+    pub async fn discover(&self) -> Result<PathMtu, PingError> {
+        trace!("discover path mtu to {} ....", self.dst_ip);
+        let min = self.overhead();
+        let max = self.settings.max_mtu.max(min);
+        if let Ok(rtt) = self.probe(max).await {
+            return Ok(PathMtu { mtu: max, rtt });
+        }
+
+        // 不变式: low 能通过，high 不能通过
+        let mut low = min;
+        let mut rtt = self.probe(low).await?;
+        let mut high = max;
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            match self.probe(mid).await {
+                Ok(mid_rtt) => {
+                    low = mid;
+                    rtt = mid_rtt;
+                }
+                // 路由器报告了下一跳MTU，比它大的包都不能通过
+                Err(PingError::PacketTooBig { mtu, .. })
+                    if (low..mid).contains(&(mtu as usize)) =>
+                {
+                    high = mtu as usize + 1;
+                }
+                Err(_) => high = mid,
+            }
+        }
+
+        trace!("path mtu to {} is {}", self.dst_ip, low);
+        Ok(PathMtu { mtu: low, rtt })
+    }
+
+    /// IP头部和ICMP头部的大小，即负载为0时包的大小
+    fn overhead(&self) -> usize {
+        let ip_header_length = match self.dst_ip {
+            IpAddr::V4(_) => IP_V4_MIN_HEADER_LENGTH,
+            IpAddr::V6(_) => IP_V6_HEADER_LENGTH,
+        };
+        ip_header_length + ICMP_HEADER_LENGTH
+    }
+
+    /// 探测指定大小(含IP头部)的包能否到达目标，最多尝试 `count` 次
+    async fn probe(&self, size: usize) -> Result<Duration, PingError> {
+        let mut last_error = PingError::Timeout;
+        for _ in 0..self.settings.count {
+            match self
+                .icmp_ping
+                .ping(
+                    self.dst_ip,
+                    self.timeout,
+                    size - self.overhead(),
+                    self.settings.socket_mode,
+                    true,
+                )
+                .await
+            {
+                Ok(rtt) => return Ok(rtt),
+                Err(e) => {
+                    debug!("ping {} ({} bytes, DF) 失败: {}", self.dst_ip, size, e);
+                    // 包过大是确定的结果，不必重试
+                    if matches!(e, PingError::PacketTooBig { .. }) {
+                        return Err(e);
+                    }
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    }
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use wheel_rs::serde::duration_option_serde;
+
+/// ICMP任务配置
+///
+/// 每次探测会连续发送 `count` 个 Echo Request 包，类似 `ping -c`，
+/// 并根据收到的回包统计丢包率、RTT和抖动。
+/// 启用路径MTU发现模式时改为查找能到达目标的最大包。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct IcmpSettings {
+    /// 每次探测发送的包数
+    #[serde(default = "count_default")]
+    pub count: u16,
+    /// 相邻两个包的发送间隔
+    #[serde(with = "duration_option_serde", default = "spacing_default")]
+    pub spacing: Option<Duration>,
+    /// 每个包的负载大小(字节)，不含ICMP头部
+    #[serde(default)]
+    pub payload_size: usize,
+    /// 套接字模式
+    #[serde(default)]
+    pub socket_mode: IcmpSocketMode,
+    /// 是否设置DF位，不允许分片，目前只支持Linux
+    #[serde(default)]
+    pub dont_fragment: bool,
+    /// 是否启用路径MTU发现模式
+    ///
+    /// 启用后每次探测不再统计丢包和RTT，而是设置DF位，二分查找能到达目标的最大包(含IP头部)，
+    /// 每个包大小最多尝试 `count` 次，收到一个回包即认为能通过
+    #[serde(default)]
+    pub pmtu_discovery: bool,
+    /// 路径MTU发现的上限(字节)，含IP头部
+    #[serde(default = "max_mtu_default")]
+    pub max_mtu: usize,
+}
+
+/// ICMP套接字模式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IcmpSocketMode {
+    /// 优先使用原始套接字，没有权限(EPERM)时回退到数据报套接字
+    #[default]
+    Auto,
+    /// 原始套接字(SOCK_RAW)，需要以root运行或授予CAP_NET_RAW
+    Raw,
+    /// 数据报套接字(SOCK_DGRAM)，Linux下用户组在 `net.ipv4.ping_group_range` 内即可使用，
+    /// 内核会改写标识符，回包也不含IP头部
+    Dgram,
+}
+
+fn count_default() -> u16 {
+    1 // 默认 1 个包
+}
+fn spacing_default() -> Option<Duration> {
+    Some(Duration::from_secs(1)) // 默认 1 秒
+}
+fn max_mtu_default() -> usize {
+    1500 // 默认以太网的MTU
+}
+
+impl Default for IcmpSettings {
+    fn default() -> Self {
+        Self {
+            count: count_default(),
+            spacing: spacing_default(),
+            payload_size: 0,
+            socket_mode: IcmpSocketMode::default(),
+            dont_fragment: false,
+            pmtu_discovery: false,
+            max_mtu: max_mtu_default(),
+        }
+    }
+}
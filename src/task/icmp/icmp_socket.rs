@@ -0,0 +1,423 @@
+use crate::task::icmp::icmp_packet::{
+    parse_icmp_v4, parse_icmp_v6, IcmpMessage, IP_PROTOCOL_TCP, IP_PROTOCOL_UDP,
+};
+use crate::task::icmp::icmp_settings::IcmpSocketMode;
+use log::{debug, error, trace};
+use socket2::{Domain, Protocol, SockAddr, SockRef, Socket, Type};
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::{oneshot, Mutex as AsyncMutex};
+use tokio::time::{sleep, Instant};
+
+/// 接收缓冲区大小
+const RECV_BUFFER_SIZE: usize = 65536;
+/// 接收失败后重试前的等待时间，避免持续出错时空转占满CPU
+const RECV_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// 共享的ICMP套接字表，键为(是否是IPv6, 套接字模式)
+type IcmpSockets = HashMap<(bool, IcmpSocketMode), Arc<IcmpSocket>>;
+
+/// 全局共享的ICMP套接字
+static ICMP_SOCKETS: LazyLock<Mutex<IcmpSockets>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+/// 全局的序列号，所有目标共用，保证同一时刻在途的探测不会重复
+static SEQ: AtomicU16 = AtomicU16::new(0);
+
+/// 在途探测的键，回包按它匹配到发出它的探测
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProbeKey {
+    /// ICMP Echo 探测，按(标识符, 序列号, 目标地址)匹配
+    Echo { id: u16, seq: u16, addr: IpAddr },
+    /// UDP 探测，按(源端口, 目标端口, 目标地址)匹配，只会收到差错报文
+    Udp {
+        src_port: u16,
+        dst_port: u16,
+        addr: IpAddr,
+    },
+    /// TCP 探测，按(源端口, 目标端口, 目标地址)匹配，只会收到差错报文
+    Tcp {
+        src_port: u16,
+        dst_port: u16,
+        addr: IpAddr,
+    },
+}
+
+/// 设置套接字发出的包的TTL(IPv6为跳数限制)
+pub fn set_socket_ttl(socket: SockRef<'_>, is_v6: bool, ttl: u32) -> io::Result<()> {
+    if is_v6 {
+        socket.set_unicast_hops_v6(ttl)
+    } else {
+        socket.set_ttl_v4(ttl)
+    }
+}
+
+/// 获取套接字的路径MTU发现模式(IP_MTU_DISCOVER / IPV6_MTU_DISCOVER)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn mtu_discover(socket: SockRef<'_>, is_v6: bool) -> io::Result<libc::c_int> {
+    use std::os::fd::AsRawFd;
+    let (level, name) = if is_v6 {
+        (libc::IPPROTO_IPV6, libc::IPV6_MTU_DISCOVER)
+    } else {
+        (libc::IPPROTO_IP, libc::IP_MTU_DISCOVER)
+    };
+    let mut value: libc::c_int = 0;
+    let mut len = size_of::<libc::c_int>() as libc::socklen_t;
+    // SAFETY: value 和 len 在调用期间有效，长度与 value 的类型一致
+    let ret = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &mut value as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(value)
+}
+
+/// 设置套接字的路径MTU发现模式(IP_MTU_DISCOVER / IPV6_MTU_DISCOVER)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn set_mtu_discover(socket: SockRef<'_>, is_v6: bool, value: libc::c_int) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    let (level, name) = if is_v6 {
+        (libc::IPPROTO_IPV6, libc::IPV6_MTU_DISCOVER)
+    } else {
+        (libc::IPPROTO_IP, libc::IP_MTU_DISCOVER)
+    };
+    // SAFETY: value 在调用期间有效，长度与 value 的类型一致
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &value as *const libc::c_int as *const libc::c_void,
+            size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// 设置套接字发出的包不允许分片
+///
+/// 使用 IP_PMTUDISC_PROBE 模式：设置DF位，且不受内核缓存的路径MTU限制，
+/// 超过网卡MTU的包发送时直接返回 EMSGSIZE，路径上更小的MTU由路由器回复包过大
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn set_dont_fragment(socket: SockRef<'_>, is_v6: bool) -> io::Result<()> {
+    let value = if is_v6 {
+        libc::IPV6_PMTUDISC_PROBE
+    } else {
+        libc::IP_PMTUDISC_PROBE
+    };
+    set_mtu_discover(socket, is_v6, value)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn mtu_discover(_socket: SockRef<'_>, _is_v6: bool) -> io::Result<i32> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn set_mtu_discover(_socket: SockRef<'_>, _is_v6: bool, _value: i32) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn set_dont_fragment(_socket: SockRef<'_>, _is_v6: bool) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+/// 分发给探测的回包
+#[derive(Debug, Clone)]
+pub struct IcmpReply {
+    /// 收到回包的时间
+    pub recv_time: Instant,
+    /// 回包的源地址，差错报文则是发出它的路由器的地址
+    pub source: IpAddr,
+    /// 回包的消息
+    pub message: IcmpMessage,
+}
+
+/// 共享的ICMP套接字
+///
+/// 每个地址族和套接字模式只创建一个长期存在的非阻塞套接字，并注册到 tokio 中。
+/// 后台接收任务读取所有回包，再按 [`ProbeKey`] 分发给等待中的探测，
+/// 目标不可达、超时等差错报文则按其中引用的原始数据包分发，
+/// 因此大量目标可以同时探测，而不会阻塞运行时的工作线程，也不会误收其它进程的回包。
+pub struct IcmpSocket {
+    /// 套接字，原始套接字借用 UdpSocket 来完成异步收发
+    socket: UdpSocket,
+    /// 是否是ICMPv6
+    is_v6: bool,
+    /// 套接字模式，只会是 Raw 或 Dgram
+    mode: IcmpSocketMode,
+    /// 数据报套接字的标识符，内核会将发出的包的标识符改写成套接字绑定的"端口"
+    dgram_id: Option<u16>,
+    /// 套接字默认的TTL，临时修改TTL发送后要恢复成它
+    default_ttl: u32,
+    /// 套接字默认的路径MTU发现模式，临时禁止分片发送后要恢复成它，平台不支持时为None
+    default_mtu_discover: Option<i32>,
+    /// 发送锁，保证临时修改的TTL只作用于当次发送
+    send_lock: AsyncMutex<()>,
+    /// 等待回包的探测
+    waiters: Mutex<HashMap<ProbeKey, oneshot::Sender<IcmpReply>>>,
+}
+
+impl IcmpSocket {
+    /// 获取目标地址对应地址族和模式的共享套接字，首次调用时创建
+    ///
+    /// 创建失败时返回错误，下次调用会重新尝试创建
+    pub fn get(dst_ip: &IpAddr, mode: IcmpSocketMode) -> io::Result<Arc<IcmpSocket>> {
+        let is_v6 = dst_ip.is_ipv6();
+        let mut sockets = ICMP_SOCKETS.lock().unwrap();
+        if let Some(socket) = sockets.get(&(is_v6, mode)) {
+            return Ok(socket.clone());
+        }
+
+        let socket = match mode {
+            IcmpSocketMode::Auto => {
+                match Self::get_or_open(&mut sockets, is_v6, IcmpSocketMode::Raw) {
+                    Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                        debug!("没有权限创建ICMP原始套接字({})，回退到数据报套接字", e);
+                        Self::get_or_open(&mut sockets, is_v6, IcmpSocketMode::Dgram)?
+                    }
+                    result => result?,
+                }
+            }
+            _ => Self::get_or_open(&mut sockets, is_v6, mode)?,
+        };
+        sockets.insert((is_v6, mode), socket.clone());
+        Ok(socket)
+    }
+
+    /// 获取或创建指定模式(Raw 或 Dgram)的共享套接字
+    fn get_or_open(
+        sockets: &mut IcmpSockets,
+        is_v6: bool,
+        mode: IcmpSocketMode,
+    ) -> io::Result<Arc<IcmpSocket>> {
+        if let Some(socket) = sockets.get(&(is_v6, mode)) {
+            return Ok(socket.clone());
+        }
+        let socket = Arc::new(Self::open(is_v6, mode)?);
+        tokio::spawn(socket.clone().recv_loop());
+        sockets.insert((is_v6, mode), socket.clone());
+        Ok(socket)
+    }
+
+    /// 获取下一个序列号，在达到最大值后会自然回绕，这符合 ICMP 协议规范
+    pub fn next_seq() -> u16 {
+        SEQ.fetch_add(1, Ordering::Relaxed).wrapping_add(1)
+    }
+
+    /// 创建套接字
+    fn open(is_v6: bool, mode: IcmpSocketMode) -> io::Result<Self> {
+        let (domain, protocol, src_ip) = if is_v6 {
+            (
+                Domain::IPV6,
+                Protocol::ICMPV6,
+                IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            )
+        } else {
+            (
+                Domain::IPV4,
+                Protocol::ICMPV4,
+                IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            )
+        };
+        let socket_type = match mode {
+            IcmpSocketMode::Dgram => Type::DGRAM,
+            _ => Type::RAW,
+        };
+
+        let socket = Socket::new(domain, socket_type, Some(protocol))?;
+        // 绑定到本地 0.0.0.0 / :: 让内核选源地址，数据报套接字同时由内核分配标识符
+        socket.bind(&SockAddr::from(SocketAddr::new(src_ip, 0)))?;
+        socket.set_nonblocking(true)?;
+        let dgram_id = match mode {
+            IcmpSocketMode::Dgram => socket.local_addr()?.as_socket().map(|addr| addr.port()),
+            _ => None,
+        };
+        let default_ttl = if is_v6 {
+            socket.unicast_hops_v6()?
+        } else {
+            socket.ttl_v4()?
+        };
+        let default_mtu_discover = mtu_discover(SockRef::from(&socket), is_v6).ok();
+        debug!(
+            "创建共享的{}套接字: {:?}",
+            if is_v6 { "ICMPv6" } else { "ICMPv4" },
+            mode
+        );
+
+        Ok(Self {
+            socket: UdpSocket::from_std(socket.into())?,
+            is_v6,
+            mode,
+            dgram_id,
+            default_ttl,
+            default_mtu_discover,
+            send_lock: AsyncMutex::new(()),
+            waiters: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 获取探测实际使用的标识符，数据报套接字发出的包的标识符会被内核改写
+    pub fn probe_id(&self, id: u16) -> u16 {
+        self.dgram_id.unwrap_or(id)
+    }
+
+    /// 登记一个在途探测，须在发送之前调用，以免错过很快到达的回包
+    pub fn register(self: &Arc<Self>, key: ProbeKey) -> PendingReply {
+        let (tx, rx) = oneshot::channel();
+        self.waiters.lock().unwrap().insert(key, tx);
+        PendingReply {
+            socket: self.clone(),
+            key,
+            rx,
+        }
+    }
+
+    /// 发送数据包
+    /// # 参数
+    /// * `packet` - ICMP数据包
+    /// * `dst_ip` - 目标地址
+    /// * `ttl` - 只对本次发送生效的TTL，为None时使用默认的TTL
+    /// * `dont_fragment` - 本次发送是否设置DF位，不允许分片
+    pub async fn send_to(
+        &self,
+        packet: &[u8],
+        dst_ip: IpAddr,
+        ttl: Option<u32>,
+        dont_fragment: bool,
+    ) -> io::Result<()> {
+        let _guard = self.send_lock.lock().await;
+        let dst_addr = SocketAddr::new(dst_ip, 0);
+        let socket = || SockRef::from(&self.socket);
+        if let Some(ttl) = ttl {
+            set_socket_ttl(socket(), self.is_v6, ttl)?;
+        }
+        if dont_fragment {
+            set_dont_fragment(socket(), self.is_v6)?;
+        }
+        let result = self.socket.send_to(packet, dst_addr).await;
+        if ttl.is_some() {
+            set_socket_ttl(socket(), self.is_v6, self.default_ttl)?;
+        }
+        if let (true, Some(value)) = (dont_fragment, self.default_mtu_discover) {
+            set_mtu_discover(socket(), self.is_v6, value)?;
+        }
+        result?;
+        Ok(())
+    }
+
+    /// 后台接收任务，读取回包并分发给等待中的探测
+    async fn recv_loop(self: Arc<Self>) {
+        let mut buf = vec![0u8; RECV_BUFFER_SIZE];
+        loop {
+            let (len, src_addr) = match self.socket.recv_from(&mut buf).await {
+                Ok(result) => result,
+                Err(e) => {
+                    error!("接收ICMP回包失败: {}", e);
+                    sleep(RECV_ERROR_BACKOFF).await;
+                    continue;
+                }
+            };
+            let recv_time = Instant::now();
+            let packet = &buf[..len];
+            let message = if self.is_v6 {
+                parse_icmp_v6(packet)
+            } else {
+                parse_icmp_v4(packet, self.mode == IcmpSocketMode::Raw)
+            };
+            let Some(message) = message else {
+                trace!("忽略无法识别的ICMP包: {:?}", packet);
+                continue;
+            };
+            let Some(key) = Self::probe_key(&message, src_addr.ip()) else {
+                continue;
+            };
+            match self.waiters.lock().unwrap().remove(&key) {
+                Some(tx) => {
+                    let _ = tx.send(IcmpReply {
+                        recv_time,
+                        source: src_addr.ip(),
+                        message,
+                    });
+                }
+                None => trace!("忽略不属于任何在途探测的回包: {:?}", key),
+            }
+        }
+    }
+
+    /// 取出回包对应的探测的键
+    ///
+    /// 回显应答按其自身的标识符、序列号和源地址匹配，
+    /// 差错报文按其中引用的原始数据包的标识符、序列号(或端口)和目标地址匹配
+    fn probe_key(message: &IcmpMessage, src_ip: IpAddr) -> Option<ProbeKey> {
+        match message {
+            IcmpMessage::EchoReply { id, seq } => Some(ProbeKey::Echo {
+                id: *id,
+                seq: *seq,
+                addr: src_ip,
+            }),
+            IcmpMessage::DestinationUnreachable { quoted, .. }
+            | IcmpMessage::TimeExceeded { quoted, .. }
+            | IcmpMessage::PacketTooBig { quoted, .. } => match quoted.protocol {
+                IP_PROTOCOL_UDP | IP_PROTOCOL_TCP => {
+                    let (src_port, dst_port) = quoted.ports()?;
+                    Some(if quoted.protocol == IP_PROTOCOL_UDP {
+                        ProbeKey::Udp {
+                            src_port,
+                            dst_port,
+                            addr: quoted.dst,
+                        }
+                    } else {
+                        ProbeKey::Tcp {
+                            src_port,
+                            dst_port,
+                            addr: quoted.dst,
+                        }
+                    })
+                }
+                _ => {
+                    let (id, seq) = quoted.echo_request_id_seq()?;
+                    Some(ProbeKey::Echo {
+                        id,
+                        seq,
+                        addr: quoted.dst,
+                    })
+                }
+            },
+        }
+    }
+}
+
+/// 等待中的回包，drop时自动注销，超时或取消的探测不会残留在等待表中
+pub struct PendingReply {
+    socket: Arc<IcmpSocket>,
+    key: ProbeKey,
+    rx: oneshot::Receiver<IcmpReply>,
+}
+
+impl PendingReply {
+    /// 等待回包
+    pub async fn recv(&mut self) -> Option<IcmpReply> {
+        (&mut self.rx).await.ok()
+    }
+}
+
+impl Drop for PendingReply {
+    fn drop(&mut self) {
+        self.socket.waiters.lock().unwrap().remove(&self.key);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::metrics::metrics_cst::{
+    ICMP_JITTER_PROMETHEUS_METRIC_DESC, ICMP_JITTER_PROMETHEUS_METRIC_NAME,
+    ICMP_PACKETS_RECEIVED_PROMETHEUS_METRIC_DESC, ICMP_PACKETS_RECEIVED_PROMETHEUS_METRIC_NAME,
+    ICMP_PACKETS_SENT_PROMETHEUS_METRIC_DESC, ICMP_PACKETS_SENT_PROMETHEUS_METRIC_NAME,
+    ICMP_PACKET_LOSS_PROMETHEUS_METRIC_DESC, ICMP_PACKET_LOSS_PROMETHEUS_METRIC_NAME,
+    ICMP_RTT_PROMETHEUS_METRIC_DESC, ICMP_RTT_PROMETHEUS_METRIC_NAME,
+    STAT_PROMETHEUS_METRIC_LABEL_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use std::time::Duration;
+
+/// 一次ICMP探测的统计结果，算法与 `ping -c` 的汇总输出一致
+#[derive(Debug, Clone)]
+pub struct IcmpStats {
+    /// 发送的包数
+    pub sent: u16,
+    /// 收到的包数
+    pub received: u16,
+    /// 最小RTT
+    pub min: Option<Duration>,
+    /// 平均RTT
+    pub avg: Option<Duration>,
+    /// 最大RTT
+    pub max: Option<Duration>,
+    /// RTT的平均偏差(标准差)
+    pub mdev: Option<Duration>,
+    /// 抖动，相邻两个回包RTT之差的绝对值的平均值
+    pub jitter: Option<Duration>,
+}
+
+impl IcmpStats {
+    /// 构造函数
+    /// # 参数
+    /// * `sent` - 发送的包数
+    /// * `rtts` - 按发送顺序排列的已收到回包的RTT
+    pub fn new(sent: u16, rtts: &[Duration]) -> Self {
+        let received = rtts.len() as u16;
+        if rtts.is_empty() {
+            return Self {
+                sent,
+                received,
+                min: None,
+                avg: None,
+                max: None,
+                mdev: None,
+                jitter: None,
+            };
+        }
+
+        let secs: Vec<f64> = rtts.iter().map(Duration::as_secs_f64).collect();
+        let count = secs.len() as f64;
+        let avg = secs.iter().sum::<f64>() / count;
+        let avg_square = secs.iter().map(|s| s * s).sum::<f64>() / count;
+        // 浮点误差可能导致方差为极小的负数
+        let mdev = (avg_square - avg * avg).max(0.0).sqrt();
+        let jitter = if secs.len() > 1 {
+            secs.windows(2).map(|w| (w[1] - w[0]).abs()).sum::<f64>() / (count - 1.0)
+        } else {
+            0.0
+        };
+
+        Self {
+            sent,
+            received,
+            min: rtts.iter().min().copied(),
+            avg: Some(Duration::from_secs_f64(avg)),
+            max: rtts.iter().max().copied(),
+            mdev: Some(Duration::from_secs_f64(mdev)),
+            jitter: Some(Duration::from_secs_f64(jitter)),
+        }
+    }
+
+    /// 丢包率，范围 0.0 ~ 1.0
+    pub fn loss_ratio(&self) -> f64 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+        (self.sent - self.received) as f64 / self.sent as f64
+    }
+
+    /// 转换成探测指标，RTT单位为毫秒
+    pub fn to_metrics(&self) -> Vec<ProbeMetric> {
+        let mut metrics = vec![
+            ProbeMetric::new(
+                ICMP_PACKETS_SENT_PROMETHEUS_METRIC_NAME,
+                ICMP_PACKETS_SENT_PROMETHEUS_METRIC_DESC,
+                self.sent as f64,
+            ),
+            ProbeMetric::new(
+                ICMP_PACKETS_RECEIVED_PROMETHEUS_METRIC_NAME,
+                ICMP_PACKETS_RECEIVED_PROMETHEUS_METRIC_DESC,
+                self.received as f64,
+            ),
+            ProbeMetric::new(
+                ICMP_PACKET_LOSS_PROMETHEUS_METRIC_NAME,
+                ICMP_PACKET_LOSS_PROMETHEUS_METRIC_DESC,
+                self.loss_ratio(),
+            ),
+        ];
+        for (stat, rtt) in [
+            ("min", self.min),
+            ("avg", self.avg),
+            ("max", self.max),
+            ("mdev", self.mdev),
+        ] {
+            if let Some(rtt) = rtt {
+                metrics.push(
+                    ProbeMetric::new(
+                        ICMP_RTT_PROMETHEUS_METRIC_NAME,
+                        ICMP_RTT_PROMETHEUS_METRIC_DESC,
+                        rtt.as_secs_f64() * 1000.0,
+                    )
+                    .with_label(STAT_PROMETHEUS_METRIC_LABEL_NAME, stat),
+                );
+            }
+        }
+        if let Some(jitter) = self.jitter {
+            metrics.push(ProbeMetric::new(
+                ICMP_JITTER_PROMETHEUS_METRIC_NAME,
+                ICMP_JITTER_PROMETHEUS_METRIC_DESC,
+                jitter.as_secs_f64() * 1000.0,
+            ));
+        }
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(rtts: &[u64]) -> Vec<Duration> {
+        rtts.iter().map(|rtt| Duration::from_millis(*rtt)).collect()
+    }
+
+    /// 断言两个时长相差不到1微秒
+    fn assert_close(actual: Option<Duration>, expected_millis: f64) {
+        let actual = actual.unwrap().as_secs_f64() * 1000.0;
+        assert!(
+            (actual - expected_millis).abs() < 0.001,
+            "{} != {}",
+            actual,
+            expected_millis
+        );
+    }
+
+    #[test]
+    fn all_replied() {
+        let stats = IcmpStats::new(4, &millis(&[10, 20, 30, 40]));
+        assert_eq!(stats.received, 4);
+        assert_eq!(stats.loss_ratio(), 0.0);
+        assert_eq!(stats.min, Some(Duration::from_millis(10)));
+        assert_eq!(stats.max, Some(Duration::from_millis(40)));
+        assert_close(stats.avg, 25.0);
+        // sqrt((10² + 20² + 30² + 40²) / 4 - 25²) = sqrt(125)
+        assert_close(stats.mdev, 125f64.sqrt());
+        assert_close(stats.jitter, 10.0);
+    }
+
+    #[test]
+    fn jitter_uses_send_order() {
+        // 相邻之差为 20、10、30，与排序后的结果不同
+        let stats = IcmpStats::new(4, &millis(&[10, 30, 20, 50]));
+        assert_close(stats.jitter, 20.0);
+        assert_close(stats.avg, 27.5);
+        assert_eq!(stats.min, Some(Duration::from_millis(10)));
+        assert_eq!(stats.max, Some(Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn partial_loss() {
+        let stats = IcmpStats::new(5, &millis(&[12, 12, 12, 12]));
+        assert_eq!(stats.sent, 5);
+        assert_eq!(stats.received, 4);
+        assert_eq!(stats.loss_ratio(), 0.2);
+        assert_close(stats.avg, 12.0);
+        // 相同的RTT不会因为浮点误差得到NaN的偏差
+        assert_close(stats.mdev, 0.0);
+        assert_eq!(stats.jitter, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn single_reply() {
+        let stats = IcmpStats::new(1, &millis(&[7]));
+        assert_eq!(stats.loss_ratio(), 0.0);
+        assert_eq!(stats.min, stats.max);
+        assert_close(stats.avg, 7.0);
+        assert_eq!(stats.mdev, Some(Duration::ZERO));
+        assert_eq!(stats.jitter, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn no_reply() {
+        let stats = IcmpStats::new(3, &[]);
+        assert_eq!(stats.received, 0);
+        assert_eq!(stats.loss_ratio(), 1.0);
+        assert!(stats.min.is_none() && stats.avg.is_none() && stats.max.is_none());
+        assert!(stats.mdev.is_none() && stats.jitter.is_none());
+        // 只有发送、接收的包数和丢包率
+        assert_eq!(stats.to_metrics().len(), 3);
+    }
+
+    #[test]
+    fn nothing_sent() {
+        assert_eq!(IcmpStats::new(0, &[]).loss_ratio(), 0.0);
+    }
+
+    #[test]
+    fn metrics_in_milliseconds() {
+        let metrics = IcmpStats::new(2, &millis(&[10, 30])).to_metrics();
+        // 包数、丢包率，min/avg/max/mdev 及抖动
+        assert_eq!(metrics.len(), 8);
+        let rtt = |stat: &str| {
+            metrics
+                .iter()
+                .find(|metric| {
+                    metric.name == ICMP_RTT_PROMETHEUS_METRIC_NAME
+                        && metric.labels.iter().any(|(name, value)| {
+                            *name == STAT_PROMETHEUS_METRIC_LABEL_NAME && value == stat
+                        })
+                })
+                .map(|metric| metric.value)
+                .unwrap()
+        };
+        assert!((rtt("min") - 10.0).abs() < 0.001);
+        assert!((rtt("avg") - 20.0).abs() < 0.001);
+        assert!((rtt("max") - 30.0).abs() < 0.001);
+        assert!((rtt("mdev") - 10.0).abs() < 0.001);
+    }
+}
@@ -1,2 +1,7 @@
 pub mod icmp_executor;
-pub mod icmp_ping;
\ No newline at end of file
+pub mod icmp_packet;
+pub mod icmp_ping;
+pub mod icmp_pmtu;
+pub mod icmp_settings;
+pub mod icmp_socket;
+pub mod icmp_stats;
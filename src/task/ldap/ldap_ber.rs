@@ -0,0 +1,334 @@
+use crate::ping_error::PingError;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// 整数
+pub const BER_INTEGER: u8 = 0x02;
+/// 字节串
+pub const BER_OCTET_STRING: u8 = 0x04;
+/// 布尔值
+pub const BER_BOOLEAN: u8 = 0x01;
+/// 枚举
+pub const BER_ENUMERATED: u8 = 0x0a;
+/// 序列
+pub const BER_SEQUENCE: u8 = 0x30;
+
+/// 接收的消息的最大长度
+const MAX_MESSAGE_SIZE: usize = 256 * 1024;
+
+/// 构造BER编码的数据
+#[derive(Default)]
+pub struct BerWriter {
+    buf: Vec<u8>,
+}
+
+impl BerWriter {
+    /// 追加一个元素
+    /// # 参数
+    /// * `tag` - 标签
+    /// * `value` - 元素的内容
+    pub fn element(&mut self, tag: u8, value: &[u8]) -> &mut Self {
+        self.buf.push(tag);
+        let len = value.len();
+        if len < 0x80 {
+            self.buf.push(len as u8);
+        } else {
+            // 长格式，先是长度占的字节数，再是大端的长度
+            let bytes = len.to_be_bytes();
+            let start = bytes
+                .iter()
+                .position(|b| *b != 0)
+                .unwrap_or(bytes.len() - 1);
+            self.buf.push(0x80 | (bytes.len() - start) as u8);
+            self.buf.extend_from_slice(&bytes[start..]);
+        }
+        self.buf.extend_from_slice(value);
+        self
+    }
+
+    /// 追加一个构造类型的元素，内容由 `build` 构造
+    pub fn constructed(&mut self, tag: u8, build: impl FnOnce(&mut BerWriter)) -> &mut Self {
+        let mut inner = BerWriter::default();
+        build(&mut inner);
+        self.element(tag, &inner.buf)
+    }
+
+    /// 追加非负整数，`tag` 为 `BER_INTEGER` 或 `BER_ENUMERATED`
+    pub fn integer(&mut self, tag: u8, value: u32) -> &mut Self {
+        let bytes = (value as u64).to_be_bytes();
+        let mut start = bytes
+            .iter()
+            .position(|b| *b != 0)
+            .unwrap_or(bytes.len() - 1);
+        if bytes[start] & 0x80 != 0 {
+            // 最高位为1时补0，避免被当作负数
+            start -= 1;
+        }
+        self.element(tag, &bytes[start..])
+    }
+
+    /// 原样追加已编码的元素
+    pub fn encoded(&mut self, element: &[u8]) -> &mut Self {
+        self.buf.extend_from_slice(element);
+        self
+    }
+
+    /// 追加字节串
+    pub fn octet_string(&mut self, value: &[u8]) -> &mut Self {
+        self.element(BER_OCTET_STRING, value)
+    }
+
+    /// 追加布尔值
+    pub fn boolean(&mut self, value: bool) -> &mut Self {
+        self.element(BER_BOOLEAN, &[if value { 0xff } else { 0 }])
+    }
+
+    /// 构造完成的数据
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// 解析BER编码的数据
+pub struct BerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BerReader<'a> {
+    /// 构造函数
+    /// # 参数
+    /// * `data` - 编码的数据
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// 读取一个元素
+    ///
+    /// # 返回值
+    /// 标签及元素的内容
+    pub fn element(&mut self) -> Result<(u8, &'a [u8]), PingError> {
+        let (&tag, rest) = self.data.split_first().ok_or_else(truncated)?;
+        let (&first, mut rest) = rest.split_first().ok_or_else(truncated)?;
+        let len = if first < 0x80 {
+            first as usize
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > size_of::<usize>() || rest.len() < count {
+                return Err(truncated());
+            }
+            let (bytes, tail) = rest.split_at(count);
+            rest = tail;
+            bytes.iter().fold(0, |len, b| len << 8 | *b as usize)
+        };
+        if rest.len() < len {
+            return Err(truncated());
+        }
+        let (value, rest) = rest.split_at(len);
+        self.data = rest;
+        Ok((tag, value))
+    }
+
+    /// 读取指定标签的元素
+    pub fn expect(&mut self, tag: u8) -> Result<&'a [u8], PingError> {
+        match self.element()? {
+            (actual, value) if actual == tag => Ok(value),
+            (actual, _) => Err(PingError::InvalidReply(format!(
+                "期望标签 0x{:02x}，收到 0x{:02x}",
+                tag, actual
+            ))),
+        }
+    }
+
+    /// 读取非负整数，`tag` 为 `BER_INTEGER` 或 `BER_ENUMERATED`
+    pub fn integer(&mut self, tag: u8) -> Result<u32, PingError> {
+        let value = self.expect(tag)?;
+        // 负数或超过32位时视为错误，最高位为1时前面补的0不计
+        let significant = value.strip_prefix(&[0]).unwrap_or(value);
+        if value.first().is_none_or(|b| b & 0x80 != 0) || significant.len() > 4 {
+            return Err(PingError::InvalidReply(String::from("错误的整数")));
+        }
+        Ok(significant.iter().fold(0, |n, b| n << 8 | *b as u32))
+    }
+
+    /// 读取字节串
+    pub fn octet_string(&mut self) -> Result<&'a [u8], PingError> {
+        self.expect(BER_OCTET_STRING)
+    }
+}
+
+/// 读取一个完整的消息
+///
+/// # 返回值
+/// 消息的内容，即外层序列里面的部分
+pub async fn read_message<S>(stream: &mut S) -> Result<Vec<u8>, PingError>
+where
+    S: AsyncRead + Unpin,
+{
+    let tag = stream.read_u8().await?;
+    if tag != BER_SEQUENCE {
+        return Err(PingError::InvalidReply(format!(
+            "期望消息序列，收到标签 0x{:02x}",
+            tag
+        )));
+    }
+    let first = stream.read_u8().await?;
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 {
+            return Err(PingError::InvalidReply(format!(
+                "错误的消息长度字节数: {}",
+                count
+            )));
+        }
+        let mut bytes = [0; 4];
+        stream.read_exact(&mut bytes[4 - count..]).await?;
+        u32::from_be_bytes(bytes) as usize
+    };
+    if len > MAX_MESSAGE_SIZE {
+        return Err(PingError::InvalidReply(format!(
+            "消息长度 {} 超过 {} 字节",
+            len, MAX_MESSAGE_SIZE
+        )));
+    }
+    let mut message = vec![0; len];
+    stream.read_exact(&mut message).await?;
+    Ok(message)
+}
+
+fn truncated() -> PingError {
+    PingError::InvalidReply(String::from("消息长度不足"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_and_long_lengths() {
+        for (len, header) in [
+            (0, vec![BER_OCTET_STRING, 0x00]),
+            (127, vec![BER_OCTET_STRING, 0x7f]),
+            (128, vec![BER_OCTET_STRING, 0x81, 0x80]),
+            (300, vec![BER_OCTET_STRING, 0x82, 0x01, 0x2c]),
+            (70000, vec![BER_OCTET_STRING, 0x83, 0x01, 0x11, 0x70]),
+        ] {
+            let value = vec![b'x'; len];
+            let mut writer = BerWriter::default();
+            writer.octet_string(&value);
+            let encoded = writer.into_bytes();
+            assert_eq!(encoded[..header.len()], header);
+            assert_eq!(encoded.len(), header.len() + len);
+
+            let mut reader = BerReader::new(&encoded);
+            assert_eq!(reader.octet_string().unwrap(), value);
+            assert!(reader.element().is_err());
+        }
+    }
+
+    #[test]
+    fn integers() {
+        for (value, encoded) in [
+            (0, vec![0x00]),
+            (1, vec![0x01]),
+            (127, vec![0x7f]),
+            (128, vec![0x00, 0x80]),
+            (256, vec![0x01, 0x00]),
+            (u32::MAX, vec![0x00, 0xff, 0xff, 0xff, 0xff]),
+        ] {
+            let mut writer = BerWriter::default();
+            writer.integer(BER_INTEGER, value);
+            let mut expected = vec![BER_INTEGER, encoded.len() as u8];
+            expected.extend_from_slice(&encoded);
+            let bytes = writer.into_bytes();
+            assert_eq!(bytes, expected);
+            assert_eq!(BerReader::new(&bytes).integer(BER_INTEGER).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn invalid_integers() {
+        for encoded in [
+            &[BER_INTEGER, 0x00][..],
+            &[BER_INTEGER, 0x01, 0x80],
+            &[BER_INTEGER, 0x05, 0x01, 0x00, 0x00, 0x00, 0x00],
+        ] {
+            assert!(BerReader::new(encoded).integer(BER_INTEGER).is_err());
+        }
+        // 标签不符
+        assert!(BerReader::new(&[BER_ENUMERATED, 0x01, 0x00])
+            .integer(BER_INTEGER)
+            .is_err());
+    }
+
+    #[test]
+    fn constructed() {
+        let mut writer = BerWriter::default();
+        writer.constructed(BER_SEQUENCE, |w| {
+            w.integer(BER_INTEGER, 1)
+                .boolean(true)
+                .octet_string(b"dc=example");
+        });
+        let bytes = writer.into_bytes();
+        assert_eq!(
+            bytes,
+            [
+                0x30, 0x12, 0x02, 0x01, 0x01, 0x01, 0x01, 0xff, 0x04, 0x0a, b'd', b'c', b'=', b'e',
+                b'x', b'a', b'm', b'p', b'l', b'e'
+            ]
+        );
+
+        let mut reader = BerReader::new(BerReader::new(&bytes).expect(BER_SEQUENCE).unwrap());
+        assert_eq!(reader.integer(BER_INTEGER).unwrap(), 1);
+        assert_eq!(reader.expect(BER_BOOLEAN).unwrap(), [0xff]);
+        assert_eq!(reader.octet_string().unwrap(), b"dc=example");
+    }
+
+    #[test]
+    fn truncated_elements() {
+        for encoded in [
+            &[][..],
+            &[BER_OCTET_STRING],
+            &[BER_OCTET_STRING, 0x03, b'a', b'b'],
+            &[BER_OCTET_STRING, 0x80],
+            &[BER_OCTET_STRING, 0x82, 0x01],
+            &[BER_OCTET_STRING, 0x89, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            &[
+                BER_OCTET_STRING,
+                0x88,
+                0xff,
+                0xff,
+                0xff,
+                0xff,
+                0xff,
+                0xff,
+                0xff,
+                0xff,
+            ],
+        ] {
+            assert!(BerReader::new(encoded).element().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn read_messages() {
+        let mut stream: &[u8] = &[0x30, 0x03, 0x02, 0x01, 0x01, 0x30, 0x81, 0x01, 0xaa];
+        assert_eq!(read_message(&mut stream).await.unwrap(), [0x02, 0x01, 0x01]);
+        assert_eq!(read_message(&mut stream).await.unwrap(), [0xaa]);
+        assert!(read_message(&mut stream).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_invalid_messages() {
+        for message in [
+            &[0x04, 0x00][..],
+            &[0x30, 0x80],
+            &[0x30, 0x85, 0x00, 0x00, 0x00, 0x00, 0x01],
+            &[0x30, 0x84, 0x7f, 0xff, 0xff, 0xff],
+            &[0x30, 0x05, 0x02, 0x01],
+        ] {
+            let mut stream = message;
+            assert!(read_message(&mut stream).await.is_err());
+        }
+    }
+}
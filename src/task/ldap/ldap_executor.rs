@@ -0,0 +1,67 @@
+use crate::executor::{ExecReport, Executor};
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::TargetResolver;
+use crate::task::ldap::ldap_ping::LdapPing;
+use crate::task::ldap::ldap_settings::LdapSettings;
+use async_trait::async_trait;
+use log::trace;
+use std::net::IpAddr;
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct LdapExecutor {
+    url: String,
+    resolver: TargetResolver,
+    ldap_ping: LdapPing,
+    timeout: Duration,
+}
+
+impl LdapExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `url` - LDAP地址，如 `ldaps://ldap.example.com`
+    /// * `timeout` - 一个 `Duration`，表示超时时间
+    /// * `settings` - LDAP任务配置
+    /// * `resolve` - 解析主机名的配置
+    ///
+    /// # Panics
+    /// 配置错误时会触发panic，配置在加载时已经检查过
+    pub fn new(
+        url: String,
+        timeout: Duration,
+        settings: LdapSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        let ldap_ping = LdapPing::new(&url, &settings).unwrap();
+        Self {
+            resolver: TargetResolver::new(ldap_ping.host(), resolve),
+            ldap_ping,
+            url,
+            timeout,
+        }
+    }
+
+    /// 探测一个地址
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        trace!("开始执行 LDAP 任务: bind {} via {}", self.url, ip_addr);
+        let reply = self.ldap_ping.ping(ip_addr, self.timeout).await?;
+        Ok(reply.into_report())
+    }
+}
+
+#[async_trait]
+impl Executor for LdapExecutor {
+    fn get_name(&self) -> String {
+        String::from("LDAP")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move { executor.exec_addr(ip_addr).await }
+            })
+            .await
+    }
+}
@@ -0,0 +1,317 @@
+use crate::task::ldap::ldap_ber::{BerWriter, BER_SEQUENCE};
+
+/// 与: 所有条件都满足
+const FILTER_AND: u8 = 0xa0;
+/// 或: 任一条件满足
+const FILTER_OR: u8 = 0xa1;
+/// 非
+const FILTER_NOT: u8 = 0xa2;
+/// 等于
+const FILTER_EQUALITY: u8 = 0xa3;
+/// 子串匹配，值中带 `*`
+const FILTER_SUBSTRINGS: u8 = 0xa4;
+/// 大于等于
+const FILTER_GREATER_OR_EQUAL: u8 = 0xa5;
+/// 小于等于
+const FILTER_LESS_OR_EQUAL: u8 = 0xa6;
+/// 属性存在，值为 `*`
+const FILTER_PRESENT: u8 = 0x87;
+/// 近似匹配
+const FILTER_APPROX: u8 = 0xa8;
+/// 子串的开头部分
+const SUBSTRING_INITIAL: u8 = 0x80;
+/// 子串的中间部分
+const SUBSTRING_ANY: u8 = 0x81;
+/// 子串的结尾部分
+const SUBSTRING_FINAL: u8 = 0x82;
+
+/// 把字符串形式(RFC 4515)的过滤条件编码成搜索请求中的BER格式
+///
+/// 支持与(&)、或(|)、非(!)、等于、大于等于、小于等于、近似、存在及子串匹配，
+/// 不支持扩展匹配(:=)，值中的特殊字符须以 `\XX` 转义
+///
+/// # 返回值
+/// 格式错误时返回错误信息
+pub fn encode_filter(filter: &str) -> Result<Vec<u8>, String> {
+    let mut parser = FilterParser {
+        data: filter.trim().as_bytes(),
+        pos: 0,
+    };
+    let mut writer = BerWriter::default();
+    parser
+        .filter(&mut writer)
+        .and_then(|_| {
+            if parser.pos < parser.data.len() {
+                Err(String::from("过滤条件后有多余的字符"))
+            } else {
+                Ok(())
+            }
+        })
+        .map_err(|e| format!("错误的过滤条件({}): {}", filter, e))?;
+    Ok(writer.into_bytes())
+}
+
+/// 过滤条件的递归下降解析器
+struct FilterParser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl FilterParser<'_> {
+    /// 解析一个带括号的过滤条件
+    fn filter(&mut self, writer: &mut BerWriter) -> Result<(), String> {
+        self.expect(b'(')?;
+        match self.data.get(self.pos) {
+            Some(b'&') => self.list(FILTER_AND, writer)?,
+            Some(b'|') => self.list(FILTER_OR, writer)?,
+            Some(b'!') => {
+                self.pos += 1;
+                let mut inner = BerWriter::default();
+                self.filter(&mut inner)?;
+                writer.element(FILTER_NOT, &inner.into_bytes());
+            }
+            _ => self.item(writer)?,
+        }
+        self.expect(b')')
+    }
+
+    /// 解析与、或后面的多个过滤条件
+    fn list(&mut self, tag: u8, writer: &mut BerWriter) -> Result<(), String> {
+        self.pos += 1;
+        let mut inner = BerWriter::default();
+        while self.data.get(self.pos) == Some(&b'(') {
+            self.filter(&mut inner)?;
+        }
+        writer.element(tag, &inner.into_bytes());
+        Ok(())
+    }
+
+    /// 解析属性与值的比较
+    fn item(&mut self, writer: &mut BerWriter) -> Result<(), String> {
+        let end = self.data[self.pos..]
+            .iter()
+            .position(|b| *b == b')')
+            .map(|len| self.pos + len)
+            .ok_or("缺少 )")?;
+        let item = &self.data[self.pos..end];
+        self.pos = end;
+
+        let eq = item.iter().position(|b| *b == b'=').ok_or("缺少 =")?;
+        let (attr, tag) = match eq.checked_sub(1).map(|i| item[i]) {
+            Some(b'~') => (&item[..eq - 1], FILTER_APPROX),
+            Some(b'>') => (&item[..eq - 1], FILTER_GREATER_OR_EQUAL),
+            Some(b'<') => (&item[..eq - 1], FILTER_LESS_OR_EQUAL),
+            _ => (&item[..eq], FILTER_EQUALITY),
+        };
+        let value = &item[eq + 1..];
+        if attr.contains(&b':') {
+            return Err(String::from("不支持扩展匹配"));
+        }
+        if attr.is_empty()
+            || !attr
+                .iter()
+                .all(|b| b.is_ascii_alphanumeric() || b"-;.".contains(b))
+        {
+            return Err(format!("错误的属性名 {:?}", String::from_utf8_lossy(attr)));
+        }
+
+        if tag != FILTER_EQUALITY || !value.contains(&b'*') {
+            let value = unescape(value)?;
+            writer.constructed(tag, |w| {
+                w.octet_string(attr).octet_string(&value);
+            });
+            return Ok(());
+        }
+        if value == b"*" {
+            writer.element(FILTER_PRESENT, attr);
+            return Ok(());
+        }
+        // 以 * 分隔的第一段为开头，最后一段为结尾，中间的为任意位置，空的部分省略
+        let parts: Vec<&[u8]> = value.split(|b| *b == b'*').collect();
+        let mut substrings = BerWriter::default();
+        for (i, part) in parts.iter().enumerate() {
+            if part.is_empty() {
+                continue;
+            }
+            let tag = match i {
+                0 => SUBSTRING_INITIAL,
+                i if i == parts.len() - 1 => SUBSTRING_FINAL,
+                _ => SUBSTRING_ANY,
+            };
+            substrings.element(tag, &unescape(part)?);
+        }
+        let mut inner = BerWriter::default();
+        inner
+            .octet_string(attr)
+            .element(BER_SEQUENCE, &substrings.into_bytes());
+        writer.element(FILTER_SUBSTRINGS, &inner.into_bytes());
+        Ok(())
+    }
+
+    /// 跳过期望的字符
+    fn expect(&mut self, expected: u8) -> Result<(), String> {
+        match self.data.get(self.pos) {
+            Some(b) if *b == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(b) => Err(format!(
+                "位置 {} 期望 {}，实际为 {}",
+                self.pos, expected as char, *b as char
+            )),
+            None => Err(format!("缺少 {}", expected as char)),
+        }
+    }
+}
+
+/// 还原值中以 `\XX` 转义的字符
+fn unescape(value: &[u8]) -> Result<Vec<u8>, String> {
+    let mut result = Vec::with_capacity(value.len());
+    let mut iter = value.iter();
+    while let Some(&b) = iter.next() {
+        if b != b'\\' {
+            if b == b'(' {
+                return Err(String::from("值中的 ( 须转义为 \\28"));
+            }
+            result.push(b);
+            continue;
+        }
+        let hex: Vec<u8> = iter.by_ref().take(2).copied().collect();
+        let byte = std::str::from_utf8(&hex)
+            .ok()
+            .filter(|hex| hex.len() == 2)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .ok_or("\\ 后须为两位十六进制数")?;
+        result.push(byte);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn present() {
+        let mut expected = vec![FILTER_PRESENT, 11];
+        expected.extend_from_slice(b"objectClass");
+        assert_eq!(encode_filter("(objectClass=*)").unwrap(), expected);
+    }
+
+    #[test]
+    fn equality() {
+        assert_eq!(
+            encode_filter(" (uid=pong) ").unwrap(),
+            [
+                &[FILTER_EQUALITY, 0x0b, 0x04, 0x03][..],
+                b"uid",
+                &[0x04, 0x04],
+                b"pong"
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn substrings() {
+        // 只有中间部分
+        assert_eq!(
+            encode_filter("(uid=*abc*)").unwrap(),
+            [0xa4, 0x0c, 0x04, 0x03, b'u', b'i', b'd', 0x30, 0x05, 0x81, 0x03, b'a', b'b', b'c']
+        );
+        // 只有开头或结尾部分
+        assert_eq!(
+            encode_filter("(uid=ab*)").unwrap(),
+            [0xa4, 0x0b, 0x04, 0x03, b'u', b'i', b'd', 0x30, 0x04, 0x80, 0x02, b'a', b'b']
+        );
+        assert_eq!(
+            encode_filter("(uid=*z)").unwrap(),
+            [0xa4, 0x0a, 0x04, 0x03, b'u', b'i', b'd', 0x30, 0x03, 0x82, 0x01, b'z']
+        );
+    }
+
+    #[test]
+    fn substrings_with_escapes() {
+        // 转义的 * 是值的一部分，不是通配符
+        assert_eq!(
+            encode_filter(r"(cn=a\2ab*\5c)").unwrap(),
+            [
+                0xa4, 0x0e, 0x04, 0x02, b'c', b'n', 0x30, 0x08, 0x80, 0x03, b'a', b'*', b'b', 0x82,
+                0x01, b'\\'
+            ]
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let filter =
+            r"(&(objectClass=person)(|(uid=a*b*c)(cn~=x))(!(age>=10))(sn<=z)(mail=*)(cn=\28x\29))";
+        assert_eq!(
+            encode_filter(filter).unwrap(),
+            [
+                0xa0, 0x5b, 0xa3, 0x15, 0x04, 0x0b, 0x6f, 0x62, 0x6a, 0x65, 0x63, 0x74, 0x43, 0x6c,
+                0x61, 0x73, 0x73, 0x04, 0x06, 0x70, 0x65, 0x72, 0x73, 0x6f, 0x6e, 0xa1, 0x1b, 0xa4,
+                0x10, 0x04, 0x03, 0x75, 0x69, 0x64, 0x30, 0x09, 0x80, 0x01, 0x61, 0x81, 0x01, 0x62,
+                0x82, 0x01, 0x63, 0xa8, 0x07, 0x04, 0x02, 0x63, 0x6e, 0x04, 0x01, 0x78, 0xa2, 0x0b,
+                0xa5, 0x09, 0x04, 0x03, 0x61, 0x67, 0x65, 0x04, 0x02, 0x31, 0x30, 0xa6, 0x07, 0x04,
+                0x02, 0x73, 0x6e, 0x04, 0x01, 0x7a, 0x87, 0x04, 0x6d, 0x61, 0x69, 0x6c, 0xa3, 0x09,
+                0x04, 0x02, 0x63, 0x6e, 0x04, 0x03, 0x28, 0x78, 0x29
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_or() {
+        assert_eq!(encode_filter("(|)").unwrap(), [FILTER_OR, 0x00]);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            unescape(br"\2a\28\29\5C\00caf\c3\a9").unwrap(),
+            b"*()\\\0caf\xc3\xa9"
+        );
+        assert!(unescape(br"a\2").is_err());
+        assert!(unescape(br"a\zz").is_err());
+    }
+
+    /// 断言过滤条件格式错误，且错误信息包含 `reason`
+    fn assert_error(filter: &str, reason: &str) {
+        let error = encode_filter(filter).unwrap_err();
+        assert!(
+            error.starts_with(&format!("错误的过滤条件({}): ", filter)) && error.contains(reason),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn unbalanced_parentheses() {
+        assert_error("(&(uid=a)", "缺少 )");
+        assert_error("(uid=a", "缺少 )");
+        assert_error("(uid=a))", "多余的字符");
+        assert_error("uid=a", "位置 0 期望 (，实际为 u");
+        assert_error("", "缺少 (");
+        assert_error("(!(uid=a)(cn=b))", "期望 )");
+    }
+
+    #[test]
+    fn extensible_match() {
+        assert_error("(cn:caseExactMatch:=Pong)", "不支持扩展匹配");
+        assert_error("(:dn:2.4.6.8.10:=x)", "不支持扩展匹配");
+    }
+
+    #[test]
+    fn unescaped_parenthesis() {
+        assert_error("(cn=a(b)", "值中的 ( 须转义为 \\28");
+        assert_error("(cn=a(b*)", "值中的 ( 须转义为 \\28");
+        assert_error(r"(cn=a\2)", "\\ 后须为两位十六进制数");
+    }
+
+    #[test]
+    fn bad_attribute() {
+        assert_error("(=a)", "错误的属性名");
+        assert_error("(c n=a)", "错误的属性名");
+        assert_error("(uid)", "缺少 =");
+    }
+}
@@ -0,0 +1,412 @@
+use crate::executor::ExecReport;
+use crate::metrics::metrics_cst::{
+    LDAP_DURATION_PROMETHEUS_METRIC_DESC, LDAP_DURATION_PROMETHEUS_METRIC_NAME,
+    LDAP_ENTRIES_PROMETHEUS_METRIC_DESC, LDAP_ENTRIES_PROMETHEUS_METRIC_NAME,
+    PHASE_PROMETHEUS_METRIC_LABEL_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::task::database::db_probe::DbTls;
+use crate::task::ldap::ldap_ber::{
+    read_message, BerReader, BerWriter, BER_ENUMERATED, BER_INTEGER, BER_SEQUENCE,
+};
+use crate::task::ldap::ldap_filter::encode_filter;
+use crate::task::ldap::ldap_settings::{LdapScope, LdapSettings};
+use crate::task::tcp::tcp_ping::TcpPing;
+use crate::task::tcp::tcp_settings::TcpSettings;
+use http::Uri;
+use log::trace;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+/// ldap的默认端口号
+const LDAP_PORT: u16 = 389;
+/// ldaps的默认端口号
+const LDAPS_PORT: u16 = 636;
+/// LDAPv3
+const LDAP_VERSION: u32 = 3;
+/// 绑定请求的消息ID
+const BIND_MESSAGE_ID: u32 = 1;
+/// 搜索请求的消息ID
+const SEARCH_MESSAGE_ID: u32 = 2;
+/// 解除绑定的消息ID
+const UNBIND_MESSAGE_ID: u32 = 3;
+/// 绑定请求
+const BIND_REQUEST: u8 = 0x60;
+/// 绑定响应
+const BIND_RESPONSE: u8 = 0x61;
+/// 解除绑定
+const UNBIND_REQUEST: u8 = 0x42;
+/// 搜索请求
+const SEARCH_REQUEST: u8 = 0x63;
+/// 搜索返回的条目
+const SEARCH_RESULT_ENTRY: u8 = 0x64;
+/// 搜索结束
+const SEARCH_RESULT_DONE: u8 = 0x65;
+/// 搜索返回的引用
+const SEARCH_RESULT_REFERENCE: u8 = 0x73;
+/// 扩展响应，消息ID为0时是服务器断开连接的通知
+const EXTENDED_RESPONSE: u8 = 0x78;
+/// 简单绑定的密码
+const SIMPLE_AUTH: u8 = 0x80;
+/// 不请求任何属性(RFC 4511)，只统计条目数
+const NO_ATTRIBUTES: &[u8] = b"1.1";
+/// 结果码: 成功
+const RESULT_SUCCESS: u32 = 0;
+/// 结果码: 超过条目数限制，已返回的条目仍然有效
+const RESULT_SIZE_LIMIT_EXCEEDED: u32 = 4;
+
+/// LDAP探测各阶段的耗时
+#[derive(Debug, Clone, Default)]
+pub struct LdapTimings {
+    /// 建立TCP连接
+    pub connect: Duration,
+    /// 从连接建立到绑定成功，包括TLS握手，未完成时为None
+    pub bind: Option<Duration>,
+    /// 从发出搜索请求到搜索结束，未完成时为None
+    pub search: Option<Duration>,
+}
+
+/// 一次LDAP探测的结果
+#[derive(Debug, Default)]
+pub struct LdapReply {
+    /// 各阶段的耗时
+    pub timings: LdapTimings,
+    /// 搜索返回的条目数，搜索未完成时为None
+    pub entries: Option<u32>,
+    /// 绑定、搜索失败或条目数不足的原因
+    pub error: Option<PingError>,
+}
+
+impl LdapReply {
+    /// 转换成执行报告，以各阶段的耗时之和作为耗时
+    pub fn into_report(self) -> ExecReport {
+        let timings = &self.timings;
+        let phases = [
+            ("connect", Some(timings.connect)),
+            ("bind", timings.bind),
+            ("search", timings.search),
+        ];
+        let mut metrics: Vec<ProbeMetric> = phases
+            .iter()
+            .filter_map(|(phase, duration)| {
+                duration.map(|duration| {
+                    ProbeMetric::new(
+                        LDAP_DURATION_PROMETHEUS_METRIC_NAME,
+                        LDAP_DURATION_PROMETHEUS_METRIC_DESC,
+                        duration.as_secs_f64() * 1000.0,
+                    )
+                    .with_label(PHASE_PROMETHEUS_METRIC_LABEL_NAME, phase)
+                })
+            })
+            .collect();
+        if let Some(entries) = self.entries {
+            metrics.push(ProbeMetric::new(
+                LDAP_ENTRIES_PROMETHEUS_METRIC_NAME,
+                LDAP_ENTRIES_PROMETHEUS_METRIC_DESC,
+                entries as f64,
+            ));
+        }
+        ExecReport {
+            elapsed: Some(phases.iter().filter_map(|(_, duration)| *duration).sum()),
+            error: self.error,
+            metrics,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct LdapPing {
+    url: String,
+    /// 主机名，IPv6地址带方括号
+    host: String,
+    port: u16,
+    /// TLS配置，ldap连接时为None
+    tls: Option<DbTls>,
+    /// 绑定的DN及密码，匿名绑定时为None
+    bind: Option<(String, String)>,
+    base_dn: String,
+    scope: LdapScope,
+    /// 编码后的过滤条件
+    filter: Vec<u8>,
+    min_entries: u32,
+}
+
+impl LdapPing {
+    /// 构造函数
+    /// # 参数
+    /// * `url` - LDAP地址，如 `ldaps://ldap.example.com`，端口号省略时ldap为389，ldaps为636
+    /// * `settings` - LDAP任务配置
+    ///
+    /// # 返回值
+    /// 地址、凭据或过滤条件错误时返回错误信息
+    pub fn new(url: &str, settings: &LdapSettings) -> Result<Self, String> {
+        let uri = Uri::from_str(url).map_err(|e| format!("错误的地址({}): {}", url, e))?;
+        let secure = match uri.scheme_str() {
+            Some("ldap") => false,
+            Some("ldaps") => true,
+            _ => return Err(format!("地址须以ldap://或ldaps://开头: {}", url)),
+        };
+        if !matches!(uri.path(), "" | "/") || uri.query().is_some() {
+            return Err(format!(
+                "地址中不能带DN或搜索条件，请使用base-dn及filter配置: {}",
+                url
+            ));
+        }
+        let host = uri
+            .host()
+            .ok_or_else(|| format!("地址中缺少主机名: {}", url))?
+            .to_string();
+
+        let credentials = settings.credentials.credentials()?;
+        let bind = match (credentials.username, credentials.password) {
+            (Some(dn), Some(password)) => Some((dn, password)),
+            (None, None) => None,
+            _ => return Err(String::from("用户名(绑定的DN)和密码须同时配置")),
+        };
+
+        Ok(Self {
+            url: url.to_string(),
+            port: uri
+                .port_u16()
+                .unwrap_or(if secure { LDAPS_PORT } else { LDAP_PORT }),
+            tls: secure
+                .then(|| DbTls::new(&host, settings.insecure))
+                .transpose()?,
+            host,
+            bind,
+            base_dn: settings.base_dn.clone(),
+            scope: settings.scope,
+            filter: encode_filter(&settings.filter)?,
+            min_entries: settings.min_entries,
+        })
+    }
+
+    /// 主机名，IPv6地址带方括号
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// 连接目标的一个地址，绑定后执行搜索，结束后解除绑定
+    ///
+    /// # 返回值
+    /// 连接失败时返回错误，之后的失败记录在结果中
+    pub async fn ping(&self, ip_addr: IpAddr, timeout: Duration) -> Result<LdapReply, PingError> {
+        let socket_addr = SocketAddr::new(ip_addr, self.port);
+        trace!("ldap {} via {} ....", self.url, socket_addr);
+        let start = Instant::now();
+        let (stream, connect) = TcpPing::new(socket_addr, &TcpSettings::default())
+            .connect(timeout)
+            .await?;
+        let mut reply = LdapReply {
+            timings: LdapTimings {
+                connect,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = tokio::time::timeout(
+            timeout.saturating_sub(start.elapsed()),
+            self.session(stream, &mut reply),
+        )
+        .await
+        .unwrap_or(Err(PingError::Timeout));
+        reply.error = result.err();
+        trace!("ldap {} via {} {:?}", self.url, socket_addr, reply);
+        Ok(reply)
+    }
+
+    /// 需要时先完成TLS握手
+    async fn session(&self, stream: TcpStream, reply: &mut LdapReply) -> Result<(), PingError> {
+        let mark = Instant::now();
+        match &self.tls {
+            Some(tls) => self.converse(tls.connect(stream).await?, mark, reply).await,
+            None => self.converse(stream, mark, reply).await,
+        }
+    }
+
+    /// 绑定并搜索
+    ///
+    /// # 参数
+    /// * `mark` - TCP连接建立的时间
+    async fn converse<S>(
+        &self,
+        mut stream: S,
+        mark: Instant,
+        reply: &mut LdapReply,
+    ) -> Result<(), PingError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (dn, password) = self
+            .bind
+            .as_ref()
+            .map_or(("", ""), |(dn, password)| (dn.as_str(), password.as_str()));
+        send(&mut stream, BIND_MESSAGE_ID, BIND_REQUEST, |w| {
+            w.integer(BER_INTEGER, LDAP_VERSION)
+                .octet_string(dn.as_bytes())
+                .element(SIMPLE_AUTH, password.as_bytes());
+        })
+        .await?;
+        let message = read_response(&mut stream, BIND_MESSAGE_ID).await?;
+        let (op, body) = message.op()?;
+        if op != BIND_RESPONSE {
+            return Err(invalid(&format!("期望绑定响应，收到 0x{:02x}", op)));
+        }
+        check_result("绑定", body, &[RESULT_SUCCESS])?;
+        reply.timings.bind = Some(mark.elapsed());
+
+        let mark = Instant::now();
+        send(&mut stream, SEARCH_MESSAGE_ID, SEARCH_REQUEST, |w| {
+            w.octet_string(self.base_dn.as_bytes())
+                .integer(BER_ENUMERATED, self.scope as u32)
+                .integer(BER_ENUMERATED, 0) // 不解引用别名
+                .integer(BER_INTEGER, 0) // 不限条目数
+                .integer(BER_INTEGER, 0) // 不限时间，由探测的超时控制
+                .boolean(true) // 只返回属性名
+                .encoded(&self.filter)
+                .constructed(BER_SEQUENCE, |w| {
+                    w.octet_string(NO_ATTRIBUTES);
+                });
+        })
+        .await?;
+        let mut entries = 0;
+        loop {
+            let message = read_response(&mut stream, SEARCH_MESSAGE_ID).await?;
+            match message.op()? {
+                (SEARCH_RESULT_ENTRY, _) => entries += 1,
+                (SEARCH_RESULT_REFERENCE, _) => {}
+                (SEARCH_RESULT_DONE, body) => {
+                    check_result("搜索", body, &[RESULT_SUCCESS, RESULT_SIZE_LIMIT_EXCEEDED])?;
+                    break;
+                }
+                (op, _) => return Err(invalid(&format!("搜索时收到意外的消息 0x{:02x}", op))),
+            }
+        }
+        reply.timings.search = Some(mark.elapsed());
+        reply.entries = Some(entries);
+
+        let _ = send(&mut stream, UNBIND_MESSAGE_ID, UNBIND_REQUEST, |_| {}).await;
+        if entries < self.min_entries {
+            return Err(invalid(&format!(
+                "搜索返回 {} 个条目，少于 {}",
+                entries, self.min_entries
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 一个响应消息
+struct LdapMessage {
+    /// 去掉外层序列的消息
+    data: Vec<u8>,
+}
+
+impl LdapMessage {
+    /// 消息ID之后的操作
+    ///
+    /// # 返回值
+    /// 操作的标签及内容
+    fn op(&self) -> Result<(u8, &[u8]), PingError> {
+        let mut reader = BerReader::new(&self.data);
+        reader.integer(BER_INTEGER)?;
+        reader.element()
+    }
+}
+
+/// 发送一个请求
+/// # 参数
+/// * `message_id` - 消息ID
+/// * `op` - 操作的标签
+/// * `build` - 构造操作的内容
+async fn send<S>(
+    stream: &mut S,
+    message_id: u32,
+    op: u8,
+    build: impl FnOnce(&mut BerWriter),
+) -> Result<(), PingError>
+where
+    S: AsyncWrite + Unpin,
+{
+    let mut writer = BerWriter::default();
+    writer.constructed(BER_SEQUENCE, |w| {
+        w.integer(BER_INTEGER, message_id).constructed(op, build);
+    });
+    stream.write_all(&writer.into_bytes()).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// 读取指定请求的响应，服务器通知断开连接时返回错误
+async fn read_response<S>(stream: &mut S, message_id: u32) -> Result<LdapMessage, PingError>
+where
+    S: AsyncRead + Unpin,
+{
+    let message = LdapMessage {
+        data: read_message(stream).await?,
+    };
+    let id = BerReader::new(&message.data).integer(BER_INTEGER)?;
+    if id == 0
+        && let (EXTENDED_RESPONSE, body) = message.op()?
+    {
+        check_result("连接", body, &[])?;
+    }
+    if id != message_id {
+        return Err(invalid(&format!("期望消息ID {}，收到 {}", message_id, id)));
+    }
+    Ok(message)
+}
+
+/// 检查响应中的结果码
+/// # 参数
+/// * `action` - 请求的操作，用于错误信息
+/// * `body` - 响应的内容，以LDAPResult开头
+/// * `accepted` - 视为成功的结果码
+fn check_result(action: &str, body: &[u8], accepted: &[u32]) -> Result<(), PingError> {
+    let mut reader = BerReader::new(body);
+    let code = reader.integer(BER_ENUMERATED)?;
+    if accepted.contains(&code) {
+        return Ok(());
+    }
+    reader.octet_string()?; // matchedDN
+    let message = String::from_utf8_lossy(reader.octet_string()?);
+    Err(invalid(&format!(
+        "{}失败: {}({}) {}",
+        action,
+        result_code_name(code),
+        code,
+        message.trim()
+    )))
+}
+
+/// 常见结果码的名称(RFC 4511)
+fn result_code_name(code: u32) -> &'static str {
+    match code {
+        1 => "operationsError",
+        2 => "protocolError",
+        3 => "timeLimitExceeded",
+        7 => "authMethodNotSupported",
+        8 => "strongerAuthRequired",
+        11 => "adminLimitExceeded",
+        13 => "confidentialityRequired",
+        32 => "noSuchObject",
+        34 => "invalidDNSyntax",
+        48 => "inappropriateAuthentication",
+        49 => "invalidCredentials",
+        50 => "insufficientAccessRights",
+        51 => "busy",
+        52 => "unavailable",
+        53 => "unwillingToPerform",
+        80 => "other",
+        _ => "resultCode",
+    }
+}
+
+fn invalid(reason: &str) -> PingError {
+    PingError::InvalidReply(reason.to_string())
+}
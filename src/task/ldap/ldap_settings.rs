@@ -0,0 +1,65 @@
+use crate::task::database::credential_settings::CredentialSettings;
+use serde::{Deserialize, Serialize};
+
+/// LDAP任务配置
+///
+/// 连接目标地址(`ldap://` 或 `ldaps://`)后先绑定，再执行一次搜索，
+/// 绑定失败、搜索失败或返回的条目数少于 `min-entries` 时探测失败。
+/// 没有配置凭据时匿名绑定，配置了时以用户名作为DN简单绑定。
+/// 默认搜索根DSE，大多数服务器允许匿名读取。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct LdapSettings {
+    /// 简单绑定的凭据，用户名为绑定的DN，用户名和密码须同时配置
+    #[serde(flatten)]
+    pub credentials: CredentialSettings,
+    /// 搜索的起点DN，默认为空，即根DSE
+    #[serde(default)]
+    pub base_dn: String,
+    /// 搜索的范围
+    #[serde(default)]
+    pub scope: LdapScope,
+    /// 搜索的过滤条件，格式见 RFC 4515，如 `(&(objectClass=person)(uid=pong))`
+    #[serde(default = "filter_default")]
+    pub filter: String,
+    /// 搜索返回的最少条目数
+    #[serde(default = "min_entries_default")]
+    pub min_entries: u32,
+    /// ldaps连接时是否跳过证书校验，用于自签名证书的服务
+    #[serde(default)]
+    pub insecure: bool,
+}
+
+/// 搜索的范围，取值为搜索请求中的编码
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LdapScope {
+    /// 只搜索起点本身
+    #[default]
+    Base = 0,
+    /// 只搜索起点的直接下级
+    One = 1,
+    /// 搜索起点及其所有下级
+    Sub = 2,
+}
+
+fn filter_default() -> String {
+    String::from("(objectClass=*)") // 默认匹配所有条目
+}
+
+fn min_entries_default() -> u32 {
+    1 // 默认至少返回1个条目
+}
+
+impl Default for LdapSettings {
+    fn default() -> Self {
+        Self {
+            credentials: CredentialSettings::default(),
+            base_dn: String::new(),
+            scope: LdapScope::default(),
+            filter: filter_default(),
+            min_entries: min_entries_default(),
+            insecure: false,
+        }
+    }
+}
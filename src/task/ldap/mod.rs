@@ -0,0 +1,5 @@
+pub mod ldap_ber;
+pub mod ldap_executor;
+pub mod ldap_filter;
+pub mod ldap_ping;
+pub mod ldap_settings;
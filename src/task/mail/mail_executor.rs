@@ -0,0 +1,74 @@
+use crate::executor::{ExecReport, Executor};
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::TargetResolver;
+use crate::task::database::db_probe::split_target;
+use crate::task::mail::mail_ping::{MailPing, MailProtocol};
+use crate::task::mail::mail_settings::MailSettings;
+use async_trait::async_trait;
+use log::trace;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct MailExecutor {
+    protocol: MailProtocol,
+    resolver: TargetResolver,
+    port: u16,
+    mail_ping: MailPing,
+    timeout: Duration,
+}
+
+impl MailExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `protocol` - 邮件服务的协议
+    /// * `host_port` - 邮件服务的主机名，端口号可以省略，默认为协议的默认端口号
+    /// * `timeout` - 一个 `Duration`，表示超时时间
+    /// * `settings` - 邮件服务任务配置
+    /// * `resolve` - 解析主机名的配置
+    ///
+    /// # Panics
+    /// 配置错误时会触发panic，配置在加载时已经检查过
+    pub fn new(
+        protocol: MailProtocol,
+        host_port: String,
+        timeout: Duration,
+        settings: MailSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        let (host, port) = split_target(&host_port, protocol.default_port());
+        Self {
+            protocol,
+            resolver: TargetResolver::new(&host, resolve),
+            port,
+            mail_ping: MailPing::new(protocol, &host, &settings).unwrap(),
+            timeout,
+        }
+    }
+
+    /// 探测一个地址
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        let socket_addr = SocketAddr::new(ip_addr, self.port);
+        trace!("开始执行 {} 任务: ping {}", self.protocol, socket_addr);
+        let reply = self.mail_ping.ping(socket_addr, self.timeout).await?;
+        trace!("ping {} {:?}", socket_addr, reply);
+        Ok(reply.into_report())
+    }
+}
+
+#[async_trait]
+impl Executor for MailExecutor {
+    fn get_name(&self) -> String {
+        self.protocol.to_string()
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move { executor.exec_addr(ip_addr).await }
+            })
+            .await
+    }
+}
@@ -0,0 +1,562 @@
+use crate::executor::ExecReport;
+use crate::metrics::metrics_cst::{
+    MAIL_CERT_EXPIRY_PROMETHEUS_METRIC_DESC, MAIL_CERT_EXPIRY_PROMETHEUS_METRIC_NAME,
+    MAIL_STEP_DURATION_PROMETHEUS_METRIC_DESC, MAIL_STEP_DURATION_PROMETHEUS_METRIC_NAME,
+    MAIL_STEP_PASSED_PROMETHEUS_METRIC_DESC, MAIL_STEP_PASSED_PROMETHEUS_METRIC_NAME,
+    STEP_PROMETHEUS_METRIC_LABEL_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::task::database::db_probe::DbTls;
+use crate::task::mail::mail_settings::MailSettings;
+use crate::task::tcp::tcp_ping::TcpPing;
+use crate::task::tcp::tcp_settings::TcpSettings;
+use crate::task::tls::tls_handshake::LeafCertificate;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::net::SocketAddr;
+use std::time::Duration;
+use strum_macros::Display;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+/// 单行回复的最大字节数
+const MAX_LINE_SIZE: usize = 8 * 1024;
+/// 多行回复的最大行数
+const MAX_LINES: usize = 256;
+/// EHLO命令中默认发送的主机名
+const EHLO_DEFAULT: &str = "pong-rs";
+
+/// 邮件服务的协议
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum MailProtocol {
+    Smtp,
+    Imap,
+    Pop3,
+}
+
+impl MailProtocol {
+    /// 协议的默认端口号
+    pub fn default_port(self) -> u16 {
+        match self {
+            MailProtocol::Smtp => 25,
+            MailProtocol::Imap => 143,
+            MailProtocol::Pop3 => 110,
+        }
+    }
+
+    /// 升级到TLS的命令，也是服务器能力中的名称
+    fn starttls_command(self) -> &'static str {
+        match self {
+            MailProtocol::Pop3 => "STLS",
+            _ => "STARTTLS",
+        }
+    }
+}
+
+/// 探测的步骤
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum MailStep {
+    /// 读取欢迎信息
+    Banner,
+    /// 查询服务器的能力
+    Hello,
+    /// 升级到TLS，包括TLS握手及升级后重新查询服务器的能力
+    Starttls,
+    /// 认证
+    Auth,
+}
+
+/// 一次邮件服务探测的结果
+#[derive(Debug)]
+pub struct MailReply {
+    /// 建立TCP连接的耗时
+    pub connect: Duration,
+    /// 按配置要执行的步骤
+    pub steps: Vec<MailStep>,
+    /// 已通过步骤的耗时，按执行的顺序
+    pub passed: Vec<Duration>,
+    /// 失败的原因
+    pub error: Option<PingError>,
+    /// 升级到TLS后服务器的叶子证书
+    pub leaf: Option<LeafCertificate>,
+}
+
+impl MailReply {
+    /// 转换成执行报告，以建立连接及已通过步骤的耗时之和作为耗时
+    pub fn into_report(self) -> ExecReport {
+        let mut metrics = vec![];
+        for (index, step) in self.steps.iter().enumerate() {
+            let duration = self.passed.get(index);
+            metrics.push(
+                ProbeMetric::new(
+                    MAIL_STEP_PASSED_PROMETHEUS_METRIC_NAME,
+                    MAIL_STEP_PASSED_PROMETHEUS_METRIC_DESC,
+                    if duration.is_some() { 1.0 } else { 0.0 },
+                )
+                .with_label(STEP_PROMETHEUS_METRIC_LABEL_NAME, step),
+            );
+            if let Some(duration) = duration {
+                metrics.push(
+                    ProbeMetric::new(
+                        MAIL_STEP_DURATION_PROMETHEUS_METRIC_NAME,
+                        MAIL_STEP_DURATION_PROMETHEUS_METRIC_DESC,
+                        duration.as_secs_f64() * 1000.0,
+                    )
+                    .with_label(STEP_PROMETHEUS_METRIC_LABEL_NAME, step),
+                );
+            }
+        }
+        if let Some(leaf) = &self.leaf {
+            metrics.push(ProbeMetric::new(
+                MAIL_CERT_EXPIRY_PROMETHEUS_METRIC_NAME,
+                MAIL_CERT_EXPIRY_PROMETHEUS_METRIC_DESC,
+                leaf.seconds_to_expiry() as f64,
+            ));
+        }
+        ExecReport {
+            elapsed: Some(self.connect + self.passed.iter().sum::<Duration>()),
+            error: self.error,
+            metrics,
+        }
+    }
+}
+
+/// 探测的进度
+struct MailProgress {
+    passed: Vec<Duration>,
+    leaf: Option<LeafCertificate>,
+    /// 上一个步骤结束的时间
+    mark: Instant,
+}
+
+impl MailProgress {
+    /// 记录当前步骤通过
+    fn pass(&mut self) {
+        self.passed.push(self.mark.elapsed());
+        self.mark = Instant::now();
+    }
+}
+
+#[derive(Clone)]
+pub struct MailPing {
+    protocol: MailProtocol,
+    /// 认证的用户名及密码，不认证时为None
+    login: Option<(String, String)>,
+    /// TLS配置，不升级到TLS时为None
+    tls: Option<DbTls>,
+    ehlo: String,
+}
+
+impl MailPing {
+    /// 构造函数
+    /// # 参数
+    /// * `protocol` - 邮件服务的协议
+    /// * `host` - 目标的主机名，用于校验证书
+    /// * `settings` - 邮件服务任务配置
+    ///
+    /// # 返回值
+    /// 配置了密码而没有用户名或凭据的环境变量不存在时返回错误信息
+    pub fn new(
+        protocol: MailProtocol,
+        host: &str,
+        settings: &MailSettings,
+    ) -> Result<Self, String> {
+        let credentials = settings.credentials.credentials()?;
+        let login = match (credentials.username, credentials.password) {
+            (Some(username), Some(password)) => Some((username, password)),
+            (None, Some(_)) => return Err(String::from("配置了密码时必须配置用户名")),
+            _ => None,
+        };
+        Ok(Self {
+            protocol,
+            login,
+            tls: settings
+                .starttls
+                .then(|| DbTls::new(host, settings.insecure))
+                .transpose()?,
+            ehlo: settings
+                .ehlo
+                .clone()
+                .unwrap_or_else(|| EHLO_DEFAULT.to_string()),
+        })
+    }
+
+    /// 按配置要执行的步骤
+    fn steps(&self) -> Vec<MailStep> {
+        let mut steps = vec![MailStep::Banner, MailStep::Hello];
+        if self.tls.is_some() {
+            steps.push(MailStep::Starttls);
+        }
+        if self.login.is_some() {
+            steps.push(MailStep::Auth);
+        }
+        steps
+    }
+
+    /// 建立连接并依次执行各步骤，结束后退出登录
+    ///
+    /// # 返回值
+    /// 连接失败时返回错误，步骤失败记录在结果中
+    pub async fn ping(
+        &self,
+        socket_addr: SocketAddr,
+        timeout: Duration,
+    ) -> Result<MailReply, PingError> {
+        let start = Instant::now();
+        let (stream, connect) = TcpPing::new(socket_addr, &TcpSettings::default())
+            .connect(timeout)
+            .await?;
+        let mut progress = MailProgress {
+            passed: vec![],
+            leaf: None,
+            mark: Instant::now(),
+        };
+        let result = tokio::time::timeout(
+            timeout.saturating_sub(start.elapsed()),
+            self.converse(stream, &mut progress),
+        )
+        .await
+        .unwrap_or(Err(PingError::Timeout));
+
+        // 所有步骤都通过后退出登录时超时不算失败
+        let steps = self.steps();
+        let error = match (result, steps.get(progress.passed.len())) {
+            (Err(e), Some(step)) => Some(PingError::StepFailed {
+                step: step.to_string(),
+                source: Box::new(e),
+            }),
+            _ => None,
+        };
+        Ok(MailReply {
+            connect,
+            steps,
+            passed: progress.passed,
+            error,
+            leaf: progress.leaf,
+        })
+    }
+
+    /// 执行各步骤，需要时升级到TLS
+    async fn converse(
+        &self,
+        stream: TcpStream,
+        progress: &mut MailProgress,
+    ) -> Result<(), PingError> {
+        let mut conversation = Conversation::new(self.protocol, stream);
+        conversation.banner().await?;
+        progress.pass();
+        let capabilities = conversation.hello(&self.ehlo).await?;
+        progress.pass();
+
+        let Some(tls) = &self.tls else {
+            return self.login(conversation, &capabilities, progress).await;
+        };
+        conversation.starttls(&capabilities).await?;
+        let stream = tls.connect(conversation.into_inner()?).await?;
+        progress.leaf = stream
+            .get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(|cert| LeafCertificate::parse(cert));
+        // 升级后服务器的能力可能变化，例如只在加密连接上提供认证
+        let mut conversation = Conversation::new(self.protocol, stream);
+        let capabilities = conversation.hello(&self.ehlo).await?;
+        progress.pass();
+        self.login(conversation, &capabilities, progress).await
+    }
+
+    /// 配置了凭据时认证，最后退出登录
+    async fn login<S>(
+        &self,
+        mut conversation: Conversation<S>,
+        capabilities: &[String],
+        progress: &mut MailProgress,
+    ) -> Result<(), PingError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        if let Some((username, password)) = &self.login {
+            conversation.auth(capabilities, username, password).await?;
+            progress.pass();
+        }
+        conversation.quit().await;
+        Ok(())
+    }
+}
+
+/// 与邮件服务器的对话
+struct Conversation<S> {
+    protocol: MailProtocol,
+    stream: BufReader<S>,
+    /// IMAP命令的序号
+    tag: u32,
+}
+
+impl<S> Conversation<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn new(protocol: MailProtocol, stream: S) -> Self {
+        Self {
+            protocol,
+            stream: BufReader::new(stream),
+            tag: 0,
+        }
+    }
+
+    /// 取回连接用于TLS握手
+    ///
+    /// # 返回值
+    /// 服务器在升级前多发了数据时返回错误，避免明文注入的数据被当成加密后的回复
+    fn into_inner(self) -> Result<S, PingError> {
+        if !self.stream.buffer().is_empty() {
+            return Err(invalid("服务器在升级到TLS前发送了多余的数据"));
+        }
+        Ok(self.stream.into_inner())
+    }
+
+    /// 读取并检查欢迎信息
+    async fn banner(&mut self) -> Result<(), PingError> {
+        match self.protocol {
+            MailProtocol::Smtp => self.smtp_reply(220).await.map(drop),
+            MailProtocol::Imap => {
+                let line = self.read_line().await?;
+                if line.starts_with("* OK") || line.starts_with("* PREAUTH") {
+                    Ok(())
+                } else {
+                    Err(invalid(&format!("错误的欢迎信息: {}", line)))
+                }
+            }
+            MailProtocol::Pop3 => self.pop3_reply().await.map(drop),
+        }
+    }
+
+    /// 查询服务器的能力
+    ///
+    /// # 返回值
+    /// 服务器的能力，转换成大写，每项的第一个词为能力的名称
+    async fn hello(&mut self, ehlo: &str) -> Result<Vec<String>, PingError> {
+        let capabilities = match self.protocol {
+            MailProtocol::Smtp => {
+                self.send(&format!("EHLO {}", ehlo)).await?;
+                // 第一行是服务器的问候
+                self.smtp_reply(250).await?.into_iter().skip(1).collect()
+            }
+            MailProtocol::Imap => self
+                .imap_command("CAPABILITY")
+                .await?
+                .iter()
+                .filter_map(|line| line.strip_prefix("* CAPABILITY "))
+                .flat_map(|line| line.split_whitespace().map(String::from))
+                .collect(),
+            MailProtocol::Pop3 => {
+                self.send("CAPA").await?;
+                self.pop3_reply().await?;
+                self.pop3_lines().await?
+            }
+        };
+        Ok(capabilities
+            .into_iter()
+            .map(|capability| capability.to_uppercase())
+            .collect())
+    }
+
+    /// 请求升级到TLS，服务器同意后可以开始握手
+    async fn starttls(&mut self, capabilities: &[String]) -> Result<(), PingError> {
+        let command = self.protocol.starttls_command();
+        if !has_capability(capabilities, command) {
+            return Err(invalid(&format!("服务器不支持{}", command)));
+        }
+        match self.protocol {
+            MailProtocol::Smtp => {
+                self.send(command).await?;
+                self.smtp_reply(220).await.map(drop)
+            }
+            MailProtocol::Imap => self.imap_command(command).await.map(drop),
+            MailProtocol::Pop3 => {
+                self.send(command).await?;
+                self.pop3_reply().await.map(drop)
+            }
+        }
+    }
+
+    /// 以用户名及密码认证
+    ///
+    /// SMTP按服务器的支持使用 `AUTH PLAIN` 或 `AUTH LOGIN`，IMAP使用 `LOGIN`，POP3使用 `USER`/`PASS`
+    async fn auth(
+        &mut self,
+        capabilities: &[String],
+        username: &str,
+        password: &str,
+    ) -> Result<(), PingError> {
+        match self.protocol {
+            MailProtocol::Smtp => {
+                let mechanisms: Vec<&str> = capabilities
+                    .iter()
+                    .filter_map(|capability| capability.strip_prefix("AUTH"))
+                    .flat_map(|mechanisms| mechanisms.split([' ', '=']))
+                    .collect();
+                if mechanisms.contains(&"PLAIN") {
+                    let token = STANDARD.encode(format!("\0{}\0{}", username, password));
+                    self.send(&format!("AUTH PLAIN {}", token)).await?;
+                } else if mechanisms.contains(&"LOGIN") {
+                    self.send("AUTH LOGIN").await?;
+                    self.smtp_reply(334).await?;
+                    self.send(&STANDARD.encode(username)).await?;
+                    self.smtp_reply(334).await?;
+                    self.send(&STANDARD.encode(password)).await?;
+                } else {
+                    return Err(invalid("服务器不支持PLAIN或LOGIN认证"));
+                }
+                self.smtp_reply(235).await.map(drop)
+            }
+            MailProtocol::Imap => {
+                if has_capability(capabilities, "LOGINDISABLED") {
+                    return Err(invalid("服务器禁止了LOGIN认证"));
+                }
+                let command = format!("LOGIN {} {}", quote(username), quote(password));
+                self.imap_command(&command).await.map(drop)
+            }
+            MailProtocol::Pop3 => {
+                self.send(&format!("USER {}", username)).await?;
+                self.pop3_reply().await?;
+                self.send(&format!("PASS {}", password)).await?;
+                self.pop3_reply().await.map(drop)
+            }
+        }
+    }
+
+    /// 退出登录，不等待服务器的回复
+    async fn quit(&mut self) {
+        let command = match self.protocol {
+            MailProtocol::Imap => {
+                self.tag += 1;
+                format!("a{} LOGOUT", self.tag)
+            }
+            _ => String::from("QUIT"),
+        };
+        let _ = self.send(&command).await;
+    }
+
+    /// 读取SMTP的回复，回复码与期望的不同时返回错误
+    ///
+    /// # 返回值
+    /// 回复各行去掉回复码后的内容
+    async fn smtp_reply(&mut self, expected: u16) -> Result<Vec<String>, PingError> {
+        let mut lines = vec![];
+        loop {
+            let line = self.read_line().await?;
+            let code = line
+                .get(..3)
+                .and_then(|code| code.parse::<u16>().ok())
+                .ok_or_else(|| invalid(&format!("错误的回复: {}", line)))?;
+            if code != expected {
+                return Err(invalid(&line));
+            }
+            // 回复码后为 - 表示还有下一行
+            let more = line.as_bytes().get(3) == Some(&b'-');
+            lines.push(line.get(4..).unwrap_or_default().to_string());
+            if !more {
+                return Ok(lines);
+            }
+            if lines.len() >= MAX_LINES {
+                return Err(invalid("回复的行数过多"));
+            }
+        }
+    }
+
+    /// 发送IMAP命令并读取到带标签的结果
+    ///
+    /// # 返回值
+    /// 结果前的无标签回复
+    async fn imap_command(&mut self, command: &str) -> Result<Vec<String>, PingError> {
+        self.tag += 1;
+        let tag = format!("a{} ", self.tag);
+        self.send(&format!("{}{}", tag, command)).await?;
+        let mut lines = vec![];
+        loop {
+            let line = self.read_line().await?;
+            if let Some(status) = line.strip_prefix(&tag) {
+                return if status.starts_with("OK") {
+                    Ok(lines)
+                } else {
+                    Err(invalid(status))
+                };
+            }
+            if lines.len() >= MAX_LINES {
+                return Err(invalid("回复的行数过多"));
+            }
+            lines.push(line);
+        }
+    }
+
+    /// 读取POP3的单行回复，不是 `+OK` 时返回错误
+    async fn pop3_reply(&mut self) -> Result<String, PingError> {
+        let line = self.read_line().await?;
+        match line.strip_prefix("+OK") {
+            Some(text) => Ok(text.trim_start().to_string()),
+            None => Err(invalid(&line)),
+        }
+    }
+
+    /// 读取POP3的多行回复，直到只有 `.` 的一行
+    async fn pop3_lines(&mut self) -> Result<Vec<String>, PingError> {
+        let mut lines = vec![];
+        loop {
+            let line = self.read_line().await?;
+            if line == "." {
+                return Ok(lines);
+            }
+            if lines.len() >= MAX_LINES {
+                return Err(invalid("回复的行数过多"));
+            }
+            // 以 . 开头的行会多加一个 .
+            lines.push(line.strip_prefix('.').unwrap_or(&line).to_string());
+        }
+    }
+
+    /// 发送一行命令
+    async fn send(&mut self, line: &str) -> Result<(), PingError> {
+        let stream = self.stream.get_mut();
+        stream.write_all(format!("{}\r\n", line).as_bytes()).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+
+    /// 读取一行，不含行尾
+    async fn read_line(&mut self) -> Result<String, PingError> {
+        let mut line = vec![];
+        let read = (&mut self.stream)
+            .take(MAX_LINE_SIZE as u64)
+            .read_until(b'\n', &mut line)
+            .await?;
+        if read == 0 {
+            return Err(invalid("连接已被关闭"));
+        }
+        if !line.ends_with(b"\n") {
+            return Err(invalid(&format!("回复的一行超过 {} 字节", MAX_LINE_SIZE)));
+        }
+        Ok(String::from_utf8_lossy(&line).trim_end().to_string())
+    }
+}
+
+/// 服务器是否有指定的能力
+fn has_capability(capabilities: &[String], name: &str) -> bool {
+    capabilities
+        .iter()
+        .any(|capability| capability.split_whitespace().next() == Some(name))
+}
+
+/// IMAP的带引号字符串
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn invalid(reason: &str) -> PingError {
+    PingError::InvalidReply(reason.to_string())
+}
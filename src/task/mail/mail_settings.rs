@@ -0,0 +1,22 @@
+use crate::task::database::credential_settings::CredentialSettings;
+use serde::{Deserialize, Serialize};
+
+/// 邮件服务任务配置，SMTP、IMAP和POP3任务共用
+///
+/// 连接后读取欢迎信息，再查询服务器支持的能力(SMTP为EHLO，IMAP为CAPABILITY，POP3为CAPA)，
+/// 按配置升级到TLS并认证，任一步骤失败时探测失败。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct MailSettings {
+    /// 认证的凭据，没有配置密码时不认证
+    #[serde(flatten)]
+    pub credentials: CredentialSettings,
+    /// 是否通过STARTTLS(POP3为STLS)升级到TLS，服务器不支持时探测失败
+    #[serde(default)]
+    pub starttls: bool,
+    /// 是否跳过证书校验，用于自签名证书的服务
+    #[serde(default)]
+    pub insecure: bool,
+    /// SMTP的EHLO命令中发送的主机名，默认为pong-rs
+    pub ehlo: Option<String>,
+}
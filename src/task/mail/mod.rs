@@ -0,0 +1,3 @@
+pub mod mail_executor;
+pub mod mail_ping;
+pub mod mail_settings;
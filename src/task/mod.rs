@@ -1,3 +1,19 @@
+pub mod database;
+pub mod dns;
+pub mod grpc;
 pub mod http;
+pub mod http_flow;
 pub mod icmp;
+pub mod ldap;
+pub mod mail;
+pub mod mqtt;
+pub mod mysql;
+pub mod ntp;
+pub mod postgres;
+pub mod redis;
+pub mod ssh;
 pub mod tcp;
+pub mod tls;
+pub mod traceroute;
+pub mod udp;
+pub mod websocket;
@@ -0,0 +1,4 @@
+pub mod mqtt_executor;
+pub mod mqtt_packet;
+pub mod mqtt_ping;
+pub mod mqtt_settings;
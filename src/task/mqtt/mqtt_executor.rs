@@ -0,0 +1,76 @@
+use crate::executor::{ExecReport, Executor};
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::TargetResolver;
+use crate::task::database::db_probe::split_target;
+use crate::task::mqtt::mqtt_ping::MqttPing;
+use crate::task::mqtt::mqtt_settings::MqttSettings;
+use async_trait::async_trait;
+use log::trace;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// MQTT的默认端口号
+const MQTT_PORT: u16 = 1883;
+/// MQTT over TLS的默认端口号
+const MQTTS_PORT: u16 = 8883;
+
+#[derive(Clone)]
+pub struct MqttExecutor {
+    resolver: TargetResolver,
+    port: u16,
+    mqtt_ping: MqttPing,
+    timeout: Duration,
+}
+
+impl MqttExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `host_port` - MQTT服务器的主机名，端口号可以省略，默认为1883，使用TLS时为8883
+    /// * `timeout` - 一个 `Duration`，表示超时时间
+    /// * `settings` - MQTT任务配置
+    /// * `resolve` - 解析主机名的配置
+    ///
+    /// # Panics
+    /// 配置错误时会触发panic，配置在加载时已经检查过
+    pub fn new(
+        host_port: String,
+        timeout: Duration,
+        settings: MqttSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        let default_port = if settings.tls { MQTTS_PORT } else { MQTT_PORT };
+        let (host, port) = split_target(&host_port, default_port);
+        Self {
+            resolver: TargetResolver::new(&host, resolve),
+            port,
+            mqtt_ping: MqttPing::new(&host, &settings).unwrap(),
+            timeout,
+        }
+    }
+
+    /// 探测一个地址
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        let socket_addr = SocketAddr::new(ip_addr, self.port);
+        trace!("开始执行 MQTT 任务: round trip {}", socket_addr);
+        let reply = self.mqtt_ping.ping(socket_addr, self.timeout).await?;
+        trace!("round trip {} {:?}", socket_addr, reply);
+        Ok(reply.into_report())
+    }
+}
+
+#[async_trait]
+impl Executor for MqttExecutor {
+    fn get_name(&self) -> String {
+        String::from("MQTT")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move { executor.exec_addr(ip_addr).await }
+            })
+            .await
+    }
+}
@@ -0,0 +1,137 @@
+use crate::ping_error::PingError;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// 连接请求
+pub const MQTT_CONNECT: u8 = 1;
+/// 连接确认
+pub const MQTT_CONNACK: u8 = 2;
+/// 发布消息
+pub const MQTT_PUBLISH: u8 = 3;
+/// 发布确认(QoS 1)
+pub const MQTT_PUBACK: u8 = 4;
+/// 订阅请求
+pub const MQTT_SUBSCRIBE: u8 = 8;
+/// 订阅确认
+pub const MQTT_SUBACK: u8 = 9;
+/// 断开连接
+pub const MQTT_DISCONNECT: u8 = 14;
+
+/// 接收的控制报文的最大长度
+const MAX_PACKET_SIZE: usize = 256 * 1024;
+
+/// 控制报文
+#[derive(Debug)]
+pub struct MqttPacket {
+    /// 报文类型
+    pub kind: u8,
+    /// 固定报头中的标志位
+    pub flags: u8,
+    /// 可变报头及负载
+    pub body: Vec<u8>,
+}
+
+impl MqttPacket {
+    /// 构造函数
+    /// # 参数
+    /// * `kind` - 报文类型
+    /// * `flags` - 固定报头中的标志位
+    pub fn new(kind: u8, flags: u8) -> Self {
+        Self {
+            kind,
+            flags,
+            body: vec![],
+        }
+    }
+
+    /// 追加一个字节
+    pub fn byte(mut self, value: u8) -> Self {
+        self.body.push(value);
+        self
+    }
+
+    /// 追加16位无符号整数
+    pub fn u16(mut self, value: u16) -> Self {
+        self.body.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    /// 追加带长度的字符串或二进制数据
+    pub fn string(self, value: &[u8]) -> Self {
+        self.u16(value.len() as u16).bytes(value)
+    }
+
+    /// 原样追加字节
+    pub fn bytes(mut self, value: &[u8]) -> Self {
+        self.body.extend_from_slice(value);
+        self
+    }
+
+    /// 发送报文
+    pub async fn write<S>(&self, stream: &mut S) -> Result<(), PingError>
+    where
+        S: AsyncWrite + Unpin,
+    {
+        let mut packet = vec![self.kind << 4 | self.flags];
+        // 剩余长度为变长编码，每字节7位，最高位表示后面还有
+        let mut len = self.body.len();
+        loop {
+            let mut byte = (len % 128) as u8;
+            len /= 128;
+            if len > 0 {
+                byte |= 0x80;
+            }
+            packet.push(byte);
+            if len == 0 {
+                break;
+            }
+        }
+        packet.extend_from_slice(&self.body);
+        stream.write_all(&packet).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+
+    /// 读取一个报文
+    pub async fn read<S>(stream: &mut S) -> Result<Self, PingError>
+    where
+        S: AsyncRead + Unpin,
+    {
+        let header = stream.read_u8().await?;
+        let mut len = 0;
+        for shift in (0..4).map(|i| i * 7) {
+            let byte = stream.read_u8().await?;
+            len |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                if len > MAX_PACKET_SIZE {
+                    return Err(PingError::InvalidReply(format!(
+                        "报文长度 {} 超过 {} 字节",
+                        len, MAX_PACKET_SIZE
+                    )));
+                }
+                let mut body = vec![0; len];
+                stream.read_exact(&mut body).await?;
+                return Ok(Self {
+                    kind: header >> 4,
+                    flags: header & 0x0f,
+                    body,
+                });
+            }
+        }
+        Err(PingError::InvalidReply(String::from("错误的剩余长度")))
+    }
+}
+
+/// 读取带长度的字符串或二进制数据
+///
+/// # 返回值
+/// 数据及之后剩余的部分
+pub fn split_string(data: &[u8]) -> Result<(&[u8], &[u8]), PingError> {
+    let len = data
+        .get(..2)
+        .map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+        .ok_or_else(|| PingError::InvalidReply(String::from("报文长度不足")))?;
+    if data.len() < 2 + len {
+        return Err(PingError::InvalidReply(String::from("报文长度不足")));
+    }
+    Ok(data[2..].split_at(len))
+}
@@ -0,0 +1,335 @@
+use crate::executor::ExecReport;
+use crate::metrics::metrics_cst::{
+    MQTT_DURATION_PROMETHEUS_METRIC_DESC, MQTT_DURATION_PROMETHEUS_METRIC_NAME,
+    PHASE_PROMETHEUS_METRIC_LABEL_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::task::database::db_probe::DbTls;
+use crate::task::mqtt::mqtt_packet::{
+    split_string, MqttPacket, MQTT_CONNACK, MQTT_CONNECT, MQTT_DISCONNECT, MQTT_PUBACK,
+    MQTT_PUBLISH, MQTT_SUBACK, MQTT_SUBSCRIBE,
+};
+use crate::task::mqtt::mqtt_settings::MqttSettings;
+use crate::task::tcp::tcp_ping::TcpPing;
+use crate::task::tcp::tcp_settings::TcpSettings;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+/// MQTT 3.1.1 的协议级别
+const PROTOCOL_LEVEL: u8 = 4;
+/// 连接标志: 清除会话，断开后服务器不保留订阅
+const CONNECT_CLEAN_SESSION: u8 = 0x02;
+/// 连接标志: 带密码
+const CONNECT_PASSWORD: u8 = 0x40;
+/// 连接标志: 带用户名
+const CONNECT_USERNAME: u8 = 0x80;
+/// 保持连接的秒数，探测很快结束，只需大于超时时间
+const KEEP_ALIVE: u16 = 60;
+/// 订阅请求的报文ID
+const SUBSCRIBE_PACKET_ID: u16 = 1;
+/// 发布消息的报文ID
+const PUBLISH_PACKET_ID: u16 = 2;
+/// 订阅确认中表示失败的返回码
+const SUBACK_FAILURE: u8 = 0x80;
+
+/// MQTT探测各阶段的耗时
+#[derive(Debug, Clone, Default)]
+pub struct MqttTimings {
+    /// 建立TCP连接
+    pub connect: Duration,
+    /// 从连接建立到服务器接受连接，包括TLS握手，未完成时为None
+    pub handshake: Option<Duration>,
+    /// 订阅主题，未完成时为None
+    pub subscribe: Option<Duration>,
+    /// 从发布消息到收到服务器转发回来的消息，未完成时为None
+    pub round_trip: Option<Duration>,
+}
+
+/// 一次MQTT探测的结果
+#[derive(Debug)]
+pub struct MqttReply {
+    /// 各阶段的耗时
+    pub timings: MqttTimings,
+    /// 连接、订阅或收发消息失败的原因
+    pub error: Option<PingError>,
+}
+
+impl MqttReply {
+    /// 转换成执行报告，以各阶段的耗时之和作为耗时
+    pub fn into_report(self) -> ExecReport {
+        let timings = &self.timings;
+        let phases = [
+            ("connect", Some(timings.connect)),
+            ("handshake", timings.handshake),
+            ("subscribe", timings.subscribe),
+            ("round_trip", timings.round_trip),
+        ];
+        ExecReport {
+            elapsed: Some(phases.iter().filter_map(|(_, duration)| *duration).sum()),
+            error: self.error,
+            metrics: phases
+                .into_iter()
+                .filter_map(|(phase, duration)| {
+                    duration.map(|duration| {
+                        ProbeMetric::new(
+                            MQTT_DURATION_PROMETHEUS_METRIC_NAME,
+                            MQTT_DURATION_PROMETHEUS_METRIC_DESC,
+                            duration.as_secs_f64() * 1000.0,
+                        )
+                        .with_label(PHASE_PROMETHEUS_METRIC_LABEL_NAME, phase)
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MqttPing {
+    username: Option<String>,
+    password: Option<String>,
+    /// 配置的客户端ID，为None时每次探测随机生成
+    client_id: Option<String>,
+    topic_prefix: String,
+    qos: u8,
+    /// TLS配置，明文连接时为None
+    tls: Option<DbTls>,
+}
+
+impl MqttPing {
+    /// 构造函数
+    /// # 参数
+    /// * `host` - 目标的主机名，用于校验证书
+    /// * `settings` - MQTT任务配置
+    ///
+    /// # 返回值
+    /// 配置错误或凭据的环境变量不存在时返回错误信息
+    pub fn new(host: &str, settings: &MqttSettings) -> Result<Self, String> {
+        let credentials = settings.credentials.credentials()?;
+        if credentials.username.is_none() && credentials.password.is_some() {
+            return Err(String::from("配置了密码时必须配置用户名"));
+        }
+        if settings.qos > 1 {
+            return Err(format!("qos({})只支持0和1", settings.qos));
+        }
+        if settings.topic_prefix.is_empty()
+            || settings.topic_prefix.contains(['+', '#'])
+            || settings.topic_prefix.ends_with('/')
+        {
+            return Err(format!(
+                "topic-prefix({})不能为空、不能包含通配符+和#，也不能以/结尾",
+                settings.topic_prefix
+            ));
+        }
+        Ok(Self {
+            username: credentials.username,
+            password: credentials.password,
+            client_id: settings.client_id.clone(),
+            topic_prefix: settings.topic_prefix.clone(),
+            qos: settings.qos,
+            tls: settings
+                .tls
+                .then(|| DbTls::new(host, settings.insecure))
+                .transpose()?,
+        })
+    }
+
+    /// 连接服务器，订阅后发布一条消息并等待消息转发回来
+    ///
+    /// # 返回值
+    /// 连接失败时返回错误，之后的失败记录在结果中
+    pub async fn ping(
+        &self,
+        socket_addr: SocketAddr,
+        timeout: Duration,
+    ) -> Result<MqttReply, PingError> {
+        let start = Instant::now();
+        let (stream, connect) = TcpPing::new(socket_addr, &TcpSettings::default())
+            .connect(timeout)
+            .await?;
+        let mut timings = MqttTimings {
+            connect,
+            ..Default::default()
+        };
+        let result = tokio::time::timeout(
+            timeout.saturating_sub(start.elapsed()),
+            self.session(stream, &mut timings),
+        )
+        .await
+        .unwrap_or(Err(PingError::Timeout));
+        Ok(MqttReply {
+            timings,
+            error: result.err(),
+        })
+    }
+
+    /// 需要时先完成TLS握手
+    async fn session(&self, stream: TcpStream, timings: &mut MqttTimings) -> Result<(), PingError> {
+        let mark = Instant::now();
+        match &self.tls {
+            Some(tls) => {
+                self.converse(tls.connect(stream).await?, mark, timings)
+                    .await
+            }
+            None => self.converse(stream, mark, timings).await,
+        }
+    }
+
+    /// 连接、订阅并收发消息
+    ///
+    /// # 参数
+    /// * `mark` - TCP连接建立的时间
+    async fn converse<S>(
+        &self,
+        stream: S,
+        mark: Instant,
+        timings: &mut MqttTimings,
+    ) -> Result<(), PingError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut stream = BufReader::new(stream);
+        let nonce = nonce()?;
+        let client_id = self
+            .client_id
+            .clone()
+            .unwrap_or_else(|| format!("pong-rs-{}", nonce));
+        let topic = format!("{}/{}", self.topic_prefix, client_id);
+
+        self.connect_packet(&client_id)
+            .write(stream.get_mut())
+            .await?;
+        let connack = MqttPacket::read(&mut stream).await?;
+        if connack.kind != MQTT_CONNACK || connack.body.len() < 2 {
+            return Err(invalid(&format!(
+                "期望CONNACK，收到报文类型 {}",
+                connack.kind
+            )));
+        }
+        if connack.body[1] != 0 {
+            return Err(invalid(&format!(
+                "服务器拒绝连接: {}",
+                connect_return_code(connack.body[1])
+            )));
+        }
+        timings.handshake = Some(mark.elapsed());
+
+        let mark = Instant::now();
+        MqttPacket::new(MQTT_SUBSCRIBE, 0x02)
+            .u16(SUBSCRIBE_PACKET_ID)
+            .string(topic.as_bytes())
+            .byte(self.qos)
+            .write(stream.get_mut())
+            .await?;
+        loop {
+            let packet = MqttPacket::read(&mut stream).await?;
+            if packet.kind != MQTT_SUBACK {
+                continue;
+            }
+            match packet.body.get(2) {
+                Some(&SUBACK_FAILURE) => {
+                    return Err(invalid(&format!("服务器拒绝订阅主题 {}", topic)));
+                }
+                Some(_) => break,
+                None => return Err(invalid("错误的SUBACK")),
+            }
+        }
+        timings.subscribe = Some(mark.elapsed());
+
+        // 消息带上随机数和发送时间，只有原样转发回来的才算
+        let payload = format!(
+            "pong-rs {} {}",
+            nonce,
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_millis())
+        );
+        let mark = Instant::now();
+        let mut publish = MqttPacket::new(MQTT_PUBLISH, self.qos << 1).string(topic.as_bytes());
+        if self.qos > 0 {
+            publish = publish.u16(PUBLISH_PACKET_ID);
+        }
+        publish
+            .bytes(payload.as_bytes())
+            .write(stream.get_mut())
+            .await?;
+        loop {
+            let packet = MqttPacket::read(&mut stream).await?;
+            if packet.kind != MQTT_PUBLISH {
+                continue;
+            }
+            let (received_topic, rest) = split_string(&packet.body)?;
+            let qos = (packet.flags >> 1) & 0x03;
+            let received_payload = if qos > 0 {
+                let packet_id = rest.get(..2).ok_or_else(|| invalid("报文长度不足"))?;
+                MqttPacket::new(MQTT_PUBACK, 0)
+                    .bytes(packet_id)
+                    .write(stream.get_mut())
+                    .await?;
+                &rest[2..]
+            } else {
+                rest
+            };
+            if received_topic == topic.as_bytes() && received_payload == payload.as_bytes() {
+                break;
+            }
+        }
+        timings.round_trip = Some(mark.elapsed());
+
+        let _ = MqttPacket::new(MQTT_DISCONNECT, 0)
+            .write(stream.get_mut())
+            .await;
+        Ok(())
+    }
+
+    /// 连接请求，清除会话，断开后服务器不保留订阅
+    fn connect_packet(&self, client_id: &str) -> MqttPacket {
+        let mut flags = CONNECT_CLEAN_SESSION;
+        if self.username.is_some() {
+            flags |= CONNECT_USERNAME;
+        }
+        if self.password.is_some() {
+            flags |= CONNECT_PASSWORD;
+        }
+        let mut packet = MqttPacket::new(MQTT_CONNECT, 0)
+            .string(b"MQTT")
+            .byte(PROTOCOL_LEVEL)
+            .byte(flags)
+            .u16(KEEP_ALIVE)
+            .string(client_id.as_bytes());
+        for value in [&self.username, &self.password].into_iter().flatten() {
+            packet = packet.string(value.as_bytes());
+        }
+        packet
+    }
+}
+
+/// 随机数的十六进制表示，用于客户端ID及消息
+fn nonce() -> Result<String, PingError> {
+    let mut nonce = [0; 6];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| invalid("生成随机数失败"))?;
+    Ok(nonce.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// 连接确认中返回码的含义
+fn connect_return_code(code: u8) -> String {
+    match code {
+        1 => String::from("不支持的协议版本"),
+        2 => String::from("客户端ID不合格"),
+        3 => String::from("服务不可用"),
+        4 => String::from("用户名或密码错误"),
+        5 => String::from("未授权"),
+        code => format!("返回码 {}", code),
+    }
+}
+
+fn invalid(reason: &str) -> PingError {
+    PingError::InvalidReply(reason.to_string())
+}
@@ -0,0 +1,45 @@
+use crate::task::database::credential_settings::CredentialSettings;
+use serde::{Deserialize, Serialize};
+
+/// MQTT任务配置
+///
+/// 连接到MQTT服务器(MQTT 3.1.1)后订阅一个私有的主题，再向该主题发布一条带时间戳的消息，
+/// 测量消息经服务器转发回来的耗时，连接被拒绝、订阅失败或消息没有回来时探测失败。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct MqttSettings {
+    /// 认证的凭据，配置了密码时必须配置用户名
+    #[serde(flatten)]
+    pub credentials: CredentialSettings,
+    /// 客户端ID，默认每次探测随机生成，服务器按客户端ID控制权限时需要配置
+    pub client_id: Option<String>,
+    /// 探测主题的前缀，实际的主题为 `<前缀>/<客户端ID>`
+    #[serde(default = "topic_prefix_default")]
+    pub topic_prefix: String,
+    /// 订阅及发布的QoS，支持0和1
+    #[serde(default)]
+    pub qos: u8,
+    /// 是否使用TLS连接
+    #[serde(default)]
+    pub tls: bool,
+    /// 是否跳过证书校验，用于自签名证书的服务
+    #[serde(default)]
+    pub insecure: bool,
+}
+
+fn topic_prefix_default() -> String {
+    String::from("pong-rs/probe") // 默认的主题前缀为 pong-rs/probe
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            credentials: CredentialSettings::default(),
+            client_id: None,
+            topic_prefix: topic_prefix_default(),
+            qos: 0,
+            tls: false,
+            insecure: false,
+        }
+    }
+}
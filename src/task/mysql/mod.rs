@@ -0,0 +1,3 @@
+pub mod mysql_executor;
+pub mod mysql_ping;
+pub mod mysql_settings;
@@ -0,0 +1,596 @@
+use crate::ping_error::PingError;
+use crate::task::database::db_probe::{DbProtocol, DbTimer, DbTls};
+use crate::task::mysql::mysql_settings::MysqlSettings;
+use async_trait::async_trait;
+use ring::digest;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// 客户端能力：旧的密码认证
+const CLIENT_LONG_PASSWORD: u32 = 0x0000_0001;
+/// 客户端能力：连接时指定数据库
+const CLIENT_CONNECT_WITH_DB: u32 = 0x0000_0008;
+/// 客户端能力：4.1版本的协议
+const CLIENT_PROTOCOL_41: u32 = 0x0000_0200;
+/// 客户端能力：TLS连接
+const CLIENT_SSL: u32 = 0x0000_0800;
+/// 客户端能力：事务状态
+const CLIENT_TRANSACTIONS: u32 = 0x0000_2000;
+/// 客户端能力：4.1版本的认证
+const CLIENT_SECURE_CONNECTION: u32 = 0x0000_8000;
+/// 客户端能力：认证插件
+const CLIENT_PLUGIN_AUTH: u32 = 0x0008_0000;
+/// 客户端声明的最大包长度
+const MAX_PACKET_SIZE: u32 = 16 * 1024 * 1024;
+/// 读取的单个包的最大字节数
+const MAX_READ_SIZE: usize = 1024 * 1024;
+/// 字符集 utf8_general_ci
+const CHARSET_UTF8: u8 = 33;
+/// 默认的用户名
+const USERNAME_DEFAULT: &str = "root";
+/// caching_sha2_password 认证插件
+const CACHING_SHA2_PASSWORD: &str = "caching_sha2_password";
+/// mysql_native_password 认证插件
+const MYSQL_NATIVE_PASSWORD: &str = "mysql_native_password";
+
+/// 服务器的初始握手包
+#[derive(Debug)]
+struct Handshake {
+    /// 服务器的能力
+    capabilities: u32,
+    /// 认证使用的随机数
+    nonce: Vec<u8>,
+    /// 认证插件
+    plugin: String,
+}
+
+impl Handshake {
+    /// 解析第10版的初始握手包
+    fn parse(packet: &[u8]) -> Result<Self, PingError> {
+        let mut reader = PacketReader::new(packet);
+        let version = reader.u8()?;
+        if version != 10 {
+            return Err(invalid(&format!("不支持的协议版本: {}", version)));
+        }
+        reader.cstring()?; // 服务器版本
+        reader.bytes(4)?; // 连接ID
+        let mut nonce = reader.bytes(8)?.to_vec();
+        reader.bytes(1)?;
+        let mut capabilities = reader.u16()? as u32;
+        let mut plugin = String::from(MYSQL_NATIVE_PASSWORD);
+        if !reader.is_empty() {
+            reader.bytes(3)?; // 字符集及服务器状态
+            capabilities |= (reader.u16()? as u32) << 16;
+            let nonce_size = reader.u8()? as usize;
+            reader.bytes(10)?;
+            if capabilities & CLIENT_SECURE_CONNECTION != 0 {
+                // 第二部分随机数以0结尾
+                let size = nonce_size.saturating_sub(8).max(13);
+                let part = reader.bytes(size)?;
+                nonce.extend_from_slice(&part[..size - 1]);
+            }
+            if capabilities & CLIENT_PLUGIN_AUTH != 0 {
+                plugin = reader.cstring()?;
+            }
+        }
+        Ok(Self {
+            capabilities,
+            nonce,
+            plugin,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct MysqlPing {
+    username: String,
+    password: Option<String>,
+    database: Option<String>,
+    query: String,
+    /// TLS配置，明文连接时为None
+    tls: Option<DbTls>,
+}
+
+impl MysqlPing {
+    /// 构造函数
+    /// # 参数
+    /// * `host` - 目标的主机名，用于校验证书
+    /// * `settings` - MySQL任务配置
+    ///
+    /// # 返回值
+    /// 凭据的环境变量不存在时返回错误信息
+    pub fn new(host: &str, settings: &MysqlSettings) -> Result<Self, String> {
+        let credentials = settings.credentials.credentials()?;
+        Ok(Self {
+            username: credentials
+                .username
+                .unwrap_or_else(|| USERNAME_DEFAULT.to_string()),
+            password: credentials.password,
+            database: settings.database.clone(),
+            query: settings.query.clone(),
+            tls: settings
+                .tls
+                .then(|| DbTls::new(host, settings.insecure))
+                .transpose()?,
+        })
+    }
+
+    /// 客户端的能力
+    fn capabilities(&self) -> u32 {
+        let mut capabilities = CLIENT_LONG_PASSWORD
+            | CLIENT_PROTOCOL_41
+            | CLIENT_TRANSACTIONS
+            | CLIENT_SECURE_CONNECTION
+            | CLIENT_PLUGIN_AUTH;
+        if self.database.is_some() {
+            capabilities |= CLIENT_CONNECT_WITH_DB;
+        }
+        if self.tls.is_some() {
+            capabilities |= CLIENT_SSL;
+        }
+        capabilities
+    }
+
+    /// 发送握手回复包，登录并执行查询
+    ///
+    /// # 参数
+    /// * `seq` - 握手回复包的序号
+    /// * `secure` - 连接是否已经加密，决定能否发送明文密码
+    async fn converse<S>(
+        &self,
+        mut stream: BufReader<S>,
+        seq: u8,
+        handshake: &Handshake,
+        secure: bool,
+        timer: &mut DbTimer,
+    ) -> Result<Option<String>, PingError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let auth = self.scramble(&handshake.plugin, &handshake.nonce)?;
+        let mut response = self.login_header();
+        put_cstring(&mut response, &self.username);
+        response.push(auth.len() as u8);
+        response.extend_from_slice(&auth);
+        if let Some(database) = &self.database {
+            put_cstring(&mut response, database);
+        }
+        put_cstring(&mut response, &handshake.plugin);
+        write_packet(&mut stream, seq, &response).await?;
+        self.authenticate(&mut stream, handshake, secure).await?;
+        timer.auth_done();
+
+        let mut query = vec![0x03]; // COM_QUERY
+        query.extend_from_slice(self.query.as_bytes());
+        write_packet(&mut stream, 0, &query).await?;
+        let value = read_result_set(&mut stream).await?;
+        timer.query_done();
+
+        let _ = write_packet(&mut stream, 0, &[0x01]).await; // COM_QUIT
+        Ok(value)
+    }
+
+    /// 处理服务器的认证回复，包括切换认证插件及 caching_sha2_password 的完整认证
+    async fn authenticate<S>(
+        &self,
+        stream: &mut BufReader<S>,
+        handshake: &Handshake,
+        secure: bool,
+    ) -> Result<(), PingError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut plugin = handshake.plugin.clone();
+        loop {
+            let (seq, packet) = read_packet(stream).await?;
+            let response = match packet.first() {
+                Some(0x00) => return Ok(()),
+                Some(0xff) => return Err(error_packet(&packet)),
+                // 切换认证插件
+                Some(0xfe) => {
+                    let mut reader = PacketReader::new(&packet[1..]);
+                    plugin = reader.cstring()?;
+                    let nonce = reader.rest();
+                    self.scramble(&plugin, nonce.strip_suffix(&[0]).unwrap_or(nonce))?
+                }
+                Some(0x01) if plugin == CACHING_SHA2_PASSWORD => match packet.get(1) {
+                    // 快速认证成功，随后是OK包
+                    Some(0x03) => continue,
+                    // 服务器没有缓存，需要完整认证
+                    Some(0x04) if secure => {
+                        let mut response = vec![];
+                        put_cstring(&mut response, self.password.as_deref().unwrap_or(""));
+                        response
+                    }
+                    Some(0x04) => {
+                        return Err(invalid(
+                            "caching_sha2_password需要完整认证，须启用TLS或先用其他客户端登录一次",
+                        ));
+                    }
+                    _ => return Err(invalid("错误的认证回复")),
+                },
+                _ => return Err(invalid("错误的认证回复")),
+            };
+            write_packet(stream, seq.wrapping_add(1), &response).await?;
+        }
+    }
+
+    /// 握手回复包和TLS请求包共同的开头部分
+    fn login_header(&self) -> Vec<u8> {
+        let mut header = self.capabilities().to_le_bytes().to_vec();
+        header.extend_from_slice(&MAX_PACKET_SIZE.to_le_bytes());
+        header.push(CHARSET_UTF8);
+        header.extend_from_slice(&[0; 23]);
+        header
+    }
+
+    /// 按认证插件的算法用随机数加密密码，没有密码时为空
+    fn scramble(&self, plugin: &str, nonce: &[u8]) -> Result<Vec<u8>, PingError> {
+        let password = match self.password.as_deref() {
+            None | Some("") => return Ok(vec![]),
+            Some(password) => password.as_bytes(),
+        };
+        let algorithm = match plugin {
+            // SHA1(password) XOR SHA1(nonce + SHA1(SHA1(password)))
+            MYSQL_NATIVE_PASSWORD => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+            // SHA256(password) XOR SHA256(SHA256(SHA256(password)) + nonce)
+            CACHING_SHA2_PASSWORD => &digest::SHA256,
+            plugin => return Err(invalid(&format!("不支持的认证插件: {}", plugin))),
+        };
+        let hashed = digest::digest(algorithm, password);
+        let double_hashed = digest::digest(algorithm, hashed.as_ref());
+        let mut context = digest::Context::new(algorithm);
+        if plugin == MYSQL_NATIVE_PASSWORD {
+            context.update(nonce);
+            context.update(double_hashed.as_ref());
+        } else {
+            context.update(double_hashed.as_ref());
+            context.update(nonce);
+        }
+        Ok(hashed
+            .as_ref()
+            .iter()
+            .zip(context.finish().as_ref())
+            .map(|(a, b)| a ^ b)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl DbProtocol for MysqlPing {
+    async fn session(
+        &self,
+        stream: TcpStream,
+        timer: &mut DbTimer,
+    ) -> Result<Option<String>, PingError> {
+        let mut stream = BufReader::new(stream);
+        let (seq, packet) = read_packet(&mut stream).await?;
+        if packet.first() == Some(&0xff) {
+            return Err(error_packet(&packet));
+        }
+        let handshake = Handshake::parse(&packet)?;
+        let seq = seq.wrapping_add(1);
+        let Some(tls) = &self.tls else {
+            return self.converse(stream, seq, &handshake, false, timer).await;
+        };
+        if handshake.capabilities & CLIENT_SSL == 0 {
+            return Err(PingError::Tls(String::from("服务器不支持TLS连接")));
+        }
+        // 先发送TLS请求包，升级后再发送握手回复包
+        write_packet(&mut stream, seq, &self.login_header()).await?;
+        let stream = tls.connect(stream.into_inner()).await?;
+        self.converse(
+            BufReader::new(stream),
+            seq.wrapping_add(1),
+            &handshake,
+            true,
+            timer,
+        )
+        .await
+    }
+}
+
+/// 读取查询的结果集
+///
+/// # 返回值
+/// 第一行的第一列，没有结果或为NULL时返回None
+async fn read_result_set<S>(stream: &mut BufReader<S>) -> Result<Option<String>, PingError>
+where
+    S: AsyncRead + Unpin,
+{
+    let (_, packet) = read_packet(stream).await?;
+    let columns = match packet.first() {
+        Some(0x00) => return Ok(None),
+        Some(0xff) => return Err(error_packet(&packet)),
+        _ => PacketReader::new(&packet).lenenc_int()?,
+    };
+    // 跳过列的定义，直到EOF包
+    for _ in 0..columns {
+        read_packet(stream).await?;
+    }
+    let (_, packet) = read_packet(stream).await?;
+    if !is_eof(&packet) {
+        return Err(invalid("列定义后缺少EOF包"));
+    }
+
+    let mut value = None;
+    let mut first_row = true;
+    loop {
+        let (_, packet) = read_packet(stream).await?;
+        if is_eof(&packet) {
+            return Ok(value);
+        }
+        if packet.first() == Some(&0xff) {
+            return Err(error_packet(&packet));
+        }
+        if first_row {
+            first_row = false;
+            // 0xfb表示NULL
+            if packet.first() != Some(&0xfb) {
+                let mut reader = PacketReader::new(&packet);
+                let size = reader.lenenc_int()? as usize;
+                value = Some(String::from_utf8_lossy(reader.bytes(size)?).into_owned());
+            }
+        }
+    }
+}
+
+/// 发送一个包
+async fn write_packet<S>(stream: &mut S, seq: u8, payload: &[u8]) -> Result<(), PingError>
+where
+    S: AsyncWrite + Unpin,
+{
+    let mut buf = (payload.len() as u32).to_le_bytes().to_vec();
+    buf[3] = seq;
+    buf.extend_from_slice(payload);
+    stream.write_all(&buf).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// 读取一个包
+///
+/// # 返回值
+/// 包的序号及内容
+async fn read_packet<S>(stream: &mut BufReader<S>) -> Result<(u8, Vec<u8>), PingError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut header = [0; 4];
+    stream.read_exact(&mut header).await?;
+    let size = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+    if size > MAX_READ_SIZE {
+        return Err(invalid(&format!(
+            "包长度 {} 超过 {} 字节",
+            size, MAX_READ_SIZE
+        )));
+    }
+    let mut payload = vec![0; size];
+    stream.read_exact(&mut payload).await?;
+    Ok((header[3], payload))
+}
+
+/// 是否为EOF包
+fn is_eof(packet: &[u8]) -> bool {
+    packet.first() == Some(&0xfe) && packet.len() < 9
+}
+
+/// 解析错误包，取出错误码、SQL状态和错误信息
+fn error_packet(packet: &[u8]) -> PingError {
+    let code = packet
+        .get(1..3)
+        .map_or(0, |code| u16::from_le_bytes([code[0], code[1]]));
+    let rest = packet.get(3..).unwrap_or_default();
+    let message = match rest.strip_prefix(b"#") {
+        Some(rest) if rest.len() >= 5 => format!(
+            "ERROR {} ({}): {}",
+            code,
+            String::from_utf8_lossy(&rest[..5]),
+            String::from_utf8_lossy(&rest[5..])
+        ),
+        _ => format!("ERROR {}: {}", code, String::from_utf8_lossy(rest)),
+    };
+    invalid(&message)
+}
+
+/// 追加以0结尾的字符串
+fn put_cstring(buf: &mut Vec<u8>, value: &str) {
+    buf.extend_from_slice(value.as_bytes());
+    buf.push(0);
+}
+
+fn invalid(reason: &str) -> PingError {
+    PingError::InvalidReply(reason.to_string())
+}
+
+/// 按顺序读取包中的字段
+struct PacketReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PacketReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    fn bytes(&mut self, size: usize) -> Result<&'a [u8], PingError> {
+        // 长度来自服务器，相加可能溢出
+        let bytes = self
+            .pos
+            .checked_add(size)
+            .and_then(|end| self.buf.get(self.pos..end))
+            .ok_or_else(|| invalid("包长度不足"))?;
+        self.pos += size;
+        Ok(bytes)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = self.buf.get(self.pos..).unwrap_or_default();
+        self.pos = self.buf.len();
+        rest
+    }
+
+    fn u8(&mut self) -> Result<u8, PingError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, PingError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// 以0结尾的字符串
+    fn cstring(&mut self) -> Result<String, PingError> {
+        let rest = self.buf.get(self.pos..).unwrap_or_default();
+        let size = rest
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| invalid("字符串缺少结尾"))?;
+        self.pos += size + 1;
+        Ok(String::from_utf8_lossy(&rest[..size]).into_owned())
+    }
+
+    /// 长度编码的整数
+    fn lenenc_int(&mut self) -> Result<u64, PingError> {
+        let size = match self.u8()? {
+            first @ 0..=0xfa => return Ok(first as u64),
+            0xfc => 2,
+            0xfd => 3,
+            0xfe => 8,
+            _ => return Err(invalid("错误的长度编码")),
+        };
+        Ok(self
+            .bytes(size)?
+            .iter()
+            .rev()
+            .fold(0, |value, b| (value << 8) | *b as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 认证使用的20字节随机数
+    const NONCE: [u8; 20] = [
+        0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+        0x10, 0x11, 0x12, 0x13, 0x14,
+    ];
+
+    fn mysql_ping(password: Option<&str>) -> MysqlPing {
+        let mut settings = MysqlSettings::default();
+        settings.credentials.password = password.map(str::to_string);
+        MysqlPing::new("localhost", &settings).unwrap()
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn mysql_native_password_scramble() {
+        let scramble = mysql_ping(Some("secret"))
+            .scramble(MYSQL_NATIVE_PASSWORD, &NONCE)
+            .unwrap();
+        assert_eq!(hex(&scramble), "b32bb3a583e1340c0a1108d58b1be49781ad8c2f");
+
+        // 服务器保存的是 SHA1(SHA1(password))，即 PASSWORD('secret')，按服务器的方式校验
+        let stored = "14e65567abdb5135d0cfd9a70b3032c179a49ee7";
+        let mut context = digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+        context.update(&NONCE);
+        context.update(&unhex(stored));
+        let hashed: Vec<u8> = scramble
+            .iter()
+            .zip(context.finish().as_ref())
+            .map(|(a, b)| a ^ b)
+            .collect();
+        assert_eq!(
+            hex(digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, &hashed).as_ref()),
+            stored
+        );
+    }
+
+    #[test]
+    fn caching_sha2_password_scramble() {
+        let scramble = mysql_ping(Some("secret"))
+            .scramble(CACHING_SHA2_PASSWORD, &NONCE)
+            .unwrap();
+        assert_eq!(
+            hex(&scramble),
+            "746ebe205d56a0707acb3e796e834e0dd7b1d61743b26bd5202c7a623230c7c9"
+        );
+    }
+
+    #[test]
+    fn empty_password_scramble() {
+        for password in [None, Some("")] {
+            for plugin in [MYSQL_NATIVE_PASSWORD, CACHING_SHA2_PASSWORD] {
+                assert!(mysql_ping(password)
+                    .scramble(plugin, &NONCE)
+                    .unwrap()
+                    .is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_plugin() {
+        assert!(mysql_ping(Some("secret"))
+            .scramble("sha256_password", &NONCE)
+            .is_err());
+    }
+
+    #[test]
+    fn parse_handshake() {
+        let mut packet = vec![10];
+        packet.extend_from_slice(b"8.0.36\0");
+        packet.extend_from_slice(&[0x0b, 0x00, 0x00, 0x00]);
+        packet.extend_from_slice(&NONCE[..8]);
+        packet.push(0);
+        packet.extend_from_slice(&[0xff, 0xff, 0xff, 0x02, 0x00, 0xff, 0xdf, 21]);
+        packet.extend_from_slice(&[0; 10]);
+        packet.extend_from_slice(&NONCE[8..]);
+        packet.push(0);
+        packet.extend_from_slice(b"caching_sha2_password\0");
+
+        let handshake = Handshake::parse(&packet).unwrap();
+        assert_eq!(handshake.nonce, NONCE);
+        assert_eq!(handshake.plugin, CACHING_SHA2_PASSWORD);
+        assert_eq!(handshake.capabilities, 0xdfff_ffff);
+        for len in [0, 10, 20, packet.len() - 30] {
+            assert!(Handshake::parse(&packet[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn reader_rejects_overflowing_length() {
+        let mut reader = PacketReader::new(&[0xfe, 1, 2, 3]);
+        reader.u8().unwrap();
+        assert!(reader.bytes(usize::MAX).is_err());
+        assert!(reader.bytes(4).is_err());
+        assert_eq!(reader.bytes(3).unwrap(), [1, 2, 3]);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn reader_lenenc_int() {
+        let mut reader = PacketReader::new(&[0xfa, 0xfc, 0x34, 0x12, 0xfd, 0x01, 0x02, 0x03, 0xfe]);
+        assert_eq!(reader.lenenc_int().unwrap(), 0xfa);
+        assert_eq!(reader.lenenc_int().unwrap(), 0x1234);
+        assert_eq!(reader.lenenc_int().unwrap(), 0x030201);
+        assert!(reader.lenenc_int().is_err());
+        assert!(PacketReader::new(&[0xff]).lenenc_int().is_err());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod ntp_executor;
+pub mod ntp_packet;
+pub mod ntp_ping;
+pub mod ntp_settings;
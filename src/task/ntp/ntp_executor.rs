@@ -0,0 +1,107 @@
+use crate::executor::{ExecReport, Executor};
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::{split_host_port, TargetResolver};
+use crate::task::ntp::ntp_ping::{NtpPing, NtpSample};
+use crate::task::ntp::ntp_settings::NtpSettings;
+use async_trait::async_trait;
+use log::trace;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// NTP的默认端口号
+const NTP_PORT: u16 = 123;
+
+#[derive(Clone)]
+pub struct NtpExecutor {
+    resolver: TargetResolver,
+    port: u16,
+    ntp_ping: NtpPing,
+    timeout: Duration,
+    settings: NtpSettings,
+}
+
+impl NtpExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `host_port` - NTP服务器的主机名，端口号可以省略，默认为123
+    /// * `timeout` - 一个 `Duration`，表示等待应答的超时时间
+    /// * `settings` - NTP任务配置
+    /// * `resolve` - 解析主机名的配置
+    pub fn new(
+        host_port: String,
+        timeout: Duration,
+        settings: NtpSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        let (host, port) = if IpAddr::from_str(&host_port).is_ok() {
+            (host_port, NTP_PORT)
+        } else {
+            split_host_port(&host_port).unwrap_or((host_port, NTP_PORT))
+        };
+        Self {
+            resolver: TargetResolver::new(&host, resolve),
+            port,
+            ntp_ping: NtpPing::new(settings.version),
+            timeout,
+            settings,
+        }
+    }
+
+    /// 检查服务器的状态及时钟偏移
+    ///
+    /// # 返回值
+    /// 第一个不满足的条件的描述
+    fn check(&self, sample: &NtpSample) -> Result<(), String> {
+        if sample.stratum == 0 {
+            return Err(format!("服务器回复 Kiss-o'-Death: {}", sample.reference_id));
+        }
+        if sample.leap == 3 {
+            return Err(String::from("服务器时钟未同步"));
+        }
+        if let Some(max_stratum) = self.settings.max_stratum
+            && sample.stratum > max_stratum
+        {
+            return Err(format!("层级 {} 大于 {}", sample.stratum, max_stratum));
+        }
+        if let Some(max_offset) = self.settings.max_offset
+            && sample.offset.abs() > max_offset.as_secs_f64()
+        {
+            return Err(format!(
+                "时钟偏移 {:.3}ms 超过 {:?}",
+                sample.offset * 1000.0,
+                max_offset
+            ));
+        }
+        Ok(())
+    }
+
+    /// 探测一个地址，以往返延迟作为耗时
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        let socket_addr = SocketAddr::new(ip_addr, self.port);
+        trace!("开始执行 NTP 任务: query {}", socket_addr);
+        let sample = self.ntp_ping.ping(socket_addr, self.timeout).await?;
+        Ok(ExecReport {
+            elapsed: Some(Duration::from_secs_f64(sample.delay.max(0.0))),
+            error: self.check(&sample).err().map(PingError::InvalidReply),
+            metrics: sample.to_metrics(),
+        })
+    }
+}
+
+#[async_trait]
+impl Executor for NtpExecutor {
+    fn get_name(&self) -> String {
+        String::from("NTP")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move { executor.exec_addr(ip_addr).await }
+            })
+            .await
+    }
+}
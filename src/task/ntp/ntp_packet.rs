@@ -0,0 +1,182 @@
+use std::net::Ipv4Addr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// NTP报文长度(不含扩展字段和认证信息)
+pub const NTP_PACKET_LENGTH: usize = 48;
+/// 客户端模式
+const NTP_MODE_CLIENT: u8 = 3;
+/// 服务器模式
+const NTP_MODE_SERVER: u8 = 4;
+/// NTP纪元(1900-01-01)到UNIX纪元(1970-01-01)的秒数
+const NTP_UNIX_EPOCH_DELTA: u64 = 2_208_988_800;
+
+/// NTP时间戳，高32位为秒，低32位为秒的小数部分(RFC 5905)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NtpTimestamp(pub u64);
+
+impl NtpTimestamp {
+    /// 当前时间
+    pub fn now() -> Self {
+        let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let seconds = since_epoch.as_secs() + NTP_UNIX_EPOCH_DELTA;
+        let fraction = ((since_epoch.subsec_nanos() as u64) << 32) / 1_000_000_000;
+        NtpTimestamp((seconds << 32) | fraction)
+    }
+
+    /// 与另一个时间戳相差的秒数，时间戳跨越NTP纪元时仍然正确
+    pub fn seconds_since(self, earlier: NtpTimestamp) -> f64 {
+        self.0.wrapping_sub(earlier.0) as i64 as f64 / (1u64 << 32) as f64
+    }
+}
+
+/// 构造客户端请求
+///
+/// # 参数
+/// * `version` - NTP版本
+/// * `transmit` - 发送时间，服务器会将其原样放在应答的起源时间中
+pub fn build_request(version: u8, transmit: NtpTimestamp) -> [u8; NTP_PACKET_LENGTH] {
+    let mut packet = [0; NTP_PACKET_LENGTH];
+    packet[0] = (version & 0x07) << 3 | NTP_MODE_CLIENT;
+    packet[40..48].copy_from_slice(&transmit.0.to_be_bytes());
+    packet
+}
+
+/// 服务器的应答
+#[derive(Debug, Clone)]
+pub struct NtpResponse {
+    /// 闰秒指示，3表示服务器时钟未同步
+    pub leap: u8,
+    /// 层级，0表示 Kiss-o'-Death
+    pub stratum: u8,
+    /// 参考ID
+    pub reference_id: [u8; 4],
+    /// 起源时间，即请求的发送时间
+    pub origin: NtpTimestamp,
+    /// 服务器收到请求的时间
+    pub receive: NtpTimestamp,
+    /// 服务器发送应答的时间
+    pub transmit: NtpTimestamp,
+}
+
+impl NtpResponse {
+    /// 解析应答
+    ///
+    /// # 返回值
+    /// 长度不足或不是服务器模式的应答时返回None
+    pub fn parse(packet: &[u8]) -> Option<Self> {
+        if packet.len() < NTP_PACKET_LENGTH || packet[0] & 0x07 != NTP_MODE_SERVER {
+            return None;
+        }
+        let timestamp = |offset: usize| {
+            NtpTimestamp(u64::from_be_bytes(
+                packet[offset..offset + 8].try_into().unwrap(),
+            ))
+        };
+        Some(Self {
+            leap: packet[0] >> 6,
+            stratum: packet[1],
+            reference_id: packet[12..16].try_into().unwrap(),
+            origin: timestamp(24),
+            receive: timestamp(32),
+            transmit: timestamp(40),
+        })
+    }
+
+    /// 参考ID的文本表示
+    ///
+    /// 层级为0或1时是ASCII码(Kiss-o'-Death代码或参考时钟，如 `GPS`)，
+    /// 更高的层级是上游服务器的IPv4地址，上游是IPv6时为其地址的哈希
+    pub fn reference_id(&self) -> String {
+        if self.stratum <= 1 {
+            self.reference_id
+                .iter()
+                .take_while(|b| **b != 0)
+                .map(|b| *b as char)
+                .collect()
+        } else {
+            Ipv4Addr::from(self.reference_id).to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// NTPv4服务器的应答，层级2，参考ID为上游的IPv4地址
+    const RESPONSE: [u8; NTP_PACKET_LENGTH] = [
+        0x24, 0x02, 0x00, 0xe7, 0x00, 0x00, 0x00, 0x1a, 0x00, 0x00, 0x00, 0x2d, 0xc0, 0x00, 0x02,
+        0x01, 0xea, 0x9c, 0x3a, 0x10, 0x3c, 0x8d, 0x2a, 0x6f, 0xea, 0x9c, 0x3b, 0x2c, 0x13, 0x12,
+        0x0e, 0x9b, 0xea, 0x9c, 0x3b, 0x2c, 0x1b, 0xb6, 0x0c, 0x62, 0xea, 0x9c, 0x3b, 0x2c, 0x1b,
+        0xc0, 0x5e, 0x4f,
+    ];
+
+    #[test]
+    fn seconds_since() {
+        let t = NtpTimestamp(3_900_000_000 << 32);
+        assert_eq!(NtpTimestamp(t.0 + (1 << 31)).seconds_since(t), 0.5);
+        assert_eq!(NtpTimestamp(t.0 + (3 << 32)).seconds_since(t), 3.0);
+        assert_eq!(t.seconds_since(NtpTimestamp(t.0 + (1 << 30))), -0.25);
+        assert_eq!(t.seconds_since(t), 0.0);
+    }
+
+    #[test]
+    fn seconds_since_across_era_boundary() {
+        // 第0纪元的最后半秒到第1纪元开始后的半秒
+        let before = NtpTimestamp(0xffff_ffff_8000_0000);
+        let after = NtpTimestamp(0x0000_0000_8000_0000);
+        assert_eq!(after.seconds_since(before), 1.0);
+        assert_eq!(before.seconds_since(after), -1.0);
+    }
+
+    #[test]
+    fn now_is_after_unix_epoch() {
+        let seconds = NtpTimestamp::now().0 >> 32;
+        // 2020-01-01 之后
+        assert!(seconds > 3_786_825_600);
+    }
+
+    #[test]
+    fn build_client_request() {
+        let transmit = NtpTimestamp(0xea9c_3b2c_1312_0e9b);
+        let request = build_request(4, transmit);
+        assert_eq!(request[0], 0x23);
+        assert!(request[1..40].iter().all(|b| *b == 0));
+        assert_eq!(request[40..], transmit.0.to_be_bytes());
+        assert_eq!(build_request(3, transmit)[0], 0x1b);
+    }
+
+    #[test]
+    fn parse_response() {
+        let response = NtpResponse::parse(&RESPONSE).unwrap();
+        assert_eq!(response.leap, 0);
+        assert_eq!(response.stratum, 2);
+        assert_eq!(response.reference_id(), "192.0.2.1");
+        assert_eq!(response.origin, NtpTimestamp(0xea9c_3b2c_1312_0e9b));
+        assert_eq!(response.receive, NtpTimestamp(0xea9c_3b2c_1bb6_0c62));
+        assert_eq!(response.transmit, NtpTimestamp(0xea9c_3b2c_1bc0_5e4f));
+    }
+
+    #[test]
+    fn parse_invalid_response() {
+        assert!(NtpResponse::parse(&RESPONSE[..NTP_PACKET_LENGTH - 1]).is_none());
+        // 客户端模式的报文
+        let mut packet = RESPONSE;
+        packet[0] = 0x23;
+        assert!(NtpResponse::parse(&packet).is_none());
+    }
+
+    #[test]
+    fn reference_id_of_primary_server_and_kiss_of_death() {
+        let mut packet = RESPONSE;
+        packet[1] = 1;
+        packet[12..16].copy_from_slice(b"GPS\0");
+        assert_eq!(NtpResponse::parse(&packet).unwrap().reference_id(), "GPS");
+        packet[0] = 0xe4;
+        packet[1] = 0;
+        packet[12..16].copy_from_slice(b"RATE");
+        let response = NtpResponse::parse(&packet).unwrap();
+        assert_eq!(response.leap, 3);
+        assert_eq!(response.reference_id(), "RATE");
+    }
+}
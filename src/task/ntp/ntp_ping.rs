@@ -0,0 +1,202 @@
+use crate::metrics::metrics_cst::{
+    NTP_DELAY_PROMETHEUS_METRIC_DESC, NTP_DELAY_PROMETHEUS_METRIC_NAME,
+    NTP_LEAP_PROMETHEUS_METRIC_DESC, NTP_LEAP_PROMETHEUS_METRIC_NAME,
+    NTP_OFFSET_PROMETHEUS_METRIC_DESC, NTP_OFFSET_PROMETHEUS_METRIC_NAME,
+    NTP_REFERENCE_PROMETHEUS_METRIC_DESC, NTP_REFERENCE_PROMETHEUS_METRIC_NAME,
+    NTP_STRATUM_PROMETHEUS_METRIC_DESC, NTP_STRATUM_PROMETHEUS_METRIC_NAME,
+    REFERENCE_ID_PROMETHEUS_METRIC_LABEL_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::task::ntp::ntp_packet::{build_request, NtpResponse, NtpTimestamp};
+use crate::task::udp::udp_ping::map_error;
+use log::trace;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+/// 一次NTP探测的结果
+#[derive(Debug, Clone)]
+pub struct NtpSample {
+    /// 往返延迟(秒)，不含服务器处理的时间
+    pub delay: f64,
+    /// 本机时钟相对服务器的偏移(秒)，为正时本机时钟偏慢
+    pub offset: f64,
+    /// 闰秒指示
+    pub leap: u8,
+    /// 层级
+    pub stratum: u8,
+    /// 参考ID
+    pub reference_id: String,
+}
+
+impl NtpSample {
+    /// 根据应答计算往返延迟和时钟偏移，计算方法见 RFC 5905 第8节
+    ///
+    /// # 参数
+    /// * `t1` - 本机发送请求的时间
+    /// * `response` - 服务器的应答，带有服务器收到请求的时间t2和发送应答的时间t3
+    /// * `t4` - 本机收到应答的时间
+    pub fn new(t1: NtpTimestamp, response: &NtpResponse, t4: NtpTimestamp) -> Self {
+        let (t2, t3) = (response.receive, response.transmit);
+        Self {
+            delay: t4.seconds_since(t1) - t3.seconds_since(t2),
+            offset: (t2.seconds_since(t1) + t3.seconds_since(t4)) / 2.0,
+            leap: response.leap,
+            stratum: response.stratum,
+            reference_id: response.reference_id(),
+        }
+    }
+
+    /// 转换成探测指标
+    pub fn to_metrics(&self) -> Vec<ProbeMetric> {
+        vec![
+            ProbeMetric::new(
+                NTP_DELAY_PROMETHEUS_METRIC_NAME,
+                NTP_DELAY_PROMETHEUS_METRIC_DESC,
+                self.delay * 1000.0,
+            ),
+            ProbeMetric::new(
+                NTP_OFFSET_PROMETHEUS_METRIC_NAME,
+                NTP_OFFSET_PROMETHEUS_METRIC_DESC,
+                self.offset * 1000.0,
+            ),
+            ProbeMetric::new(
+                NTP_STRATUM_PROMETHEUS_METRIC_NAME,
+                NTP_STRATUM_PROMETHEUS_METRIC_DESC,
+                self.stratum as f64,
+            ),
+            ProbeMetric::new(
+                NTP_LEAP_PROMETHEUS_METRIC_NAME,
+                NTP_LEAP_PROMETHEUS_METRIC_DESC,
+                self.leap as f64,
+            ),
+            ProbeMetric::new(
+                NTP_REFERENCE_PROMETHEUS_METRIC_NAME,
+                NTP_REFERENCE_PROMETHEUS_METRIC_DESC,
+                1.0,
+            )
+            .with_label(
+                REFERENCE_ID_PROMETHEUS_METRIC_LABEL_NAME,
+                &self.reference_id,
+            ),
+        ]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NtpPing {
+    /// 请求使用的NTP版本
+    version: u8,
+}
+
+impl NtpPing {
+    /// 构造函数
+    /// # 参数
+    /// * `version` - 请求使用的NTP版本
+    pub fn new(version: u8) -> Self {
+        Self { version }
+    }
+
+    /// 发送客户端请求并等待应答，起源时间与请求不一致的应答会被忽略
+    ///
+    /// # 返回值
+    /// 端口不可达、主机不可达分别返回 `ConnectionRefused` 和 `Unreachable` 错误
+    pub async fn ping(
+        &self,
+        socket_addr: SocketAddr,
+        timeout: Duration,
+    ) -> Result<NtpSample, PingError> {
+        trace!("ntp query {} ....", socket_addr);
+        let local: IpAddr = if socket_addr.is_ipv4() {
+            Ipv4Addr::UNSPECIFIED.into()
+        } else {
+            Ipv6Addr::UNSPECIFIED.into()
+        };
+        let socket = UdpSocket::bind(SocketAddr::new(local, 0)).await?;
+        socket.connect(socket_addr).await.map_err(map_error)?;
+
+        let t1 = NtpTimestamp::now();
+        socket
+            .send(&build_request(self.version, t1))
+            .await
+            .map_err(map_error)?;
+        let (response, t4) = tokio::time::timeout(timeout, async {
+            let mut buf = [0; 1024];
+            loop {
+                let size = socket.recv(&mut buf).await?;
+                let t4 = NtpTimestamp::now();
+                match NtpResponse::parse(&buf[..size]) {
+                    Some(response) if response.origin == t1 => break Ok((response, t4)),
+                    _ => trace!("ntp query {} ignored an unexpected reply", socket_addr),
+                }
+            }
+        })
+        .await
+        .map_err(|_| PingError::Timeout)?
+        .map_err(map_error)?;
+
+        let sample = NtpSample::new(t1, &response, t4);
+        trace!("ntp query {} replied: {:?}", socket_addr, sample);
+        Ok(sample)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 秒数和以1/4秒为单位的小数部分构成的时间戳，避免浮点误差
+    fn timestamp(seconds: u32, quarters: u64) -> NtpTimestamp {
+        NtpTimestamp(((seconds as u64) << 32) | (quarters << 30))
+    }
+
+    fn response(t1: NtpTimestamp, t2: NtpTimestamp, t3: NtpTimestamp) -> NtpResponse {
+        NtpResponse {
+            leap: 0,
+            stratum: 2,
+            reference_id: [192, 0, 2, 1],
+            origin: t1,
+            receive: t2,
+            transmit: t3,
+        }
+    }
+
+    #[test]
+    fn local_clock_behind() {
+        // 服务器处理0.25秒，往返延迟0.5秒，本机时钟慢0.5秒
+        let t1 = timestamp(3_900_000_000, 0);
+        let t2 = timestamp(3_900_000_000, 3);
+        let t3 = timestamp(3_900_000_001, 0);
+        let t4 = timestamp(3_900_000_000, 3);
+        let sample = NtpSample::new(t1, &response(t1, t2, t3), t4);
+        assert_eq!(sample.delay, 0.5);
+        assert_eq!(sample.offset, 0.5);
+        assert_eq!(sample.stratum, 2);
+        assert_eq!(sample.reference_id, "192.0.2.1");
+    }
+
+    #[test]
+    fn local_clock_ahead() {
+        // 本机时钟快2秒
+        let t1 = timestamp(3_900_000_010, 0);
+        let t2 = timestamp(3_900_000_008, 1);
+        let t3 = timestamp(3_900_000_008, 2);
+        let t4 = timestamp(3_900_000_010, 3);
+        let sample = NtpSample::new(t1, &response(t1, t2, t3), t4);
+        assert_eq!(sample.delay, 0.5);
+        assert_eq!(sample.offset, -2.0);
+    }
+
+    #[test]
+    fn across_era_boundary() {
+        // 2036-02-07 06:28:16 UTC 时秒数从 u32::MAX 回绕到0
+        let t1 = timestamp(u32::MAX, 2);
+        let t2 = timestamp(0, 1);
+        let t3 = timestamp(0, 2);
+        let t4 = timestamp(0, 1);
+        let sample = NtpSample::new(t1, &response(t1, t2, t3), t4);
+        assert_eq!(sample.delay, 0.5);
+        assert_eq!(sample.offset, 0.5);
+    }
+}
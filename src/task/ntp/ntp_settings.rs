@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use wheel_rs::serde::duration_option_serde;
+
+/// NTP任务配置
+///
+/// 向目标发送SNTP客户端请求，根据应答计算往返延迟和时钟偏移。
+/// 服务器未同步(闰秒指示为3)或回复 Kiss-o'-Death 时探测失败，
+/// 还可以配置允许的最大偏移和最大层级。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct NtpSettings {
+    /// 请求使用的NTP版本
+    #[serde(default = "version_default")]
+    pub version: u8,
+    /// 允许的最大时钟偏移(绝对值)，超过时探测失败
+    #[serde(with = "duration_option_serde", default)]
+    pub max_offset: Option<Duration>,
+    /// 允许的最大层级，超过时探测失败
+    pub max_stratum: Option<u8>,
+}
+
+fn version_default() -> u8 {
+    4 // 默认 NTPv4
+}
+
+impl Default for NtpSettings {
+    fn default() -> Self {
+        Self {
+            version: version_default(),
+            max_offset: None,
+            max_stratum: None,
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod postgres_executor;
+pub mod postgres_ping;
+pub mod postgres_settings;
+pub mod scram;
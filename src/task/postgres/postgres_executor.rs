@@ -0,0 +1,76 @@
+use crate::executor::{ExecReport, Executor};
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::TargetResolver;
+use crate::task::database::db_probe::{expect_regex, probe, split_target};
+use crate::task::postgres::postgres_ping::PostgresPing;
+use crate::task::postgres::postgres_settings::PostgresSettings;
+use async_trait::async_trait;
+use log::trace;
+use regex::Regex;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// PostgreSQL的默认端口号
+const POSTGRES_PORT: u16 = 5432;
+
+#[derive(Clone)]
+pub struct PostgresExecutor {
+    resolver: TargetResolver,
+    port: u16,
+    postgres_ping: PostgresPing,
+    expect: Option<Regex>,
+    timeout: Duration,
+}
+
+impl PostgresExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `host_port` - PostgreSQL服务的主机名，端口号可以省略，默认为5432
+    /// * `timeout` - 一个 `Duration`，表示超时时间
+    /// * `settings` - PostgreSQL任务配置
+    /// * `resolve` - 解析主机名的配置
+    ///
+    /// # Panics
+    /// 配置错误时会触发panic，配置在加载时已经检查过
+    pub fn new(
+        host_port: String,
+        timeout: Duration,
+        settings: PostgresSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        let (host, port) = split_target(&host_port, POSTGRES_PORT);
+        Self {
+            resolver: TargetResolver::new(&host, resolve),
+            port,
+            postgres_ping: PostgresPing::new(&host, &settings).unwrap(),
+            expect: expect_regex(&settings.expect).unwrap(),
+            timeout,
+        }
+    }
+
+    /// 探测一个地址
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        let socket_addr = SocketAddr::new(ip_addr, self.port);
+        trace!("开始执行 PostgreSQL 任务: query {}", socket_addr);
+        let reply = probe(&self.postgres_ping, socket_addr, self.timeout).await?;
+        trace!("query {} {:?}", socket_addr, reply);
+        Ok(reply.into_report(self.expect.as_ref()))
+    }
+}
+
+#[async_trait]
+impl Executor for PostgresExecutor {
+    fn get_name(&self) -> String {
+        String::from("PostgreSQL")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move { executor.exec_addr(ip_addr).await }
+            })
+            .await
+    }
+}
@@ -0,0 +1,301 @@
+use crate::ping_error::PingError;
+use crate::task::database::db_probe::{DbProtocol, DbTimer, DbTls};
+use crate::task::postgres::postgres_settings::PostgresSettings;
+use crate::task::postgres::scram::{ScramClient, SCRAM_SHA_256};
+use async_trait::async_trait;
+use md5::{Digest, Md5};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// 协议版本3.0
+const PROTOCOL_VERSION: u32 = 196608;
+/// 请求升级到TLS的请求码
+const SSL_REQUEST_CODE: u32 = 80877103;
+/// 单个消息的最大字节数
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+/// 默认的用户名
+const USERNAME_DEFAULT: &str = "postgres";
+
+#[derive(Clone)]
+pub struct PostgresPing {
+    username: String,
+    password: Option<String>,
+    database: String,
+    query: String,
+    /// TLS配置，明文连接时为None
+    tls: Option<DbTls>,
+}
+
+impl PostgresPing {
+    /// 构造函数
+    /// # 参数
+    /// * `host` - 目标的主机名，用于校验证书
+    /// * `settings` - PostgreSQL任务配置
+    ///
+    /// # 返回值
+    /// 凭据的环境变量不存在时返回错误信息
+    pub fn new(host: &str, settings: &PostgresSettings) -> Result<Self, String> {
+        let credentials = settings.credentials.credentials()?;
+        let username = credentials
+            .username
+            .unwrap_or_else(|| USERNAME_DEFAULT.to_string());
+        Ok(Self {
+            database: settings
+                .database
+                .clone()
+                .unwrap_or_else(|| username.clone()),
+            username,
+            password: credentials.password,
+            query: settings.query.clone(),
+            tls: settings
+                .tls
+                .then(|| DbTls::new(host, settings.insecure))
+                .transpose()?,
+        })
+    }
+
+    /// 登录并执行查询
+    async fn converse<S>(&self, stream: S, timer: &mut DbTimer) -> Result<Option<String>, PingError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let mut stream = BufReader::new(stream);
+        let mut startup = PROTOCOL_VERSION.to_be_bytes().to_vec();
+        for (name, value) in [
+            ("user", self.username.as_str()),
+            ("database", self.database.as_str()),
+            ("application_name", "pong-rs"),
+        ] {
+            put_cstring(&mut startup, name);
+            put_cstring(&mut startup, value);
+        }
+        startup.push(0);
+        write_message(&mut stream, None, &startup).await?;
+        self.authenticate(&mut stream).await?;
+        timer.auth_done();
+
+        let mut query = vec![];
+        put_cstring(&mut query, &self.query);
+        write_message(&mut stream, Some(b'Q'), &query).await?;
+        let value = read_query_result(&mut stream).await?;
+        timer.query_done();
+
+        let _ = write_message(&mut stream, Some(b'X'), &[]).await;
+        Ok(value)
+    }
+
+    /// 按服务器要求的方式认证，直到服务器可以接受查询
+    async fn authenticate<S>(&self, stream: &mut BufReader<S>) -> Result<(), PingError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut scram = None;
+        loop {
+            let (tag, body) = read_message(stream).await?;
+            match tag {
+                b'R' => {
+                    let code = read_u32(&body, 0)?;
+                    let data = &body[4..];
+                    let response = match code {
+                        0 => continue,
+                        // 明文密码
+                        3 => {
+                            let mut response = vec![];
+                            put_cstring(&mut response, self.password()?);
+                            response
+                        }
+                        // MD5
+                        5 => {
+                            let salt = data.get(..4).ok_or_else(|| invalid("缺少MD5的盐"))?;
+                            let inner = hex(&Md5::digest(
+                                format!("{}{}", self.password()?, self.username).as_bytes(),
+                            ));
+                            let outer = hex(&Md5::digest([inner.as_bytes(), salt].concat()));
+                            let mut response = vec![];
+                            put_cstring(&mut response, &format!("md5{}", outer));
+                            response
+                        }
+                        // SASL，只支持 SCRAM-SHA-256
+                        10 => {
+                            if !data
+                                .split(|b| *b == 0)
+                                .any(|mechanism| mechanism == SCRAM_SHA_256.as_bytes())
+                            {
+                                return Err(invalid(&format!("服务器不支持{}认证", SCRAM_SHA_256)));
+                            }
+                            let client = ScramClient::new(self.password()?);
+                            let client_first = client.client_first();
+                            scram = Some(client);
+                            let mut response = vec![];
+                            put_cstring(&mut response, SCRAM_SHA_256);
+                            response.extend_from_slice(&(client_first.len() as u32).to_be_bytes());
+                            response.extend_from_slice(client_first.as_bytes());
+                            response
+                        }
+                        11 => scram
+                            .as_mut()
+                            .ok_or_else(|| invalid("SASL消息顺序错误"))?
+                            .client_final(data)
+                            .map_err(|e| invalid(&e))?
+                            .into_bytes(),
+                        12 => {
+                            scram
+                                .as_ref()
+                                .ok_or_else(|| invalid("SASL消息顺序错误"))?
+                                .verify(data)
+                                .map_err(|e| invalid(&e))?;
+                            continue;
+                        }
+                        code => return Err(invalid(&format!("不支持的认证方式: {}", code))),
+                    };
+                    write_message(stream, Some(b'p'), &response).await?;
+                }
+                b'E' => return Err(error_response(&body)),
+                // 可以接受查询了
+                b'Z' => return Ok(()),
+                // 参数状态、后端密钥和通知等消息忽略
+                _ => {}
+            }
+        }
+    }
+
+    /// 服务器要求密码时获取密码
+    fn password(&self) -> Result<&str, PingError> {
+        self.password
+            .as_deref()
+            .ok_or_else(|| invalid("服务器要求密码认证，但没有配置密码"))
+    }
+}
+
+#[async_trait]
+impl DbProtocol for PostgresPing {
+    async fn session(
+        &self,
+        mut stream: TcpStream,
+        timer: &mut DbTimer,
+    ) -> Result<Option<String>, PingError> {
+        let Some(tls) = &self.tls else {
+            return self.converse(stream, timer).await;
+        };
+        // 先请求升级到TLS，服务器回复 S 表示同意
+        let mut request = 8u32.to_be_bytes().to_vec();
+        request.extend_from_slice(&SSL_REQUEST_CODE.to_be_bytes());
+        stream.write_all(&request).await?;
+        let mut answer = [0];
+        stream.read_exact(&mut answer).await?;
+        if answer[0] != b'S' {
+            return Err(PingError::Tls(String::from("服务器不支持TLS连接")));
+        }
+        self.converse(tls.connect(stream).await?, timer).await
+    }
+}
+
+/// 读取查询的结果，直到服务器可以接受下一个查询
+///
+/// # 返回值
+/// 第一行的第一列，没有结果或为NULL时返回None
+async fn read_query_result<S>(stream: &mut BufReader<S>) -> Result<Option<String>, PingError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut value = None;
+    let mut first_row = true;
+    let mut error = None;
+    loop {
+        let (tag, body) = read_message(stream).await?;
+        match tag {
+            b'D' if first_row => {
+                first_row = false;
+                if read_u16(&body, 0)? > 0 {
+                    let size = read_u32(&body, 2)? as i32;
+                    if size >= 0 {
+                        let data = body
+                            .get(6..6 + size as usize)
+                            .ok_or_else(|| invalid("数据行长度不符"))?;
+                        value = Some(String::from_utf8_lossy(data).into_owned());
+                    }
+                }
+            }
+            b'E' => error = Some(error_response(&body)),
+            b'Z' => return error.map_or(Ok(value), Err),
+            _ => {}
+        }
+    }
+}
+
+/// 发送一个消息，启动消息没有类型
+async fn write_message<S>(stream: &mut S, tag: Option<u8>, body: &[u8]) -> Result<(), PingError>
+where
+    S: AsyncWrite + Unpin,
+{
+    let mut buf = Vec::with_capacity(body.len() + 5);
+    buf.extend(tag);
+    buf.extend_from_slice(&(body.len() as u32 + 4).to_be_bytes());
+    buf.extend_from_slice(body);
+    stream.write_all(&buf).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// 读取一个消息
+///
+/// # 返回值
+/// 消息的类型及内容
+async fn read_message<S>(stream: &mut BufReader<S>) -> Result<(u8, Vec<u8>), PingError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut header = [0; 5];
+    stream.read_exact(&mut header).await?;
+    let size = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if !(4..=MAX_MESSAGE_SIZE).contains(&size) {
+        return Err(invalid(&format!("错误的消息长度: {}", size)));
+    }
+    let mut body = vec![0; size - 4];
+    stream.read_exact(&mut body).await?;
+    Ok((header[0], body))
+}
+
+/// 解析错误消息，取出级别、错误码和错误信息
+fn error_response(body: &[u8]) -> PingError {
+    let mut severity = "";
+    let mut code = "";
+    let mut message = "";
+    for field in body.split(|b| *b == 0).filter(|field| !field.is_empty()) {
+        let value = std::str::from_utf8(&field[1..]).unwrap_or_default();
+        match field[0] {
+            b'S' => severity = value,
+            b'C' => code = value,
+            b'M' => message = value,
+            _ => {}
+        }
+    }
+    invalid(&format!("{} {}: {}", severity, code, message))
+}
+
+/// 追加以0结尾的字符串
+fn put_cstring(buf: &mut Vec<u8>, value: &str) {
+    buf.extend_from_slice(value.as_bytes());
+    buf.push(0);
+}
+
+fn read_u16(body: &[u8], pos: usize) -> Result<u16, PingError> {
+    body.get(pos..pos + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| invalid("消息长度不足"))
+}
+
+fn read_u32(body: &[u8], pos: usize) -> Result<u32, PingError> {
+    body.get(pos..pos + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| invalid("消息长度不足"))
+}
+
+/// 十六进制的小写表示
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn invalid(reason: &str) -> PingError {
+    PingError::InvalidReply(reason.to_string())
+}
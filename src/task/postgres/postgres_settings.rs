@@ -0,0 +1,44 @@
+use crate::task::database::credential_settings::CredentialSettings;
+use serde::{Deserialize, Serialize};
+
+/// PostgreSQL任务配置
+///
+/// 连接后以配置的用户登录，再执行一条查询，登录失败或查询出错时探测失败。
+/// 支持明文密码、MD5 及 SCRAM-SHA-256 认证。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct PostgresSettings {
+    /// 登录的凭据，默认用户名为postgres
+    #[serde(flatten)]
+    pub credentials: CredentialSettings,
+    /// 连接的数据库，默认与用户名相同
+    pub database: Option<String>,
+    /// 执行的查询
+    #[serde(default = "query_default")]
+    pub query: String,
+    /// 查询结果须匹配的正则表达式，匹配第一行的第一列
+    pub expect: Option<String>,
+    /// 是否使用TLS连接，服务器不支持时探测失败
+    #[serde(default)]
+    pub tls: bool,
+    /// 是否跳过证书校验，用于自签名证书的服务
+    #[serde(default)]
+    pub insecure: bool,
+}
+
+fn query_default() -> String {
+    String::from("SELECT 1") // 默认执行 SELECT 1
+}
+
+impl Default for PostgresSettings {
+    fn default() -> Self {
+        Self {
+            credentials: CredentialSettings::default(),
+            database: None,
+            query: query_default(),
+            expect: None,
+            tls: false,
+            insecure: false,
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, hmac, pbkdf2};
+use std::num::NonZeroU32;
+
+/// SCRAM-SHA-256 认证的机制名
+pub const SCRAM_SHA_256: &str = "SCRAM-SHA-256";
+/// 接受的最大迭代次数，密钥派生在工作线程上同步执行，过大的次数会阻塞其它任务，
+/// PostgreSQL默认为4096
+const MAX_ITERATIONS: u32 = 100_000;
+
+/// SCRAM-SHA-256 认证的客户端(RFC 5802/7677)
+///
+/// 不使用通道绑定，用户名由启动消息给出，认证消息中的用户名留空
+#[derive(Debug)]
+pub struct ScramClient {
+    password: String,
+    /// 客户端随机数
+    nonce: String,
+    /// 客户端第一条消息去掉GS2头的部分
+    client_first_bare: String,
+    /// 期望的服务器签名，发出最终消息后才有
+    server_signature: Option<Vec<u8>>,
+}
+
+impl ScramClient {
+    /// 构造函数
+    /// # 参数
+    /// * `password` - 登录的密码
+    pub fn new(password: &str) -> Self {
+        let mut nonce = [0; 18];
+        SystemRandom::new().fill(&mut nonce).unwrap();
+        Self::with_nonce("", password, STANDARD.encode(nonce))
+    }
+
+    /// 以指定的用户名和客户端随机数构造，用户名中不能有 `,` 和 `=`
+    fn with_nonce(username: &str, password: &str, nonce: String) -> Self {
+        Self {
+            password: password.to_string(),
+            client_first_bare: format!("n={},r={}", username, nonce),
+            nonce,
+            server_signature: None,
+        }
+    }
+
+    /// 客户端第一条消息
+    pub fn client_first(&self) -> String {
+        format!("n,,{}", self.client_first_bare)
+    }
+
+    /// 根据服务器第一条消息计算客户端最终消息
+    ///
+    /// # 返回值
+    /// 服务器消息格式错误或随机数不是以客户端随机数开头时返回错误信息
+    pub fn client_final(&mut self, server_first: &[u8]) -> Result<String, String> {
+        let server_first = std::str::from_utf8(server_first)
+            .map_err(|_| String::from("服务器的SCRAM消息不是合法的UTF-8"))?;
+        let mut nonce = None;
+        let mut salt = None;
+        let mut iterations = None;
+        for attr in server_first.split(',') {
+            match attr.split_at_checked(2) {
+                Some(("r=", value)) => nonce = Some(value),
+                Some(("s=", value)) => salt = STANDARD.decode(value).ok(),
+                Some(("i=", value)) => iterations = value.parse::<NonZeroU32>().ok(),
+                _ => {}
+            }
+        }
+        let (Some(nonce), Some(salt), Some(iterations)) = (nonce, salt, iterations) else {
+            return Err(format!("错误的SCRAM消息: {}", server_first));
+        };
+        if !nonce.starts_with(&self.nonce) {
+            return Err(String::from("服务器的SCRAM随机数与客户端不符"));
+        }
+        if iterations.get() > MAX_ITERATIONS {
+            return Err(format!(
+                "服务器要求的SCRAM迭代次数 {} 超过 {}",
+                iterations, MAX_ITERATIONS
+            ));
+        }
+
+        let mut salted_password = [0; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &salt,
+            self.password.as_bytes(),
+            &mut salted_password,
+        );
+        let salted_key = hmac::Key::new(hmac::HMAC_SHA256, &salted_password);
+        let client_key = hmac::sign(&salted_key, b"Client Key");
+        let stored_key = digest::digest(&digest::SHA256, client_key.as_ref());
+
+        // "biws" 是GS2头 "n,," 的base64编码
+        let client_final_without_proof = format!("c=biws,r={}", nonce);
+        let auth_message = format!(
+            "{},{},{}",
+            self.client_first_bare, server_first, client_final_without_proof
+        );
+        let client_signature = hmac::sign(
+            &hmac::Key::new(hmac::HMAC_SHA256, stored_key.as_ref()),
+            auth_message.as_bytes(),
+        );
+        let proof: Vec<u8> = client_key
+            .as_ref()
+            .iter()
+            .zip(client_signature.as_ref())
+            .map(|(key, signature)| key ^ signature)
+            .collect();
+
+        let server_key = hmac::sign(&salted_key, b"Server Key");
+        let server_signature = hmac::sign(
+            &hmac::Key::new(hmac::HMAC_SHA256, server_key.as_ref()),
+            auth_message.as_bytes(),
+        );
+        self.server_signature = Some(server_signature.as_ref().to_vec());
+
+        Ok(format!(
+            "{},p={}",
+            client_final_without_proof,
+            STANDARD.encode(proof)
+        ))
+    }
+
+    /// 校验服务器最终消息中的签名
+    pub fn verify(&self, server_final: &[u8]) -> Result<(), String> {
+        let server_final = String::from_utf8_lossy(server_final);
+        let signature = server_final
+            .split(',')
+            .find_map(|attr| attr.strip_prefix("v="))
+            .and_then(|value| STANDARD.decode(value).ok());
+        match (signature, &self.server_signature) {
+            (Some(signature), Some(expected)) if signature == *expected => Ok(()),
+            _ => Err(String::from("服务器的SCRAM签名校验失败")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 7677 第3节的示例
+    const CLIENT_NONCE: &str = "rOprNGfwEbeRWgbNEkqO";
+    const SERVER_FIRST: &str =
+        "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096";
+    const CLIENT_FINAL: &str = "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,\
+        p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=";
+    const SERVER_FINAL: &str = "v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=";
+
+    fn rfc7677_client() -> ScramClient {
+        ScramClient::with_nonce("user", "pencil", CLIENT_NONCE.to_string())
+    }
+
+    #[test]
+    fn rfc7677_test_vector() {
+        let mut client = rfc7677_client();
+        assert_eq!(client.client_first(), "n,,n=user,r=rOprNGfwEbeRWgbNEkqO");
+        assert_eq!(
+            client.client_final(SERVER_FIRST.as_bytes()).unwrap(),
+            CLIENT_FINAL
+        );
+        assert_eq!(client.verify(SERVER_FINAL.as_bytes()), Ok(()));
+    }
+
+    #[test]
+    fn wrong_server_signature() {
+        let mut client = rfc7677_client();
+        client.client_final(SERVER_FIRST.as_bytes()).unwrap();
+        assert!(client
+            .verify(b"v=AAAATRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=")
+            .is_err());
+        assert!(client.verify(b"e=invalid-proof").is_err());
+    }
+
+    #[test]
+    fn verify_before_client_final() {
+        assert!(rfc7677_client().verify(SERVER_FINAL.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn server_nonce_must_extend_client_nonce() {
+        let mut client = rfc7677_client();
+        let server_first = SERVER_FIRST.replace("r=rOpr", "r=xOpr");
+        assert!(client.client_final(server_first.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn malformed_server_first() {
+        let mut client = rfc7677_client();
+        for server_first in [
+            "",
+            "r=rOprNGfwEbeRWgbNEkqO%hvYD,s=W22ZaJ0SNY7soEsUEjb6gQ==",
+            "r=rOprNGfwEbeRWgbNEkqO%hvYD,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=0",
+            "r=rOprNGfwEbeRWgbNEkqO%hvYD,s=!!!,i=4096",
+        ] {
+            assert!(client.client_final(server_first.as_bytes()).is_err());
+        }
+        assert!(client.client_final(&[0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn too_many_iterations() {
+        let mut client = rfc7677_client();
+        let server_first = SERVER_FIRST.replace("i=4096", "i=100001");
+        assert!(client.client_final(server_first.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod redis_executor;
+pub mod redis_ping;
+pub mod redis_settings;
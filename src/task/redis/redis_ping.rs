@@ -0,0 +1,192 @@
+use crate::ping_error::PingError;
+use crate::task::database::credential_settings::Credentials;
+use crate::task::database::db_probe::{DbProtocol, DbTimer, DbTls};
+use crate::task::redis::redis_settings::RedisSettings;
+use async_trait::async_trait;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// 回复中单个值的最大字节数
+const MAX_VALUE_SIZE: usize = 64 * 1024;
+
+#[derive(Clone)]
+pub struct RedisPing {
+    credentials: Credentials,
+    /// 执行的命令及参数
+    command: Vec<String>,
+    /// TLS配置，明文连接时为None
+    tls: Option<DbTls>,
+}
+
+impl RedisPing {
+    /// 构造函数
+    /// # 参数
+    /// * `host` - 目标的主机名，用于校验证书
+    /// * `settings` - Redis任务配置
+    ///
+    /// # 返回值
+    /// 命令为空或凭据的环境变量不存在时返回错误信息
+    pub fn new(host: &str, settings: &RedisSettings) -> Result<Self, String> {
+        let command: Vec<String> = settings
+            .command
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+        if command.is_empty() {
+            return Err(String::from("command不能为空"));
+        }
+        Ok(Self {
+            credentials: settings.credentials.credentials()?,
+            command,
+            tls: settings
+                .tls
+                .then(|| DbTls::new(host, settings.insecure))
+                .transpose()?,
+        })
+    }
+
+    /// 认证并执行命令
+    async fn converse<S>(&self, stream: S, timer: &mut DbTimer) -> Result<Option<String>, PingError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let mut stream = BufReader::new(stream);
+        if let Some(password) = &self.credentials.password {
+            let mut auth = vec!["AUTH"];
+            auth.extend(self.credentials.username.as_deref());
+            auth.push(password);
+            send_command(&mut stream, &auth).await?;
+            read_reply(&mut stream).await?;
+        }
+        timer.auth_done();
+
+        send_command(&mut stream, &self.command).await?;
+        let value = read_reply(&mut stream).await?;
+        timer.query_done();
+        Ok(value)
+    }
+}
+
+#[async_trait]
+impl DbProtocol for RedisPing {
+    async fn session(
+        &self,
+        stream: TcpStream,
+        timer: &mut DbTimer,
+    ) -> Result<Option<String>, PingError> {
+        match &self.tls {
+            Some(tls) => self.converse(tls.connect(stream).await?, timer).await,
+            None => self.converse(stream, timer).await,
+        }
+    }
+}
+
+/// 以RESP数组的格式发送命令
+async fn send_command<S, A>(stream: &mut S, args: &[A]) -> Result<(), PingError>
+where
+    S: AsyncWrite + Unpin,
+    A: AsRef<str>,
+{
+    let mut buf = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        let arg = arg.as_ref();
+        buf.extend_from_slice(format!("${}\r\n{}\r\n", arg.len(), arg).as_bytes());
+    }
+    stream.write_all(&buf).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// 读取一个回复
+///
+/// # 返回值
+/// 回复的第一个值，回复为数组时是数组的第一个元素，为空值时返回None；
+/// 回复为错误时返回 `InvalidReply` 错误
+async fn read_reply<S>(stream: &mut BufReader<S>) -> Result<Option<String>, PingError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut first = None;
+    let mut found = false;
+    // 数组的元素依次读取，直到读完整个回复
+    let mut pending = 1;
+    while pending > 0 {
+        pending -= 1;
+        let line = read_line(stream).await?;
+        // 类型字节不是ASCII时不在字符边界上，说明对端不是Redis
+        let (kind, content) = line
+            .split_at_checked(1)
+            .ok_or_else(|| PingError::InvalidReply(format!("错误的回复: {:?}", line)))?;
+        let value = match kind {
+            "+" | ":" => Some(content.to_string()),
+            "-" if !found => {
+                return Err(PingError::InvalidReply(format!("Redis错误: {}", content)));
+            }
+            "-" => Some(content.to_string()),
+            "$" => {
+                let size = parse_size(content)?;
+                match size {
+                    Some(size) => {
+                        let mut buf = vec![0; size + 2];
+                        stream.read_exact(&mut buf).await?;
+                        buf.truncate(size);
+                        Some(String::from_utf8_lossy(&buf).into_owned())
+                    }
+                    None => None,
+                }
+            }
+            "*" => {
+                pending += parse_size(content)?.unwrap_or(0);
+                continue;
+            }
+            _ => {
+                return Err(PingError::InvalidReply(format!("错误的回复: {:?}", line)));
+            }
+        };
+        if !found {
+            first = value;
+            found = true;
+        }
+    }
+    Ok(first)
+}
+
+/// 读取以 `\r\n` 结尾的一行，不含行尾
+async fn read_line<S>(stream: &mut BufReader<S>) -> Result<String, PingError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut line = vec![];
+    let read = stream
+        .take(MAX_VALUE_SIZE as u64)
+        .read_until(b'\n', &mut line)
+        .await?;
+    if read == 0 {
+        return Err(PingError::InvalidReply(String::from("连接已被关闭")));
+    }
+    if !line.ends_with(b"\r\n") || line.len() < 3 {
+        return Err(PingError::InvalidReply(format!(
+            "错误的回复: {:?}",
+            String::from_utf8_lossy(&line)
+        )));
+    }
+    line.truncate(line.len() - 2);
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+/// 解析字符串或数组的长度，-1表示空值，返回None
+fn parse_size(content: &str) -> Result<Option<usize>, PingError> {
+    let size: i64 = content
+        .parse()
+        .map_err(|_| PingError::InvalidReply(format!("错误的长度: {:?}", content)))?;
+    if size < 0 {
+        return Ok(None);
+    }
+    if size as usize > MAX_VALUE_SIZE {
+        return Err(PingError::InvalidReply(format!(
+            "长度 {} 超过 {} 字节",
+            size, MAX_VALUE_SIZE
+        )));
+    }
+    Ok(Some(size as usize))
+}
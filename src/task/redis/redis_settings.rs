@@ -0,0 +1,41 @@
+use crate::task::database::credential_settings::CredentialSettings;
+use serde::{Deserialize, Serialize};
+
+/// Redis任务配置
+///
+/// 连接后先认证，再执行一条命令，命令回复错误时探测失败。
+/// 只配置密码时使用 `AUTH <password>` 认证，同时配置了用户名时使用ACL的 `AUTH <username> <password>`。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct RedisSettings {
+    /// 认证的凭据，没有配置密码时不认证
+    #[serde(flatten)]
+    pub credentials: CredentialSettings,
+    /// 执行的命令，参数以空白分隔
+    #[serde(default = "command_default")]
+    pub command: String,
+    /// 命令回复须匹配的正则表达式，回复为数组时匹配第一个元素
+    pub expect: Option<String>,
+    /// 是否使用TLS连接
+    #[serde(default)]
+    pub tls: bool,
+    /// 是否跳过证书校验，用于自签名证书的服务
+    #[serde(default)]
+    pub insecure: bool,
+}
+
+fn command_default() -> String {
+    String::from("PING") // 默认执行 PING
+}
+
+impl Default for RedisSettings {
+    fn default() -> Self {
+        Self {
+            credentials: CredentialSettings::default(),
+            command: command_default(),
+            expect: None,
+            tls: false,
+            insecure: false,
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod ssh_executor;
+pub mod ssh_host_key;
+pub mod ssh_packet;
+pub mod ssh_ping;
+pub mod ssh_settings;
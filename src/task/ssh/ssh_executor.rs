@@ -0,0 +1,82 @@
+use crate::executor::{ExecReport, Executor};
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::TargetResolver;
+use crate::task::database::db_probe::split_target;
+use crate::task::ssh::ssh_host_key::parse_fingerprint;
+use crate::task::ssh::ssh_ping::SshPing;
+use crate::task::ssh::ssh_settings::SshSettings;
+use async_trait::async_trait;
+use log::trace;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// SSH的默认端口号
+const SSH_PORT: u16 = 22;
+
+#[derive(Clone)]
+pub struct SshExecutor {
+    resolver: TargetResolver,
+    port: u16,
+    ssh_ping: SshPing,
+    /// 配置的主机密钥指纹
+    fingerprint: Option<String>,
+    timeout: Duration,
+}
+
+impl SshExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `host_port` - SSH服务的主机名，端口号可以省略，默认为22
+    /// * `timeout` - 一个 `Duration`，表示超时时间
+    /// * `settings` - SSH任务配置
+    /// * `resolve` - 解析主机名的配置
+    ///
+    /// # Panics
+    /// 配置错误时会触发panic，配置在加载时已经检查过
+    pub fn new(
+        host_port: String,
+        timeout: Duration,
+        settings: SshSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        let (host, port) = split_target(&host_port, SSH_PORT);
+        Self {
+            resolver: TargetResolver::new(&host, resolve),
+            port,
+            ssh_ping: SshPing::new(&settings).unwrap(),
+            fingerprint: settings
+                .fingerprint
+                .as_deref()
+                .map(parse_fingerprint)
+                .transpose()
+                .unwrap(),
+            timeout,
+        }
+    }
+
+    /// 探测一个地址
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        let socket_addr = SocketAddr::new(ip_addr, self.port);
+        trace!("开始执行 SSH 任务: kex {}", socket_addr);
+        let reply = self.ssh_ping.ping(socket_addr, self.timeout).await?;
+        trace!("kex {} {:?}", socket_addr, reply);
+        Ok(reply.into_report(self.fingerprint.as_deref()))
+    }
+}
+
+#[async_trait]
+impl Executor for SshExecutor {
+    fn get_name(&self) -> String {
+        String::from("SSH")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move { executor.exec_addr(ip_addr).await }
+            })
+            .await
+    }
+}
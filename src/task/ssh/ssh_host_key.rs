@@ -0,0 +1,143 @@
+use crate::ping_error::PingError;
+use crate::task::ssh::ssh_packet::SshReader;
+use crate::task::ssh::ssh_settings::SshHostKeyAlgorithm;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::Engine;
+use ring::digest;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+
+/// 指纹的前缀
+const FINGERPRINT_PREFIX: &str = "SHA256:";
+
+impl SshHostKeyAlgorithm {
+    /// 主机密钥的类型，即公钥中的算法名
+    fn key_type(self) -> &'static str {
+        match self {
+            SshHostKeyAlgorithm::Ed25519 => "ssh-ed25519",
+            SshHostKeyAlgorithm::EcdsaP256 => "ecdsa-sha2-nistp256",
+            SshHostKeyAlgorithm::EcdsaP384 => "ecdsa-sha2-nistp384",
+            SshHostKeyAlgorithm::RsaSha512 | SshHostKeyAlgorithm::RsaSha256 => "ssh-rsa",
+        }
+    }
+}
+
+/// 服务器的主机密钥
+#[derive(Debug, Clone)]
+pub struct SshHostKey {
+    /// 公钥的编码，即 `known_hosts` 中base64编码前的内容
+    blob: Vec<u8>,
+}
+
+impl SshHostKey {
+    /// 构造函数
+    /// # 参数
+    /// * `blob` - 密钥交换回复中的主机密钥
+    pub fn new(blob: &[u8]) -> Self {
+        Self {
+            blob: blob.to_vec(),
+        }
+    }
+
+    /// SHA256指纹，与 `ssh-keygen -lf` 的输出相同
+    pub fn fingerprint(&self) -> String {
+        let hash = digest::digest(&digest::SHA256, &self.blob);
+        format!("{}{}", FINGERPRINT_PREFIX, STANDARD_NO_PAD.encode(hash))
+    }
+
+    /// 校验服务器对交换哈希的签名，证明服务器持有主机密钥的私钥
+    ///
+    /// # 参数
+    /// * `algorithm` - 协商的主机密钥算法
+    /// * `message` - 交换哈希
+    /// * `signature` - 密钥交换回复中的签名
+    pub fn verify(
+        &self,
+        algorithm: SshHostKeyAlgorithm,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), PingError> {
+        let mut key = SshReader::new(&self.blob);
+        let key_type = key.string()?;
+        if key_type != algorithm.key_type().as_bytes() {
+            return Err(invalid(&format!(
+                "主机密钥的类型 {} 与协商的算法 {} 不符",
+                String::from_utf8_lossy(key_type),
+                algorithm
+            )));
+        }
+        let mut signature = SshReader::new(signature);
+        let signature_algorithm = signature.string()?;
+        if signature_algorithm != algorithm.to_string().as_bytes() {
+            return Err(invalid(&format!(
+                "签名的算法 {} 与协商的算法 {} 不符",
+                String::from_utf8_lossy(signature_algorithm),
+                algorithm
+            )));
+        }
+        let signature = signature.string()?;
+
+        let result = match algorithm {
+            SshHostKeyAlgorithm::Ed25519 => {
+                UnparsedPublicKey::new(&signature::ED25519, key.string()?)
+                    .verify(message, signature)
+            }
+            SshHostKeyAlgorithm::EcdsaP256 | SshHostKeyAlgorithm::EcdsaP384 => {
+                let (verification, size) = if algorithm == SshHostKeyAlgorithm::EcdsaP256 {
+                    (&signature::ECDSA_P256_SHA256_FIXED, 32)
+                } else {
+                    (&signature::ECDSA_P384_SHA384_FIXED, 48)
+                };
+                // 跳过曲线名
+                key.string()?;
+                let point = key.string()?;
+                // 签名为两个mpint，转换成定长的 r || s
+                let mut parts = SshReader::new(signature);
+                let mut fixed = vec![];
+                for part in [parts.mpint()?, parts.mpint()?] {
+                    if part.len() > size {
+                        return Err(invalid("错误的ECDSA签名"));
+                    }
+                    fixed.resize(fixed.len() + size - part.len(), 0);
+                    fixed.extend_from_slice(part);
+                }
+                UnparsedPublicKey::new(verification, point).verify(message, &fixed)
+            }
+            SshHostKeyAlgorithm::RsaSha512 | SshHostKeyAlgorithm::RsaSha256 => {
+                let e = key.mpint()?;
+                let n = key.mpint()?;
+                let verification = if algorithm == SshHostKeyAlgorithm::RsaSha512 {
+                    &signature::RSA_PKCS1_2048_8192_SHA512
+                } else {
+                    &signature::RSA_PKCS1_2048_8192_SHA256
+                };
+                RsaPublicKeyComponents { n, e }.verify(verification, message, signature)
+            }
+        };
+        result.map_err(|_| invalid("主机密钥的签名校验失败"))
+    }
+}
+
+/// 解析配置的指纹
+///
+/// # 返回值
+/// 统一成 `SHA256:` 开头、不带填充的格式，格式错误时返回错误信息
+pub fn parse_fingerprint(fingerprint: &str) -> Result<String, String> {
+    let encoded = fingerprint.trim();
+    let encoded = encoded
+        .strip_prefix(FINGERPRINT_PREFIX)
+        .unwrap_or(encoded)
+        .trim_end_matches('=');
+    match STANDARD_NO_PAD.decode(encoded) {
+        Ok(hash) if hash.len() == digest::SHA256_OUTPUT_LEN => {
+            Ok(format!("{}{}", FINGERPRINT_PREFIX, encoded))
+        }
+        _ => Err(format!(
+            "fingerprint({})格式错误，应为 ssh-keygen -lf 输出的SHA256指纹",
+            fingerprint
+        )),
+    }
+}
+
+fn invalid(reason: &str) -> PingError {
+    PingError::InvalidReply(reason.to_string())
+}
@@ -0,0 +1,187 @@
+use crate::ping_error::PingError;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// 断开连接
+pub const SSH_MSG_DISCONNECT: u8 = 1;
+/// 忽略的消息
+pub const SSH_MSG_IGNORE: u8 = 2;
+/// 调试消息
+pub const SSH_MSG_DEBUG: u8 = 4;
+/// 密钥交换初始化
+pub const SSH_MSG_KEXINIT: u8 = 20;
+/// 客户端的ECDH公钥(RFC 5656)，curve25519也使用此消息(RFC 8731)
+pub const SSH_MSG_KEX_ECDH_INIT: u8 = 30;
+/// 服务器的ECDH公钥、主机密钥及签名
+pub const SSH_MSG_KEX_ECDH_REPLY: u8 = 31;
+/// 断开连接的原因: 应用主动断开
+pub const SSH_DISCONNECT_BY_APPLICATION: u32 = 11;
+
+/// 二进制包的最大长度，RFC 4253 要求至少支持35000字节
+const MAX_PACKET_SIZE: usize = 35000;
+/// 未加密时的块大小
+const BLOCK_SIZE: usize = 8;
+
+/// 构造消息的负载，也用于构造计算交换哈希的数据
+#[derive(Default)]
+pub struct SshWriter {
+    buf: Vec<u8>,
+}
+
+impl SshWriter {
+    /// 构造消息，第一个字节为消息类型
+    pub fn message(message: u8) -> Self {
+        Self { buf: vec![message] }
+    }
+
+    /// 原样追加字节
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.buf.extend_from_slice(bytes);
+        self
+    }
+
+    /// 布尔值
+    pub fn boolean(&mut self, value: bool) -> &mut Self {
+        self.buf.push(value as u8);
+        self
+    }
+
+    /// 32位无符号整数
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    /// 带长度的字符串
+    pub fn string(&mut self, value: &[u8]) -> &mut Self {
+        self.u32(value.len() as u32).bytes(value)
+    }
+
+    /// 以逗号分隔的名称列表
+    pub fn name_list(&mut self, names: &[&str]) -> &mut Self {
+        self.string(names.join(",").as_bytes())
+    }
+
+    /// 大端无符号整数，编码成mpint
+    pub fn mpint(&mut self, value: &[u8]) -> &mut Self {
+        let value = strip_leading_zeros(value);
+        if value.first().is_some_and(|b| b & 0x80 != 0) {
+            // 最高位为1时补0，避免被当作负数
+            self.u32(value.len() as u32 + 1).bytes(&[0]).bytes(value)
+        } else {
+            self.string(value)
+        }
+    }
+
+    /// 构造完成的负载
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// 解析消息的负载
+pub struct SshReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> SshReader<'a> {
+    /// 构造函数
+    /// # 参数
+    /// * `data` - 消息的负载
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// 读取指定长度的字节
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], PingError> {
+        if self.data.len() < len {
+            return Err(PingError::InvalidReply(String::from("消息长度不足")));
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    /// 读取一个字节
+    pub fn byte(&mut self) -> Result<u8, PingError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// 32位无符号整数
+    pub fn u32(&mut self) -> Result<u32, PingError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// 带长度的字符串
+    pub fn string(&mut self) -> Result<&'a [u8], PingError> {
+        let len = self.u32()? as usize;
+        self.bytes(len)
+    }
+
+    /// 以逗号分隔的名称列表
+    pub fn name_list(&mut self) -> Result<Vec<String>, PingError> {
+        let names = String::from_utf8_lossy(self.string()?);
+        Ok(names
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect())
+    }
+
+    /// mpint，返回去掉前导0的大端无符号整数
+    pub fn mpint(&mut self) -> Result<&'a [u8], PingError> {
+        Ok(strip_leading_zeros(self.string()?))
+    }
+}
+
+/// 去掉大端整数的前导0
+pub fn strip_leading_zeros(value: &[u8]) -> &[u8] {
+    let start = value.iter().position(|b| *b != 0).unwrap_or(value.len());
+    &value[start..]
+}
+
+/// 发送未加密的二进制包
+pub async fn write_packet<S>(stream: &mut S, payload: &[u8]) -> Result<(), PingError>
+where
+    S: AsyncWrite + Unpin,
+{
+    // 长度、填充长度、负载及填充的总长须为块大小的整数倍，填充至少4字节
+    let mut padding = BLOCK_SIZE - (5 + payload.len()) % BLOCK_SIZE;
+    if padding < 4 {
+        padding += BLOCK_SIZE;
+    }
+    let mut packet = Vec::with_capacity(5 + payload.len() + padding);
+    packet.extend_from_slice(&((1 + payload.len() + padding) as u32).to_be_bytes());
+    packet.push(padding as u8);
+    packet.extend_from_slice(payload);
+    packet.resize(packet.len() + padding, 0);
+    stream.write_all(&packet).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// 读取未加密的二进制包
+///
+/// # 返回值
+/// 包的负载，第一个字节为消息类型
+pub async fn read_packet<S>(stream: &mut S) -> Result<Vec<u8>, PingError>
+where
+    S: AsyncRead + Unpin,
+{
+    let len = stream.read_u32().await? as usize;
+    if !(2..=MAX_PACKET_SIZE).contains(&len) {
+        return Err(PingError::InvalidReply(format!("错误的包长度: {}", len)));
+    }
+    let mut packet = vec![0; len];
+    stream.read_exact(&mut packet).await?;
+    let padding = packet[0] as usize;
+    if padding + 2 > len {
+        return Err(PingError::InvalidReply(format!(
+            "错误的填充长度: {}",
+            padding
+        )));
+    }
+    packet.truncate(len - padding);
+    packet.remove(0);
+    Ok(packet)
+}
@@ -0,0 +1,389 @@
+use crate::executor::ExecReport;
+use crate::metrics::metrics_cst::{
+    FINGERPRINT_PROMETHEUS_METRIC_LABEL_NAME, HOST_KEY_ALGORITHM_PROMETHEUS_METRIC_LABEL_NAME,
+    PHASE_PROMETHEUS_METRIC_LABEL_NAME, SOFTWARE_PROMETHEUS_METRIC_LABEL_NAME,
+    SSH_DURATION_PROMETHEUS_METRIC_DESC, SSH_DURATION_PROMETHEUS_METRIC_NAME,
+    SSH_FINGERPRINT_MATCH_PROMETHEUS_METRIC_DESC, SSH_FINGERPRINT_MATCH_PROMETHEUS_METRIC_NAME,
+    SSH_INFO_PROMETHEUS_METRIC_DESC, SSH_INFO_PROMETHEUS_METRIC_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::task::ssh::ssh_host_key::SshHostKey;
+use crate::task::ssh::ssh_packet::{
+    read_packet, write_packet, SshReader, SshWriter, SSH_DISCONNECT_BY_APPLICATION, SSH_MSG_DEBUG,
+    SSH_MSG_DISCONNECT, SSH_MSG_IGNORE, SSH_MSG_KEXINIT, SSH_MSG_KEX_ECDH_INIT,
+    SSH_MSG_KEX_ECDH_REPLY,
+};
+use crate::task::ssh::ssh_settings::{SshHostKeyAlgorithm, SshSettings};
+use crate::task::tcp::tcp_ping::TcpPing;
+use crate::task::tcp::tcp_settings::TcpSettings;
+use log::trace;
+use ring::agreement::{self, EphemeralPrivateKey, UnparsedPublicKey};
+use ring::digest;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+/// 客户端的版本标识
+const CLIENT_IDENTIFICATION: &str = "SSH-2.0-pong-rs";
+/// 版本标识一行的最大长度(RFC 4253)
+const MAX_IDENTIFICATION_SIZE: usize = 255;
+/// 版本标识之前最多允许的其它行数
+const MAX_PRELUDE_LINES: usize = 32;
+/// 支持的密钥交换算法，均使用SHA-256计算交换哈希
+const KEX_ALGORITHMS: [&str; 3] = [
+    "curve25519-sha256",
+    "curve25519-sha256@libssh.org",
+    "ecdh-sha2-nistp256",
+];
+/// 提供的加密算法，只用于让协商通过，取得主机密钥后即断开连接，不会真正使用
+const CIPHERS: [&str; 5] = [
+    "chacha20-poly1305@openssh.com",
+    "aes128-gcm@openssh.com",
+    "aes256-gcm@openssh.com",
+    "aes128-ctr",
+    "aes256-ctr",
+];
+/// 提供的MAC算法，同样不会真正使用
+const MACS: [&str; 4] = [
+    "hmac-sha2-256-etm@openssh.com",
+    "hmac-sha2-256",
+    "hmac-sha2-512",
+    "hmac-sha1",
+];
+
+/// 一次SSH探测的结果
+#[derive(Debug, Clone)]
+pub struct SshReply {
+    /// 建立TCP连接的耗时
+    pub connect: Duration,
+    /// 从连接建立到读到服务器版本标识的耗时
+    pub banner: Duration,
+    /// 密钥交换直到校验完主机密钥签名的耗时
+    pub kex: Duration,
+    /// 服务器的软件版本，如 `OpenSSH_9.6p1`
+    pub software: String,
+    /// 协商的主机密钥算法
+    pub host_key_algorithm: SshHostKeyAlgorithm,
+    /// 主机密钥的SHA256指纹
+    pub fingerprint: String,
+}
+
+impl SshReply {
+    /// 与配置的指纹比较并转换成执行报告，以各阶段的耗时之和作为耗时
+    ///
+    /// # 参数
+    /// * `pinned` - 配置的指纹，已统一格式
+    pub fn into_report(self, pinned: Option<&str>) -> ExecReport {
+        let mut metrics: Vec<ProbeMetric> = [
+            ("connect", self.connect),
+            ("banner", self.banner),
+            ("kex", self.kex),
+        ]
+        .into_iter()
+        .map(|(phase, duration)| {
+            ProbeMetric::new(
+                SSH_DURATION_PROMETHEUS_METRIC_NAME,
+                SSH_DURATION_PROMETHEUS_METRIC_DESC,
+                duration.as_secs_f64() * 1000.0,
+            )
+            .with_label(PHASE_PROMETHEUS_METRIC_LABEL_NAME, phase)
+        })
+        .collect();
+        metrics.push(
+            ProbeMetric::new(
+                SSH_INFO_PROMETHEUS_METRIC_NAME,
+                SSH_INFO_PROMETHEUS_METRIC_DESC,
+                1.0,
+            )
+            .with_label(SOFTWARE_PROMETHEUS_METRIC_LABEL_NAME, &self.software)
+            .with_label(
+                HOST_KEY_ALGORITHM_PROMETHEUS_METRIC_LABEL_NAME,
+                self.host_key_algorithm,
+            )
+            .with_label(FINGERPRINT_PROMETHEUS_METRIC_LABEL_NAME, &self.fingerprint),
+        );
+
+        let mut error = None;
+        if let Some(pinned) = pinned {
+            let matched = pinned == self.fingerprint;
+            metrics.push(ProbeMetric::new(
+                SSH_FINGERPRINT_MATCH_PROMETHEUS_METRIC_NAME,
+                SSH_FINGERPRINT_MATCH_PROMETHEUS_METRIC_DESC,
+                if matched { 1.0 } else { 0.0 },
+            ));
+            if !matched {
+                error = Some(PingError::InvalidReply(format!(
+                    "主机密钥的指纹 {} 与配置的 {} 不符",
+                    self.fingerprint, pinned
+                )));
+            }
+        }
+        ExecReport {
+            elapsed: Some(self.connect + self.banner + self.kex),
+            error,
+            metrics,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SshPing {
+    /// 协商时提供的主机密钥算法
+    host_key_algorithms: Vec<SshHostKeyAlgorithm>,
+}
+
+impl SshPing {
+    /// 构造函数
+    /// # 参数
+    /// * `settings` - SSH任务配置
+    ///
+    /// # 返回值
+    /// 没有配置主机密钥算法时返回错误信息
+    pub fn new(settings: &SshSettings) -> Result<Self, String> {
+        if settings.host_key_algorithms.is_empty() {
+            return Err(String::from("host-key-algorithms不能为空"));
+        }
+        Ok(Self {
+            host_key_algorithms: settings.host_key_algorithms.clone(),
+        })
+    }
+
+    /// 建立连接，交换版本标识并完成密钥交换，取得主机密钥后断开
+    pub async fn ping(
+        &self,
+        socket_addr: SocketAddr,
+        timeout: Duration,
+    ) -> Result<SshReply, PingError> {
+        let start = Instant::now();
+        let (stream, connect) = TcpPing::new(socket_addr, &TcpSettings::default())
+            .connect(timeout)
+            .await?;
+        tokio::time::timeout(
+            timeout.saturating_sub(start.elapsed()),
+            self.exchange(stream, connect),
+        )
+        .await
+        .map_err(|_| PingError::Timeout)?
+    }
+
+    /// 交换版本标识并完成密钥交换(RFC 4253 / RFC 5656 / RFC 8731)
+    async fn exchange(&self, stream: TcpStream, connect: Duration) -> Result<SshReply, PingError> {
+        let mut stream = BufReader::new(stream);
+        let mark = Instant::now();
+        stream
+            .get_mut()
+            .write_all(format!("{}\r\n", CLIENT_IDENTIFICATION).as_bytes())
+            .await?;
+        let server_identification = read_identification(&mut stream).await?;
+        let banner = mark.elapsed();
+        trace!("ssh server identification: {}", server_identification);
+
+        let mark = Instant::now();
+        let client_kexinit = self.kexinit()?;
+        write_packet(stream.get_mut(), &client_kexinit).await?;
+        let server_kexinit = read_message(&mut stream, SSH_MSG_KEXINIT).await?;
+        let (kex_algorithm, host_key_algorithm) = self.negotiate(&server_kexinit)?;
+        trace!("ssh kex {} with {}", kex_algorithm, host_key_algorithm);
+
+        let algorithm = if kex_algorithm.starts_with("curve25519") {
+            &agreement::X25519
+        } else {
+            &agreement::ECDH_P256
+        };
+        let private_key = EphemeralPrivateKey::generate(algorithm, &SystemRandom::new())
+            .map_err(|_| invalid("生成临时密钥失败"))?;
+        let client_public = private_key
+            .compute_public_key()
+            .map_err(|_| invalid("生成临时密钥失败"))?;
+        let mut init = SshWriter::message(SSH_MSG_KEX_ECDH_INIT);
+        init.string(client_public.as_ref());
+        write_packet(stream.get_mut(), &init.into_bytes()).await?;
+
+        let reply = read_message(&mut stream, SSH_MSG_KEX_ECDH_REPLY).await?;
+        let mut reader = SshReader::new(&reply[1..]);
+        let host_key_blob = reader.string()?;
+        let server_public = reader.string()?;
+        let signature = reader.string()?;
+        let shared_secret = agreement::agree_ephemeral(
+            private_key,
+            &UnparsedPublicKey::new(algorithm, server_public),
+            |secret| secret.to_vec(),
+        )
+        .map_err(|_| invalid("服务器的临时公钥无效"))?;
+
+        // 服务器用主机密钥对交换哈希签名，校验通过才能证明主机密钥属于该服务器
+        let mut exchange_hash = SshWriter::default();
+        exchange_hash
+            .string(CLIENT_IDENTIFICATION.as_bytes())
+            .string(server_identification.as_bytes())
+            .string(&client_kexinit)
+            .string(&server_kexinit)
+            .string(host_key_blob)
+            .string(client_public.as_ref())
+            .string(server_public)
+            .mpint(&shared_secret);
+        let exchange_hash = digest::digest(&digest::SHA256, &exchange_hash.into_bytes());
+        let host_key = SshHostKey::new(host_key_blob);
+        host_key.verify(host_key_algorithm, exchange_hash.as_ref(), signature)?;
+        let kex = mark.elapsed();
+
+        let mut disconnect = SshWriter::message(SSH_MSG_DISCONNECT);
+        disconnect
+            .u32(SSH_DISCONNECT_BY_APPLICATION)
+            .string(b"probe finished")
+            .string(b"");
+        let _ = write_packet(stream.get_mut(), &disconnect.into_bytes()).await;
+
+        Ok(SshReply {
+            connect,
+            banner,
+            kex,
+            software: software(&server_identification),
+            host_key_algorithm,
+            fingerprint: host_key.fingerprint(),
+        })
+    }
+
+    /// 客户端的密钥交换初始化消息
+    fn kexinit(&self) -> Result<Vec<u8>, PingError> {
+        let mut cookie = [0; 16];
+        SystemRandom::new()
+            .fill(&mut cookie)
+            .map_err(|_| invalid("生成随机数失败"))?;
+        let host_key_algorithms: Vec<String> = self
+            .host_key_algorithms
+            .iter()
+            .map(|algorithm| algorithm.to_string())
+            .collect();
+        let host_key_algorithms: Vec<&str> =
+            host_key_algorithms.iter().map(String::as_str).collect();
+        let mut kexinit = SshWriter::message(SSH_MSG_KEXINIT);
+        kexinit
+            .bytes(&cookie)
+            .name_list(&KEX_ALGORITHMS)
+            .name_list(&host_key_algorithms)
+            .name_list(&CIPHERS)
+            .name_list(&CIPHERS)
+            .name_list(&MACS)
+            .name_list(&MACS)
+            .name_list(&["none"])
+            .name_list(&["none"])
+            .name_list(&[])
+            .name_list(&[])
+            .boolean(false)
+            .u32(0);
+        Ok(kexinit.into_bytes())
+    }
+
+    /// 按客户端的优先级选出双方都支持的密钥交换算法和主机密钥算法
+    fn negotiate(
+        &self,
+        server_kexinit: &[u8],
+    ) -> Result<(&'static str, SshHostKeyAlgorithm), PingError> {
+        let mut reader = SshReader::new(server_kexinit);
+        // 跳过消息类型和cookie
+        reader.bytes(17)?;
+        let kex_algorithms = reader.name_list()?;
+        let host_key_algorithms = reader.name_list()?;
+        let kex_algorithm = KEX_ALGORITHMS
+            .into_iter()
+            .find(|algorithm| kex_algorithms.iter().any(|a| a == algorithm))
+            .ok_or_else(|| {
+                invalid(&format!(
+                    "没有共同的密钥交换算法，服务器支持: {}",
+                    kex_algorithms.join(",")
+                ))
+            })?;
+        let host_key_algorithm = self
+            .host_key_algorithms
+            .iter()
+            .find(|algorithm| host_key_algorithms.contains(&algorithm.to_string()))
+            .ok_or_else(|| {
+                invalid(&format!(
+                    "没有共同的主机密钥算法，服务器支持: {}",
+                    host_key_algorithms.join(",")
+                ))
+            })?;
+        Ok((kex_algorithm, *host_key_algorithm))
+    }
+}
+
+/// 读取服务器的版本标识，跳过之前的其它行
+async fn read_identification<S>(stream: &mut BufReader<S>) -> Result<String, PingError>
+where
+    S: AsyncRead + Unpin,
+{
+    for _ in 0..=MAX_PRELUDE_LINES {
+        let mut line = vec![];
+        let read = (&mut *stream)
+            .take(MAX_IDENTIFICATION_SIZE as u64)
+            .read_until(b'\n', &mut line)
+            .await?;
+        if read == 0 {
+            return Err(invalid("连接已被关闭"));
+        }
+        if !line.ends_with(b"\n") {
+            return Err(invalid(&format!(
+                "一行超过 {} 字节",
+                MAX_IDENTIFICATION_SIZE
+            )));
+        }
+        let line = String::from_utf8_lossy(&line)
+            .trim_end_matches(['\r', '\n'])
+            .to_string();
+        if line.starts_with("SSH-") {
+            if !line.starts_with("SSH-2.0-") && !line.starts_with("SSH-1.99-") {
+                return Err(invalid(&format!("不支持的协议版本: {}", line)));
+            }
+            return Ok(line);
+        }
+    }
+    Err(invalid("没有读到服务器的版本标识"))
+}
+
+/// 读取指定类型的消息，跳过忽略及调试消息
+async fn read_message<S>(stream: &mut S, expected: u8) -> Result<Vec<u8>, PingError>
+where
+    S: AsyncRead + Unpin,
+{
+    loop {
+        let payload = read_packet(stream).await?;
+        match payload.first().copied() {
+            Some(message) if message == expected => return Ok(payload),
+            Some(SSH_MSG_IGNORE | SSH_MSG_DEBUG) => {}
+            Some(SSH_MSG_DISCONNECT) => {
+                let mut reader = SshReader::new(&payload[1..]);
+                let reason = reader.u32()?;
+                let description = String::from_utf8_lossy(reader.string()?).into_owned();
+                return Err(invalid(&format!(
+                    "服务器断开了连接({}): {}",
+                    reason, description
+                )));
+            }
+            message => {
+                return Err(invalid(&format!(
+                    "期望消息类型 {}，收到 {:?}",
+                    expected, message
+                )));
+            }
+        }
+    }
+}
+
+/// 从版本标识中取出软件版本，`SSH-2.0-OpenSSH_9.6p1 Ubuntu-3` 中的 `OpenSSH_9.6p1`
+fn software(identification: &str) -> String {
+    identification
+        .splitn(3, '-')
+        .nth(2)
+        .and_then(|rest| rest.split_whitespace().next())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn invalid(reason: &str) -> PingError {
+    PingError::InvalidReply(reason.to_string())
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+
+/// SSH任务配置
+///
+/// 连接后读取服务器的版本标识，完成密钥交换直到取得并校验服务器的主机密钥，之后断开连接，不登录。
+/// 配置了指纹时，主机密钥的指纹与配置不符则探测失败。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct SshSettings {
+    /// 主机密钥的SHA256指纹，格式与 `ssh-keygen -lf` 的输出相同，如 `SHA256:uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s`
+    pub fingerprint: Option<String>,
+    /// 协商时提供的主机密钥算法，按优先级排列；服务器有多个主机密钥时，
+    /// 应只配置与指纹对应的算法
+    #[serde(default = "host_key_algorithms_default")]
+    pub host_key_algorithms: Vec<SshHostKeyAlgorithm>,
+}
+
+/// 主机密钥算法
+#[derive(Debug, Serialize, Deserialize, Display, Clone, Copy, PartialEq, Eq)]
+pub enum SshHostKeyAlgorithm {
+    /// Ed25519
+    #[serde(rename = "ssh-ed25519")]
+    #[strum(serialize = "ssh-ed25519")]
+    Ed25519,
+    /// NIST P-256曲线的ECDSA
+    #[serde(rename = "ecdsa-sha2-nistp256")]
+    #[strum(serialize = "ecdsa-sha2-nistp256")]
+    EcdsaP256,
+    /// NIST P-384曲线的ECDSA
+    #[serde(rename = "ecdsa-sha2-nistp384")]
+    #[strum(serialize = "ecdsa-sha2-nistp384")]
+    EcdsaP384,
+    /// RSA，签名使用SHA-512
+    #[serde(rename = "rsa-sha2-512")]
+    #[strum(serialize = "rsa-sha2-512")]
+    RsaSha512,
+    /// RSA，签名使用SHA-256
+    #[serde(rename = "rsa-sha2-256")]
+    #[strum(serialize = "rsa-sha2-256")]
+    RsaSha256,
+}
+
+fn host_key_algorithms_default() -> Vec<SshHostKeyAlgorithm> {
+    vec![
+        SshHostKeyAlgorithm::Ed25519,
+        SshHostKeyAlgorithm::EcdsaP256,
+        SshHostKeyAlgorithm::EcdsaP384,
+        SshHostKeyAlgorithm::RsaSha512,
+        SshHostKeyAlgorithm::RsaSha256,
+    ]
+}
+
+impl Default for SshSettings {
+    fn default() -> Self {
+        Self {
+            fingerprint: None,
+            host_key_algorithms: host_key_algorithms_default(),
+        }
+    }
+}
@@ -1,2 +1,3 @@
 pub mod tcp_executor;
 pub mod tcp_ping;
+pub mod tcp_settings;
@@ -1,15 +1,18 @@
-use crate::executor::Executor;
+use crate::executor::{ExecReport, Executor};
 use crate::ping_error::PingError;
-use crate::task::tcp::tcp_ping::TcpPing;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::{split_host_port, TargetResolver};
+use crate::task::tcp::tcp_ping::{expect_matched_metric, TcpConnectState, TcpPing};
+use crate::task::tcp::tcp_settings::TcpSettings;
 use async_trait::async_trait;
 use log::trace;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
-use wheel_rs::dns_utils::parse_host_port;
+use tokio::time::Instant;
 
 #[derive(Clone)]
 pub struct TcpExecutor {
-    ip_addr: String,
+    resolver: TargetResolver,
     port: u16,
     tcp_ping: TcpPing,
     timeout: Duration,
@@ -19,17 +22,24 @@ impl TcpExecutor {
     /// # 构造函数
     /// ## 参数
     /// * `host_port` - 要ping的主机名及端口号
-    /// * `timeout` - 一个 `Duration`，表示超时时间
-    pub fn new(host_port: String, timeout: Duration) -> Self {
-        // 解析主机的字符串成IP地址和端口号
-        let (ip_addr, port) = parse_host_port(&host_port).unwrap();
-        // 创建SocketAddr对象
-        let socket_addr = SocketAddr::new(ip_addr, port);
-
-        let tcp_ping = TcpPing::new(socket_addr);
+    /// * `timeout` - 一个 `Duration`，表示连接及收发数据的总超时时间
+    /// * `settings` - TCP任务配置
+    /// * `resolve` - 解析主机名的配置
+    ///
+    /// # Panics
+    /// 目标缺少端口号时会触发panic，配置在加载时已经检查过
+    pub fn new(
+        host_port: String,
+        timeout: Duration,
+        settings: TcpSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        // 拆分主机名和端口号，主机名在每次执行时解析
+        let (host, port) = split_host_port(&host_port).unwrap();
+        let tcp_ping = TcpPing::new(SocketAddr::new(IpAddr::from([0, 0, 0, 0]), port), &settings);
 
         Self {
-            ip_addr: ip_addr.to_string(),
+            resolver: TargetResolver::new(&host, resolve),
             port,
             tcp_ping,
             timeout,
@@ -43,11 +53,59 @@ impl Executor for TcpExecutor {
         String::from("TCP")
     }
 
-    async fn exec(&self) -> Result<(), PingError> {
-        trace!(
-            "开始执行 TCP 任务: ping {}",
-            format!("{}:{}", self.ip_addr, self.port)
-        );
-        self.tcp_ping.ping(self.timeout)
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move { executor.exec_addr(ip_addr).await }
+            })
+            .await
+    }
+}
+
+impl TcpExecutor {
+    /// 探测一个地址
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        let socket_addr = SocketAddr::new(ip_addr, self.port);
+        trace!("开始执行 TCP 任务: ping {}", socket_addr);
+        let tcp_ping = self.tcp_ping.with_socket_addr(socket_addr);
+        let start = Instant::now();
+        let result = tcp_ping.connect(self.timeout).await;
+        // 无法归入任何连接状态的错误(如本地资源不足)直接返回
+        let Some(state) = TcpConnectState::of(&result) else {
+            return result.map(|_| ExecReport::default());
+        };
+        trace!("ping {} {}", socket_addr, state);
+        let mut metrics = state.to_metrics();
+        let (stream, connect_elapsed) = match result {
+            Ok(connected) => connected,
+            Err(e) => {
+                return Ok(ExecReport {
+                    elapsed: None,
+                    error: Some(e),
+                    metrics,
+                });
+            }
+        };
+        if !tcp_ping.has_exchange() {
+            return Ok(ExecReport {
+                elapsed: Some(connect_elapsed),
+                error: None,
+                metrics,
+            });
+        }
+
+        // 收发数据时以整个过程的耗时作为耗时
+        let result = tcp_ping
+            .exchange(stream, self.timeout.saturating_sub(start.elapsed()))
+            .await;
+        if tcp_ping.has_expect() {
+            metrics.push(expect_matched_metric(result.is_ok()));
+        }
+        Ok(ExecReport {
+            elapsed: result.is_ok().then(|| start.elapsed()),
+            error: result.err(),
+            metrics,
+        })
     }
 }
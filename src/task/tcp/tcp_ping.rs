@@ -1,23 +1,201 @@
+use crate::metrics::metrics_cst::{
+    STATE_PROMETHEUS_METRIC_LABEL_NAME, TCP_CONNECT_STATE_PROMETHEUS_METRIC_DESC,
+    TCP_CONNECT_STATE_PROMETHEUS_METRIC_NAME, TCP_EXPECT_MATCHED_PROMETHEUS_METRIC_DESC,
+    TCP_EXPECT_MATCHED_PROMETHEUS_METRIC_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
 use crate::ping_error::PingError;
+use crate::task::tcp::tcp_settings::TcpSettings;
 use log::trace;
-use std::net::{SocketAddr, TcpStream};
+use regex::bytes::Regex;
+use std::io;
+use std::net::SocketAddr;
 use std::time::Duration;
+use strum_macros::Display;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+/// 错误信息中最多展示的回复字节数
+const REPLY_PREVIEW_SIZE: usize = 64;
+
+/// TCP连接的结果
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum TcpConnectState {
+    /// 连接成功
+    Connected,
+    /// 目标回复RST，端口没有监听
+    Refused,
+    /// 超时未收到回复，通常是被防火墙丢弃
+    Timeout,
+    /// 主机或网络不可达
+    Unreachable,
+}
+
+impl TcpConnectState {
+    /// 所有的状态
+    const ALL: [TcpConnectState; 4] = [
+        TcpConnectState::Connected,
+        TcpConnectState::Refused,
+        TcpConnectState::Timeout,
+        TcpConnectState::Unreachable,
+    ];
+
+    /// 根据连接的结果判断连接状态，不属于任何一种状态的错误返回None
+    pub fn of<T>(result: &Result<T, PingError>) -> Option<Self> {
+        match result {
+            Ok(_) => Some(TcpConnectState::Connected),
+            Err(PingError::ConnectionRefused) => Some(TcpConnectState::Refused),
+            Err(PingError::Timeout) => Some(TcpConnectState::Timeout),
+            Err(PingError::Unreachable(_)) => Some(TcpConnectState::Unreachable),
+            Err(_) => None,
+        }
+    }
+
+    /// 转换成探测指标，当前状态为1，其余状态为0
+    pub fn to_metrics(self) -> Vec<ProbeMetric> {
+        Self::ALL
+            .iter()
+            .map(|state| {
+                ProbeMetric::new(
+                    TCP_CONNECT_STATE_PROMETHEUS_METRIC_NAME,
+                    TCP_CONNECT_STATE_PROMETHEUS_METRIC_DESC,
+                    if *state == self { 1.0 } else { 0.0 },
+                )
+                .with_label(STATE_PROMETHEUS_METRIC_LABEL_NAME, state)
+            })
+            .collect()
+    }
+}
+
+/// 期望回复是否匹配的探测指标
+pub fn expect_matched_metric(matched: bool) -> ProbeMetric {
+    ProbeMetric::new(
+        TCP_EXPECT_MATCHED_PROMETHEUS_METRIC_NAME,
+        TCP_EXPECT_MATCHED_PROMETHEUS_METRIC_DESC,
+        if matched { 1.0 } else { 0.0 },
+    )
+}
 
 #[derive(Clone)]
 pub struct TcpPing {
     socket_addr: SocketAddr,
+    /// 连接后发送的数据
+    send: Option<Vec<u8>>,
+    /// 期望回复匹配的正则表达式
+    expect: Option<Regex>,
+    /// 最多读取的字节数
+    max_read: usize,
 }
 impl TcpPing {
-    pub fn new(socket_addr: SocketAddr) -> Self {
-        TcpPing { socket_addr }
+    /// 构造函数
+    ///
+    /// # Panics
+    /// 发送的数据或期望回复的正则表达式格式错误时会触发panic，配置在加载时已经检查过
+    pub fn new(socket_addr: SocketAddr, settings: &TcpSettings) -> Self {
+        TcpPing {
+            socket_addr,
+            send: settings.send_bytes().unwrap(),
+            expect: settings.expect_regex().unwrap(),
+            max_read: settings.max_read,
+        }
     }
 
-    pub fn ping(&self, timeout: Duration) -> Result<(), PingError> {
+    /// 以相同的配置探测另一个地址
+    pub fn with_socket_addr(&self, socket_addr: SocketAddr) -> Self {
+        TcpPing {
+            socket_addr,
+            ..self.clone()
+        }
+    }
+
+    /// 是否需要在连接后收发数据
+    pub fn has_exchange(&self) -> bool {
+        self.send.is_some() || self.expect.is_some()
+    }
+
+    /// 是否配置了期望的回复
+    pub fn has_expect(&self) -> bool {
+        self.expect.is_some()
+    }
+
+    /// 异步连接目标
+    ///
+    /// # 返回值
+    /// 成功时返回连接及建立连接的耗时，目标拒绝连接、超时和不可达分别返回
+    /// `ConnectionRefused`、`Timeout` 和 `Unreachable` 错误
+    pub async fn connect(&self, timeout: Duration) -> Result<(TcpStream, Duration), PingError> {
         trace!("ping {} ....", self.socket_addr);
-        // 连接成功后断开
-        TcpStream::connect_timeout(&self.socket_addr, timeout)?
-            .shutdown(std::net::Shutdown::Both)?;
-        trace!("ping {} success", self.socket_addr);
-        Ok(())
+        let start = Instant::now();
+        let stream = tokio::time::timeout(timeout, TcpStream::connect(self.socket_addr))
+            .await
+            .map_err(|_| PingError::Timeout)?
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::ConnectionRefused => PingError::ConnectionRefused,
+                io::ErrorKind::TimedOut => PingError::Timeout,
+                io::ErrorKind::HostUnreachable | io::ErrorKind::NetworkUnreachable => {
+                    PingError::Unreachable(e)
+                }
+                _ => PingError::Io(e),
+            })?;
+        let elapsed = start.elapsed();
+        trace!("ping {} connected in {:?}", self.socket_addr, elapsed);
+        Ok((stream, elapsed))
+    }
+
+    /// 发送配置的数据，再读取回复直到匹配期望的正则表达式
+    ///
+    /// # 参数
+    /// * `stream` - 已建立的连接，结束后断开
+    /// * `timeout` - 收发数据的超时时间
+    ///
+    /// # 返回值
+    /// 超时、连接被关闭或读满 `max_read` 个字节仍未匹配时返回 `InvalidReply` 错误
+    pub async fn exchange(
+        &self,
+        mut stream: TcpStream,
+        timeout: Duration,
+    ) -> Result<(), PingError> {
+        let mut buf = Vec::with_capacity(self.max_read);
+        let result = tokio::time::timeout(timeout, async {
+            if let Some(send) = &self.send {
+                stream.write_all(send).await?;
+                stream.flush().await?;
+            }
+            let Some(expect) = &self.expect else {
+                return Ok(());
+            };
+            let mut chunk = [0; 1024];
+            while buf.len() < self.max_read {
+                let size = (self.max_read - buf.len()).min(chunk.len());
+                let read = stream.read(&mut chunk[..size]).await?;
+                if read == 0 {
+                    return Err(self.unexpected_reply("连接已被关闭", &buf));
+                }
+                buf.extend_from_slice(&chunk[..read]);
+                if expect.is_match(&buf) {
+                    trace!("ping {} reply matched", self.socket_addr);
+                    return Ok(());
+                }
+            }
+            Err(self.unexpected_reply("已读满最大字节数", &buf))
+        })
+        .await;
+        match result {
+            Ok(result) => result,
+            Err(_) => Err(self.unexpected_reply("等待回复超时", &buf)),
+        }
+    }
+
+    /// 构造回复不符合期望的错误
+    fn unexpected_reply(&self, reason: &str, buf: &[u8]) -> PingError {
+        let preview = String::from_utf8_lossy(&buf[..buf.len().min(REPLY_PREVIEW_SIZE)]);
+        PingError::InvalidReply(format!(
+            "{}，回复未匹配 /{}/: {:?}",
+            reason,
+            self.expect.as_ref().map_or("", |expect| expect.as_str()),
+            preview
+        ))
     }
 }
@@ -0,0 +1,156 @@
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+
+/// TCP任务配置
+///
+/// 默认只检查端口能否建立连接。配置了 `send` 或 `expect` 时，连接后先发送数据，
+/// 再读取回复直到匹配 `expect` 的正则表达式，读到 `max-read` 个字节或超时都算失败，
+/// 可以用来检查 SSH、SMTP、FTP 的欢迎信息或 Redis 的 `PING`/`+PONG` 等基于文本行的协议。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct TcpSettings {
+    /// 连接后发送的数据，支持 `\r`、`\n`、`\t`、`\0`、`\\` 和 `\xHH` 转义
+    pub send: Option<String>,
+    /// 连接后发送的数据的十六进制表示，如 `50494e470d0a`，与 `send` 二选一
+    pub send_hex: Option<String>,
+    /// 期望回复匹配的正则表达式，按字节匹配，不要求回复是合法的UTF-8
+    pub expect: Option<String>,
+    /// 最多读取的字节数
+    #[serde(default = "max_read_default")]
+    pub max_read: usize,
+}
+
+fn max_read_default() -> usize {
+    4096 // 默认最多读取 4KB
+}
+
+impl TcpSettings {
+    /// 获取要发送的数据
+    ///
+    /// # 返回值
+    /// 未配置时返回Ok(None)，转义或十六进制格式错误时返回错误信息
+    pub fn send_bytes(&self) -> Result<Option<Vec<u8>>, String> {
+        match (&self.send, &self.send_hex) {
+            (Some(_), Some(_)) => Err(String::from("send和send-hex不能同时配置")),
+            (Some(text), None) => unescape(text).map(Some),
+            (None, Some(hex)) => decode_hex(hex).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// 编译期望回复匹配的正则表达式
+    pub fn expect_regex(&self) -> Result<Option<Regex>, String> {
+        self.expect
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("expect不是合法的正则表达式: {}", e))
+    }
+}
+
+/// 解析转义后的文本
+pub fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('r') => bytes.push(b'\r'),
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some('0') => bytes.push(0),
+            Some('\\') => bytes.push(b'\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                bytes.extend(decode_hex(&hex).map_err(|_| format!("错误的转义: \\x{}", hex))?);
+            }
+            Some(c) => return Err(format!("不支持的转义: \\{}", c)),
+            None => return Err(String::from("转义字符\\不能出现在末尾")),
+        }
+    }
+    Ok(bytes)
+}
+
+/// 解析十六进制字符串，忽略其中的空白
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = hex.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    // from_str_radix 接受 `+` 号，需要先检查每个字符
+    if digits.is_empty()
+        || !digits.len().is_multiple_of(2)
+        || !digits.iter().all(u8::is_ascii_hexdigit)
+    {
+        return Err(format!("错误的十六进制数据: {}", hex));
+    }
+    Ok(digits
+        .chunks(2)
+        .map(|pair| (hex_value(pair[0]) << 4) | hex_value(pair[1]))
+        .collect())
+}
+
+/// 单个十六进制字符的值，调用前已检查过是十六进制字符
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        _ => digit - b'A' + 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_sequences() {
+        assert_eq!(unescape(r"PING\r\n").unwrap(), b"PING\r\n");
+        assert_eq!(unescape("a\\tb\\0").unwrap(), b"a\tb\0");
+        assert_eq!(unescape(r"\\").unwrap(), b"\\");
+        assert_eq!(unescape(r"\x00\x7F\xff").unwrap(), [0x00, 0x7f, 0xff]);
+        // 非ASCII字符按UTF-8编码
+        assert_eq!(unescape("é").unwrap(), "é".as_bytes());
+        assert_eq!(unescape("").unwrap(), b"");
+    }
+
+    #[test]
+    fn invalid_escapes() {
+        assert_eq!(
+            unescape(r"PING\"),
+            Err(String::from("转义字符\\不能出现在末尾"))
+        );
+        assert_eq!(unescape(r"\q"), Err(String::from("不支持的转义: \\q")));
+        for (text, hex) in [(r"\x1", "1"), (r"\x", ""), (r"\xg0", "g0"), (r"\x+1", "+1")] {
+            assert_eq!(unescape(text), Err(format!("错误的转义: \\x{}", hex)));
+        }
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(decode_hex("50494e470d0a").unwrap(), b"PING\r\n");
+        assert_eq!(
+            decode_hex("DE ad\nBE\tef").unwrap(),
+            [0xde, 0xad, 0xbe, 0xef]
+        );
+    }
+
+    #[test]
+    fn invalid_hex() {
+        for hex in ["", "  ", "abc", "0g", "+f", "-1", "0x00", "é0"] {
+            assert_eq!(decode_hex(hex), Err(format!("错误的十六进制数据: {}", hex)));
+        }
+    }
+
+    #[test]
+    fn send_and_send_hex_are_exclusive() {
+        let settings = TcpSettings {
+            send: Some(String::from("a")),
+            send_hex: Some(String::from("61")),
+            ..Default::default()
+        };
+        assert!(settings.send_bytes().is_err());
+        assert_eq!(TcpSettings::default().send_bytes(), Ok(None));
+    }
+}
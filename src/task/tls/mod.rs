@@ -0,0 +1,4 @@
+pub mod tls_executor;
+pub mod tls_handshake;
+pub mod tls_ping;
+pub mod tls_settings;
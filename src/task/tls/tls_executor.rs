@@ -0,0 +1,79 @@
+use crate::executor::{ExecReport, Executor};
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::{split_host_port, TargetResolver};
+use crate::task::tls::tls_ping::TlsPing;
+use crate::task::tls::tls_settings::TlsSettings;
+use async_trait::async_trait;
+use log::{debug, trace};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct TlsExecutor {
+    resolver: TargetResolver,
+    port: u16,
+    tls_ping: TlsPing,
+    timeout: Duration,
+}
+
+impl TlsExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `host_port` - 要握手的主机名及端口号
+    /// * `timeout` - 一个 `Duration`，表示连接及握手的总超时时间
+    /// * `settings` - TLS任务配置
+    /// * `resolve` - 解析主机名的配置
+    ///
+    /// # Panics
+    /// 目标缺少端口号或SNI错误时会触发panic，配置在加载时已经检查过
+    pub fn new(
+        host_port: String,
+        timeout: Duration,
+        settings: TlsSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        let (host, port) = split_host_port(&host_port).unwrap();
+        Self {
+            tls_ping: TlsPing::new(&host, &settings).unwrap(),
+            resolver: TargetResolver::new(&host, resolve),
+            port,
+            timeout,
+        }
+    }
+
+    /// 探测一个地址，以建立连接和握手的总耗时作为耗时，证书链校验失败时报告失败原因
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        let socket_addr = SocketAddr::new(ip_addr, self.port);
+        trace!("开始执行 TLS 任务: handshake {}", socket_addr);
+        let handshake = self.tls_ping.ping(socket_addr, self.timeout).await?;
+        debug!(
+            "TLS {} {} {} 证书: {:?}",
+            socket_addr, handshake.version, handshake.cipher, handshake.leaf
+        );
+        Ok(ExecReport {
+            elapsed: Some(handshake.connect + handshake.handshake),
+            error: handshake
+                .chain_error
+                .as_ref()
+                .map(|e| PingError::Tls(format!("证书链校验失败: {}", e))),
+            metrics: handshake.to_metrics(),
+        })
+    }
+}
+
+#[async_trait]
+impl Executor for TlsExecutor {
+    fn get_name(&self) -> String {
+        String::from("TLS")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move { executor.exec_addr(ip_addr).await }
+            })
+            .await
+    }
+}
@@ -0,0 +1,102 @@
+use crate::metrics::metrics_cst::{
+    CIPHER_PROMETHEUS_METRIC_LABEL_NAME, ISSUER_PROMETHEUS_METRIC_LABEL_NAME,
+    SUBJECT_PROMETHEUS_METRIC_LABEL_NAME, TLS_CERT_EXPIRY_PROMETHEUS_METRIC_DESC,
+    TLS_CERT_EXPIRY_PROMETHEUS_METRIC_NAME, TLS_CERT_INFO_PROMETHEUS_METRIC_DESC,
+    TLS_CERT_INFO_PROMETHEUS_METRIC_NAME, TLS_CHAIN_VALID_PROMETHEUS_METRIC_DESC,
+    TLS_CHAIN_VALID_PROMETHEUS_METRIC_NAME, TLS_HANDSHAKE_PROMETHEUS_METRIC_DESC,
+    TLS_HANDSHAKE_PROMETHEUS_METRIC_NAME, TLS_INFO_PROMETHEUS_METRIC_DESC,
+    TLS_INFO_PROMETHEUS_METRIC_NAME, VERSION_PROMETHEUS_METRIC_LABEL_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// 叶子证书的信息
+#[derive(Debug, Clone)]
+pub struct LeafCertificate {
+    /// 证书的主体
+    pub subject: String,
+    /// 证书的颁发者
+    pub issuer: String,
+    /// 证书的过期时间(Unix时间戳，秒)
+    pub not_after: i64,
+}
+
+impl LeafCertificate {
+    /// 解析DER编码的证书，格式错误时返回None
+    pub fn parse(der: &[u8]) -> Option<Self> {
+        let (_, cert) = X509Certificate::from_der(der).ok()?;
+        Some(Self {
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            not_after: cert.validity().not_after.timestamp(),
+        })
+    }
+
+    /// 距离证书过期的秒数，已经过期时为负数
+    pub fn seconds_to_expiry(&self) -> i64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs() as i64);
+        self.not_after - now
+    }
+}
+
+/// 一次TLS握手的结果
+#[derive(Debug, Clone)]
+pub struct TlsHandshake {
+    /// 建立TCP连接的耗时
+    pub connect: Duration,
+    /// TLS握手的耗时，不含建立TCP连接
+    pub handshake: Duration,
+    /// 协商的协议版本，如 `TLSv1_3`
+    pub version: String,
+    /// 协商的密码套件，如 `TLS13_AES_256_GCM_SHA384`
+    pub cipher: String,
+    /// 证书链校验失败的原因，校验通过时为None
+    pub chain_error: Option<String>,
+    /// 叶子证书
+    pub leaf: Option<LeafCertificate>,
+}
+
+impl TlsHandshake {
+    /// 转换成探测指标
+    pub fn to_metrics(&self) -> Vec<ProbeMetric> {
+        let mut metrics = vec![
+            ProbeMetric::new(
+                TLS_HANDSHAKE_PROMETHEUS_METRIC_NAME,
+                TLS_HANDSHAKE_PROMETHEUS_METRIC_DESC,
+                self.handshake.as_secs_f64() * 1000.0,
+            ),
+            ProbeMetric::new(
+                TLS_INFO_PROMETHEUS_METRIC_NAME,
+                TLS_INFO_PROMETHEUS_METRIC_DESC,
+                1.0,
+            )
+            .with_label(VERSION_PROMETHEUS_METRIC_LABEL_NAME, &self.version)
+            .with_label(CIPHER_PROMETHEUS_METRIC_LABEL_NAME, &self.cipher),
+            ProbeMetric::new(
+                TLS_CHAIN_VALID_PROMETHEUS_METRIC_NAME,
+                TLS_CHAIN_VALID_PROMETHEUS_METRIC_DESC,
+                if self.chain_error.is_none() { 1.0 } else { 0.0 },
+            ),
+        ];
+        if let Some(leaf) = &self.leaf {
+            metrics.push(
+                ProbeMetric::new(
+                    TLS_CERT_INFO_PROMETHEUS_METRIC_NAME,
+                    TLS_CERT_INFO_PROMETHEUS_METRIC_DESC,
+                    1.0,
+                )
+                .with_label(SUBJECT_PROMETHEUS_METRIC_LABEL_NAME, &leaf.subject)
+                .with_label(ISSUER_PROMETHEUS_METRIC_LABEL_NAME, &leaf.issuer),
+            );
+            metrics.push(ProbeMetric::new(
+                TLS_CERT_EXPIRY_PROMETHEUS_METRIC_NAME,
+                TLS_CERT_EXPIRY_PROMETHEUS_METRIC_DESC,
+                leaf.seconds_to_expiry() as f64,
+            ));
+        }
+        metrics
+    }
+}
@@ -0,0 +1,174 @@
+use crate::ping_error::PingError;
+use crate::task::tcp::tcp_ping::TcpPing;
+use crate::task::tcp::tcp_settings::TcpSettings;
+use crate::task::tls::tls_handshake::{LeafCertificate, TlsHandshake};
+use crate::task::tls::tls_settings::TlsSettings;
+use log::trace;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{ring, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_rustls::TlsConnector;
+
+/// 记录证书链校验结果的校验器
+///
+/// 证书链的校验委托给 webpki，但无论校验是否通过都继续握手，
+/// 这样证书过期或不受信任时仍然能拿到协商结果和证书的信息。
+/// 握手消息的签名仍然严格校验。
+#[derive(Debug)]
+struct RecordingVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    /// 证书链校验失败的原因，校验通过时为None
+    chain_error: Mutex<Option<String>>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let result = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        );
+        *self.chain_error.lock().unwrap() = result.err().map(|e| e.to_string());
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[derive(Clone)]
+pub struct TlsPing {
+    server_name: ServerName<'static>,
+    provider: Arc<CryptoProvider>,
+    roots: Arc<RootCertStore>,
+}
+
+impl TlsPing {
+    /// 构造函数
+    /// # 参数
+    /// * `host` - 目标的主机名，未配置SNI时作为握手时发送的SNI，也用于校验证书
+    /// * `settings` - TLS任务配置
+    ///
+    /// # 返回值
+    /// SNI不是合法的主机名或IP地址时返回错误信息
+    pub fn new(host: &str, settings: &TlsSettings) -> Result<Self, String> {
+        let sni = settings
+            .sni
+            .as_deref()
+            .unwrap_or(host.trim_start_matches('[').trim_end_matches(']'));
+        let server_name = ServerName::try_from(sni.to_string())
+            .map_err(|e| format!("错误的SNI({}): {}", sni, e))?;
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        Ok(Self {
+            server_name,
+            provider: Arc::new(ring::default_provider()),
+            roots: Arc::new(roots),
+        })
+    }
+
+    /// 连接目标的一个地址并完成TLS握手，握手完成后断开
+    ///
+    /// # 返回值
+    /// 证书链校验失败不会返回错误，失败原因记录在结果中
+    pub async fn ping(
+        &self,
+        socket_addr: SocketAddr,
+        timeout: Duration,
+    ) -> Result<TlsHandshake, PingError> {
+        trace!(
+            "tls handshake with {:?} via {} ....",
+            self.server_name,
+            socket_addr
+        );
+        let start = Instant::now();
+        let (stream, connect) = TcpPing::new(socket_addr, &TcpSettings::default())
+            .connect(timeout)
+            .await?;
+
+        // 每次握手使用新的校验器，以便取回本次的校验结果
+        let verifier = Arc::new(RecordingVerifier {
+            inner: WebPkiServerVerifier::builder_with_provider(
+                self.roots.clone(),
+                self.provider.clone(),
+            )
+            .build()
+            .map_err(|e| PingError::Tls(e.to_string()))?,
+            chain_error: Mutex::new(None),
+        });
+        let config = ClientConfig::builder_with_provider(self.provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| PingError::Tls(e.to_string()))?
+            .dangerous()
+            .with_custom_certificate_verifier(verifier.clone())
+            .with_no_client_auth();
+
+        let handshake_start = Instant::now();
+        let stream = tokio::time::timeout(
+            timeout.saturating_sub(start.elapsed()),
+            TlsConnector::from(Arc::new(config)).connect(self.server_name.clone(), stream),
+        )
+        .await
+        .map_err(|_| PingError::Timeout)?
+        .map_err(|e| PingError::Tls(e.to_string()))?;
+        let handshake = handshake_start.elapsed();
+
+        let (_, connection) = stream.get_ref();
+        let result = TlsHandshake {
+            connect,
+            handshake,
+            version: connection
+                .protocol_version()
+                .map_or_else(String::new, |version| format!("{:?}", version)),
+            cipher: connection
+                .negotiated_cipher_suite()
+                .map_or_else(String::new, |suite| format!("{:?}", suite.suite())),
+            chain_error: verifier.chain_error.lock().unwrap().take(),
+            leaf: connection
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .and_then(|cert| LeafCertificate::parse(cert)),
+        };
+        trace!(
+            "tls handshake with {:?} success in {:?}",
+            self.server_name,
+            handshake
+        );
+        Ok(result)
+    }
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// TLS任务配置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct TlsSettings {
+    /// 握手时发送的SNI，默认为目标的主机名，目标是IP地址时按IP地址校验证书
+    pub sni: Option<String>,
+}
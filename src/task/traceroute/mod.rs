@@ -0,0 +1,5 @@
+pub mod traceroute_executor;
+pub mod traceroute_hop;
+pub mod traceroute_path;
+pub mod traceroute_ping;
+pub mod traceroute_settings;
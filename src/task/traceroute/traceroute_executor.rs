@@ -0,0 +1,143 @@
+use crate::executor::{ExecReport, Executor};
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::TargetResolver;
+use crate::task::traceroute::traceroute_hop::{hops_to_metrics, Hop, HopReplyKind};
+use crate::task::traceroute::traceroute_path::{group_paths, paths_to_metrics};
+use crate::task::traceroute::traceroute_ping::TraceroutePing;
+use crate::task::traceroute::traceroute_settings::TracerouteSettings;
+use async_trait::async_trait;
+use log::{debug, trace};
+use std::net::IpAddr;
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct TracerouteExecutor {
+    resolver: TargetResolver,
+    timeout: Duration,
+    multipath: bool,
+    traceroute_ping: TraceroutePing,
+}
+
+impl TracerouteExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `host` - 要追踪的主机名或 IP 地址
+    /// * `timeout` - 一个 `Duration`，表示每个探测包等待回复的超时时间，整次追踪的期限由它推算
+    /// * `settings` - 路由追踪任务配置
+    /// * `resolve` - 解析主机名的配置
+    pub fn new(
+        host: String,
+        timeout: Duration,
+        settings: TracerouteSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        Self {
+            resolver: TargetResolver::new(&host, resolve),
+            timeout,
+            multipath: settings.multipath,
+            traceroute_ping: TraceroutePing::new(settings),
+        }
+    }
+}
+
+#[async_trait]
+impl Executor for TracerouteExecutor {
+    fn get_name(&self) -> String {
+        String::from("TRACEROUTE")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move {
+                    if executor.multipath {
+                        executor.exec_multipath(ip_addr).await
+                    } else {
+                        executor.exec_addr(ip_addr).await
+                    }
+                }
+            })
+            .await
+    }
+}
+
+impl TracerouteExecutor {
+    /// 追踪到一个地址的路径
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        trace!("开始执行 TRACEROUTE 任务: traceroute {}", ip_addr);
+        let hops = self.traceroute_ping.trace(ip_addr, self.timeout).await?;
+        for hop in hops.iter() {
+            debug!("traceroute {} {}", ip_addr, hop);
+        }
+
+        // 到达目标时以最后一跳的平均RTT作为耗时，否则指出路径中断的位置
+        let last_hop = hops.last().filter(|hop| hop.is_reached());
+        Ok(ExecReport {
+            elapsed: last_hop.and_then(|hop| hop.stats().avg),
+            error: last_hop.is_none().then(|| Self::path_error(&hops)),
+            metrics: hops_to_metrics(&hops),
+        })
+    }
+
+    /// 以多路径(Paris)模式追踪到一个地址的路径，按路径导出指标
+    async fn exec_multipath(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        trace!("开始执行 TRACEROUTE 任务: paris traceroute {}", ip_addr);
+        let flows = self
+            .traceroute_ping
+            .trace_flows(ip_addr, self.timeout)
+            .await?;
+        let paths = group_paths(flows);
+        for path in paths.iter() {
+            debug!(
+                "traceroute {} 路径{} (流: {:?})",
+                ip_addr, path.id, path.flows
+            );
+            for hop in path.hops.iter() {
+                debug!("traceroute {} {}", ip_addr, hop);
+            }
+        }
+
+        // 以到达目标的各条路径最后一跳的平均RTT的均值作为耗时，没有路径到达目标时报告第一条路径的错误
+        let rtts: Vec<Duration> = paths
+            .iter()
+            .filter(|path| path.is_reached())
+            .filter_map(|path| path.hops.last()?.stats().avg)
+            .collect();
+        let elapsed = (!rtts.is_empty()).then(|| rtts.iter().sum::<Duration>() / rtts.len() as u32);
+        let error = match elapsed {
+            Some(_) => None,
+            None => Some(
+                paths
+                    .first()
+                    .map_or(PingError::Timeout, |path| Self::path_error(&path.hops)),
+            ),
+        };
+        Ok(ExecReport {
+            elapsed,
+            error,
+            metrics: paths_to_metrics(&paths),
+        })
+    }
+
+    /// 未到达目标的路径的错误，最后一跳回复了不可达时报告不可达，否则报告超时
+    fn path_error(hops: &[Hop]) -> PingError {
+        hops.last()
+            .and_then(|hop| {
+                hop.replies
+                    .iter()
+                    .flatten()
+                    .find_map(|reply| match reply.kind {
+                        HopReplyKind::Unreachable(code) => {
+                            Some(PingError::DestinationUnreachable {
+                                router: reply.responder,
+                                code,
+                            })
+                        }
+                        _ => None,
+                    })
+            })
+            .unwrap_or(PingError::Timeout)
+    }
+}
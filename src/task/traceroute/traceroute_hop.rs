@@ -0,0 +1,224 @@
+use crate::metrics::metrics_cst::{
+    HOP_ADDR_PROMETHEUS_METRIC_LABEL_NAME, HOP_PROMETHEUS_METRIC_LABEL_NAME,
+    PATH_PROMETHEUS_METRIC_LABEL_NAME, STAT_PROMETHEUS_METRIC_LABEL_NAME,
+    TRACEROUTE_HOPS_PROMETHEUS_METRIC_DESC, TRACEROUTE_HOPS_PROMETHEUS_METRIC_NAME,
+    TRACEROUTE_HOP_LOSS_PROMETHEUS_METRIC_DESC, TRACEROUTE_HOP_LOSS_PROMETHEUS_METRIC_NAME,
+    TRACEROUTE_HOP_RTT_PROMETHEUS_METRIC_DESC, TRACEROUTE_HOP_RTT_PROMETHEUS_METRIC_NAME,
+    TRACEROUTE_PATH_HOP_LOSS_PROMETHEUS_METRIC_DESC,
+    TRACEROUTE_PATH_HOP_LOSS_PROMETHEUS_METRIC_NAME,
+    TRACEROUTE_PATH_HOP_RTT_PROMETHEUS_METRIC_DESC, TRACEROUTE_PATH_HOP_RTT_PROMETHEUS_METRIC_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::task::icmp::icmp_stats::IcmpStats;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// 单个探测包收到的回复
+#[derive(Debug, Clone)]
+pub struct HopReply {
+    /// 回复者的地址，中间路由器或目标
+    pub responder: IpAddr,
+    /// 往返时间
+    pub rtt: Duration,
+    /// 回复的类型
+    pub kind: HopReplyKind,
+}
+
+/// 回复的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HopReplyKind {
+    /// TTL耗尽，中间路由器的回复
+    TimeExceeded,
+    /// 到达了目标
+    Reached,
+    /// 目标不可达，路径在此中断
+    Unreachable(u8),
+}
+
+/// 某一跳的探测结果
+#[derive(Debug, Clone)]
+pub struct Hop {
+    /// 跳数(TTL)
+    pub ttl: u8,
+    /// 这一跳发出的每个探测包的回复，未收到回复的为None
+    pub replies: Vec<Option<HopReply>>,
+}
+
+impl Hop {
+    /// 回复者的地址，取第一个回复的地址
+    pub fn responder(&self) -> Option<IpAddr> {
+        self.replies.iter().flatten().map(|r| r.responder).next()
+    }
+
+    /// 路径是否在这一跳结束(到达目标或目标不可达)
+    pub fn is_last(&self) -> bool {
+        self.replies
+            .iter()
+            .flatten()
+            .any(|r| r.kind != HopReplyKind::TimeExceeded)
+    }
+
+    /// 这一跳是否到达了目标
+    pub fn is_reached(&self) -> bool {
+        self.replies
+            .iter()
+            .flatten()
+            .any(|r| r.kind == HopReplyKind::Reached)
+    }
+
+    /// 统计这一跳的丢包率和RTT
+    pub fn stats(&self) -> IcmpStats {
+        let rtts: Vec<Duration> = self.replies.iter().flatten().map(|r| r.rtt).collect();
+        IcmpStats::new(self.replies.len() as u16, &rtts)
+    }
+
+    /// 转换成探测指标，未回复的跳的地址为`*`
+    /// # 参数
+    /// * `path` - 多路径模式下这一跳所属路径的编号，指标会带上`path`标签
+    pub fn to_metrics(&self, path: Option<u16>) -> Vec<ProbeMetric> {
+        let (loss_name, loss_desc, rtt_name, rtt_desc) = match path {
+            None => (
+                TRACEROUTE_HOP_LOSS_PROMETHEUS_METRIC_NAME,
+                TRACEROUTE_HOP_LOSS_PROMETHEUS_METRIC_DESC,
+                TRACEROUTE_HOP_RTT_PROMETHEUS_METRIC_NAME,
+                TRACEROUTE_HOP_RTT_PROMETHEUS_METRIC_DESC,
+            ),
+            Some(_) => (
+                TRACEROUTE_PATH_HOP_LOSS_PROMETHEUS_METRIC_NAME,
+                TRACEROUTE_PATH_HOP_LOSS_PROMETHEUS_METRIC_DESC,
+                TRACEROUTE_PATH_HOP_RTT_PROMETHEUS_METRIC_NAME,
+                TRACEROUTE_PATH_HOP_RTT_PROMETHEUS_METRIC_DESC,
+            ),
+        };
+        let with_labels = |metric: ProbeMetric, hop: &str, hop_addr: &str| {
+            let metric = match path {
+                Some(path) => metric.with_label(PATH_PROMETHEUS_METRIC_LABEL_NAME, path),
+                None => metric,
+            };
+            metric
+                .with_label(HOP_PROMETHEUS_METRIC_LABEL_NAME, hop)
+                .with_label(HOP_ADDR_PROMETHEUS_METRIC_LABEL_NAME, hop_addr)
+        };
+        let hop = self.ttl.to_string();
+        let hop_addr = self
+            .responder()
+            .map(|addr| addr.to_string())
+            .unwrap_or_else(|| String::from("*"));
+        let stats = self.stats();
+
+        let mut metrics = vec![with_labels(
+            ProbeMetric::new(loss_name, loss_desc, stats.loss_ratio()),
+            &hop,
+            &hop_addr,
+        )];
+        for (stat, rtt) in [("min", stats.min), ("avg", stats.avg), ("max", stats.max)] {
+            if let Some(rtt) = rtt {
+                metrics.push(
+                    with_labels(
+                        ProbeMetric::new(rtt_name, rtt_desc, rtt.as_secs_f64() * 1000.0),
+                        &hop,
+                        &hop_addr,
+                    )
+                    .with_label(STAT_PROMETHEUS_METRIC_LABEL_NAME, stat),
+                );
+            }
+        }
+        metrics
+    }
+}
+
+/// 按 `traceroute` 的格式输出一跳，如 ` 3  10.0.0.1  1.234 ms  *  1.301 ms`
+impl Display for Hop {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>2}", self.ttl)?;
+        if let Some(addr) = self.responder() {
+            write!(f, "  {}", addr)?;
+        }
+        for reply in self.replies.iter() {
+            match reply {
+                Some(reply) => write!(f, "  {:.3} ms", reply.rtt.as_secs_f64() * 1000.0)?,
+                None => write!(f, "  *")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 将整条路径转换成探测指标
+pub fn hops_to_metrics(hops: &[Hop]) -> Vec<ProbeMetric> {
+    let mut metrics = vec![ProbeMetric::new(
+        TRACEROUTE_HOPS_PROMETHEUS_METRIC_NAME,
+        TRACEROUTE_HOPS_PROMETHEUS_METRIC_DESC,
+        hops.len() as f64,
+    )];
+    for hop in hops {
+        metrics.extend(hop.to_metrics(None));
+    }
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(last: u8, rtt: u64, kind: HopReplyKind) -> Option<HopReply> {
+        Some(HopReply {
+            responder: IpAddr::from([10, 0, 0, last]),
+            rtt: Duration::from_millis(rtt),
+            kind,
+        })
+    }
+
+    #[test]
+    fn stats_count_lost_replies() {
+        let hop = Hop {
+            ttl: 3,
+            replies: vec![
+                reply(1, 10, HopReplyKind::TimeExceeded),
+                None,
+                reply(1, 30, HopReplyKind::TimeExceeded),
+            ],
+        };
+        let stats = hop.stats();
+        assert_eq!(stats.sent, 3);
+        assert_eq!(stats.received, 2);
+        assert_eq!(stats.loss_ratio(), 1.0 / 3.0);
+        assert_eq!(stats.min, Some(Duration::from_millis(10)));
+        assert_eq!(stats.avg, Some(Duration::from_millis(20)));
+        assert_eq!(stats.max, Some(Duration::from_millis(30)));
+        assert_eq!(hop.to_string(), " 3  10.0.0.1  10.000 ms  *  30.000 ms");
+    }
+
+    #[test]
+    fn stats_without_reply() {
+        let hop = Hop {
+            ttl: 12,
+            replies: vec![None, None],
+        };
+        let stats = hop.stats();
+        assert_eq!(stats.loss_ratio(), 1.0);
+        assert!(stats.avg.is_none());
+        assert!(hop.responder().is_none());
+        assert_eq!(hop.to_string(), "12  *  *");
+        // 只有丢包率，地址为`*`
+        let metrics = hop.to_metrics(None);
+        assert_eq!(metrics.len(), 1);
+        assert!(metrics[0]
+            .labels
+            .contains(&(HOP_ADDR_PROMETHEUS_METRIC_LABEL_NAME, String::from("*"))));
+    }
+
+    #[test]
+    fn last_hop() {
+        let mut hop = Hop {
+            ttl: 5,
+            replies: vec![None, reply(9, 1, HopReplyKind::Unreachable(3))],
+        };
+        assert!(hop.is_last());
+        assert!(!hop.is_reached());
+        hop.replies.push(reply(9, 1, HopReplyKind::Reached));
+        assert!(hop.is_reached());
+    }
+}
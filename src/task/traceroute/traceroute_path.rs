@@ -0,0 +1,213 @@
+use crate::metrics::metrics_cst::{
+    PATH_PROMETHEUS_METRIC_LABEL_NAME, TRACEROUTE_PATHS_PROMETHEUS_METRIC_DESC,
+    TRACEROUTE_PATHS_PROMETHEUS_METRIC_NAME, TRACEROUTE_PATH_FLOWS_PROMETHEUS_METRIC_DESC,
+    TRACEROUTE_PATH_FLOWS_PROMETHEUS_METRIC_NAME, TRACEROUTE_PATH_HOPS_PROMETHEUS_METRIC_DESC,
+    TRACEROUTE_PATH_HOPS_PROMETHEUS_METRIC_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::task::traceroute::traceroute_hop::Hop;
+use std::net::IpAddr;
+
+/// 多路径模式下发现的一条路径，由走同一组路由器的若干条流合并而成
+#[derive(Debug, Clone)]
+pub struct Path {
+    /// 路径的编号，取走这条路径的流中最小的序号
+    pub id: u16,
+    /// 走这条路径的流的序号，从小到大排列
+    pub flows: Vec<u16>,
+    /// 按跳数排列的每一跳，包含所有流在这一跳的回复
+    pub hops: Vec<Hop>,
+}
+
+impl Path {
+    /// 每一跳的回复者，没有回复的跳为None
+    fn responders(&self) -> Vec<Option<IpAddr>> {
+        self.hops.iter().map(Hop::responder).collect()
+    }
+
+    /// 没有回复的跳数
+    fn silent_hops(&self) -> usize {
+        self.hops
+            .iter()
+            .filter(|hop| hop.responder().is_none())
+            .count()
+    }
+
+    /// 另一条路径的跳是否与这条路径一致
+    ///
+    /// 跳数相同，且每一跳的回复者相同或其中一方没有回复时认为一致
+    fn matches(&self, other: &Path) -> bool {
+        self.hops.len() == other.hops.len()
+            && self.hops.iter().zip(other.hops.iter()).all(|(a, b)| {
+                match (a.responder(), b.responder()) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => true,
+                }
+            })
+    }
+
+    /// 将另一条路径合并进来
+    fn merge(&mut self, other: Path) {
+        self.id = self.id.min(other.id);
+        self.flows.extend(other.flows);
+        self.flows.sort_unstable();
+        for (hop, other) in self.hops.iter_mut().zip(other.hops) {
+            hop.replies.extend(other.replies);
+        }
+    }
+
+    /// 这条路径是否到达了目标
+    pub fn is_reached(&self) -> bool {
+        self.hops.last().is_some_and(Hop::is_reached)
+    }
+
+    /// 转换成探测指标
+    pub fn to_metrics(&self) -> Vec<ProbeMetric> {
+        let mut metrics = vec![
+            ProbeMetric::new(
+                TRACEROUTE_PATH_FLOWS_PROMETHEUS_METRIC_NAME,
+                TRACEROUTE_PATH_FLOWS_PROMETHEUS_METRIC_DESC,
+                self.flows.len() as f64,
+            )
+            .with_label(PATH_PROMETHEUS_METRIC_LABEL_NAME, self.id),
+            ProbeMetric::new(
+                TRACEROUTE_PATH_HOPS_PROMETHEUS_METRIC_NAME,
+                TRACEROUTE_PATH_HOPS_PROMETHEUS_METRIC_DESC,
+                self.hops.len() as f64,
+            )
+            .with_label(PATH_PROMETHEUS_METRIC_LABEL_NAME, self.id),
+        ];
+        for hop in self.hops.iter() {
+            metrics.extend(hop.to_metrics(Some(self.id)));
+        }
+        metrics
+    }
+}
+
+/// 将每条流追踪到的跳按路径合并
+///
+/// 先合并每一跳回复者完全相同的流，再把有跳没有回复的路径按没有回复的跳数从少到多
+/// 并入一致的路径，这样丢包不会改变路径的划分和编号，结果也与流的先后顺序无关。
+/// # 参数
+/// * `flows` - 每条流的序号及其跳
+///
+/// # 返回值
+/// 按编号排列的路径
+pub fn group_paths(flows: Vec<(u16, Vec<Hop>)>) -> Vec<Path> {
+    let mut exact: Vec<Path> = vec![];
+    for (flow, hops) in flows {
+        let path = Path {
+            id: flow,
+            flows: vec![flow],
+            hops,
+        };
+        let responders = path.responders();
+        match exact.iter_mut().find(|p| p.responders() == responders) {
+            Some(p) => p.merge(path),
+            None => exact.push(path),
+        }
+    }
+
+    exact.sort_by_key(|path| (path.silent_hops(), path.id));
+    let mut paths: Vec<Path> = vec![];
+    for path in exact {
+        match paths.iter_mut().find(|p| p.matches(&path)) {
+            Some(p) => p.merge(path),
+            None => paths.push(path),
+        }
+    }
+    paths.sort_by_key(|path| path.id);
+    paths
+}
+
+/// 将所有路径转换成探测指标
+pub fn paths_to_metrics(paths: &[Path]) -> Vec<ProbeMetric> {
+    let mut metrics = vec![ProbeMetric::new(
+        TRACEROUTE_PATHS_PROMETHEUS_METRIC_NAME,
+        TRACEROUTE_PATHS_PROMETHEUS_METRIC_DESC,
+        paths.len() as f64,
+    )];
+    for path in paths {
+        metrics.extend(path.to_metrics());
+    }
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::traceroute::traceroute_hop::{HopReply, HopReplyKind};
+    use std::time::Duration;
+
+    /// 按回复者构造一条流的跳，`None`表示这一跳没有回复
+    fn flow(responders: &[Option<u8>]) -> Vec<Hop> {
+        responders
+            .iter()
+            .enumerate()
+            .map(|(i, responder)| Hop {
+                ttl: i as u8 + 1,
+                replies: vec![responder.map(|last| HopReply {
+                    responder: IpAddr::from([10, 0, 0, last]),
+                    rtt: Duration::from_millis(i as u64 + 1),
+                    kind: HopReplyKind::TimeExceeded,
+                })],
+            })
+            .collect()
+    }
+
+    /// 路径的编号、流和每一跳的回复者
+    fn summary(paths: &[Path]) -> Vec<(u16, Vec<u16>, Vec<Option<IpAddr>>)> {
+        paths
+            .iter()
+            .map(|path| (path.id, path.flows.clone(), path.responders()))
+            .collect()
+    }
+
+    #[test]
+    fn exact_flows_merge() {
+        let paths = group_paths(vec![
+            (0, flow(&[Some(1), Some(2), Some(3)])),
+            (1, flow(&[Some(1), Some(4), Some(3)])),
+            (2, flow(&[Some(1), Some(2), Some(3)])),
+        ]);
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].id, 0);
+        assert_eq!(paths[0].flows, vec![0, 2]);
+        assert_eq!(paths[0].hops[1].replies.len(), 2);
+        assert_eq!(paths[1].id, 1);
+        assert_eq!(paths[1].flows, vec![1]);
+    }
+
+    #[test]
+    fn silent_hops_join_last() {
+        // 第0条流在第2跳丢包，不能先占住路径而让第1、2条流分到不同的路径
+        let flows = vec![
+            (0, flow(&[Some(1), None, Some(3)])),
+            (1, flow(&[Some(1), Some(4), Some(3)])),
+            (2, flow(&[Some(1), Some(2), Some(3)])),
+            (3, flow(&[None, None, None])),
+        ];
+        let paths = group_paths(flows.clone());
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].flows, vec![0, 1, 3]);
+        assert_eq!(paths[0].responders()[1], Some(IpAddr::from([10, 0, 0, 4])));
+        assert_eq!(paths[0].hops[0].stats().loss_ratio(), 1.0 / 3.0);
+        assert_eq!(paths[1].id, 2);
+        assert_eq!(paths[1].flows, vec![2]);
+
+        // 结果与流的先后顺序无关
+        let mut reversed = flows;
+        reversed.reverse();
+        assert_eq!(summary(&group_paths(reversed)), summary(&paths));
+    }
+
+    #[test]
+    fn different_lengths_do_not_merge() {
+        let paths = group_paths(vec![
+            (0, flow(&[Some(1), Some(2)])),
+            (1, flow(&[Some(1), None, None])),
+        ]);
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|path| path.flows.len() == 1));
+    }
+}
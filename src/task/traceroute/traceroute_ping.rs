@@ -0,0 +1,523 @@
+use crate::ping_error::PingError;
+use crate::task::icmp::icmp_packet::{
+    ones_complement_sum, pseudo_header_sum_v6, IcmpMessage, ICMP_V4_FRAGMENTATION_NEEDED,
+};
+use crate::task::icmp::icmp_ping::{build_icmp_v4_echo, build_icmp_v6_echo};
+use crate::task::icmp::icmp_settings::IcmpSocketMode;
+use crate::task::icmp::icmp_socket::{
+    set_socket_ttl, IcmpReply, IcmpSocket, PendingReply, ProbeKey,
+};
+use crate::task::traceroute::traceroute_hop::{Hop, HopReply, HopReplyKind};
+use crate::task::traceroute::traceroute_settings::{TracerouteProtocol, TracerouteSettings};
+use log::{debug, trace};
+use socket2::SockRef;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::{TcpSocket, UdpSocket};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+/// UDP探测包的负载大小
+const UDP_PAYLOAD_SIZE: usize = 32;
+/// Paris模式下ICMP探测包的负载大小，前两个字节用于补偿校验和
+const PARIS_ICMP_PAYLOAD_SIZE: usize = 2;
+/// Paris模式下ICMP探测包校验和的起始值，第n条流的校验和为它加n
+const PARIS_ICMP_CHECKSUM_BASE: u16 = 0x8000;
+/// 一次追踪最长的耗时为超时时间的倍数，到期后还未发送的探测包按没有回复处理
+const DEADLINE_TIMEOUTS: u32 = 10;
+/// Paris模式下一条流连续这么多跳都没有回复时不再继续探测
+const MAX_SILENT_HOPS: u8 = 5;
+
+/// 获取与目标地址同一地址族的未指定地址，用于绑定本地端口
+fn unspecified_addr(dst_ip: &IpAddr) -> SocketAddr {
+    match dst_ip {
+        IpAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+        IpAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+    }
+}
+
+/// 构造校验和固定的 Echo Request 包
+///
+/// 负载均衡设备对ICMP通常按头部的前4个字节(类型、代码、校验和)做哈希，
+/// 这里用负载的前两个字节补偿序列号的变化，使校验和固定为 `checksum`。
+///
+/// # 参数
+/// * `pseudo_header_sum` - ICMPv6伪头部的累加值，为None时构造ICMPv4包
+fn build_paris_icmp_echo(
+    id: u16,
+    seq: u16,
+    checksum: u16,
+    pseudo_header_sum: Option<u16>,
+) -> Vec<u8> {
+    let mut buf = match pseudo_header_sum {
+        None => build_icmp_v4_echo(id, seq, PARIS_ICMP_PAYLOAD_SIZE),
+        Some(_) => build_icmp_v6_echo(id, seq, PARIS_ICMP_PAYLOAD_SIZE),
+    };
+    buf[2..4].copy_from_slice(&checksum.to_be_bytes());
+    buf[8..10].fill(0);
+    let sum = ones_complement_sum(pseudo_header_sum.unwrap_or(0) as u32, &buf);
+    buf[8..10].copy_from_slice(&(!sum).to_be_bytes());
+    if pseudo_header_sum.is_some() {
+        // ICMPv6的校验和由内核计算，补偿后内核算出的结果就是 checksum
+        buf[2..4].fill(0);
+    }
+    buf
+}
+
+/// Paris模式下的一条流
+///
+/// 同一条流的所有探测包保持相同的流标识(UDP/TCP的端口或ICMP的校验和)，
+/// 负载均衡设备会把它们哈希到同一条路径上，不同的流则可能走不同的路径。
+struct ParisFlow {
+    /// 流的序号
+    index: u16,
+    /// UDP探测共用的套接字，整条流使用同一个源端口
+    udp_socket: Option<UdpSocket>,
+    /// TCP探测共用的源端口，首次探测时由内核分配
+    tcp_src_port: u16,
+    /// ICMPv6伪头部的累加值
+    pseudo_header_sum: Option<u16>,
+}
+
+impl ParisFlow {
+    /// 构造函数
+    async fn new(
+        index: u16,
+        protocol: TracerouteProtocol,
+        dst_ip: IpAddr,
+    ) -> Result<Self, PingError> {
+        let udp_socket = match protocol {
+            TracerouteProtocol::Udp => Some(UdpSocket::bind(unspecified_addr(&dst_ip)).await?),
+            _ => None,
+        };
+        // 计算ICMPv6伪头部需要知道内核会选用的源地址
+        let pseudo_header_sum = match (protocol, dst_ip) {
+            (TracerouteProtocol::Icmp, IpAddr::V6(dst)) => {
+                let socket = UdpSocket::bind(unspecified_addr(&dst_ip)).await?;
+                socket.connect(SocketAddr::new(dst_ip, 9)).await?;
+                match socket.local_addr()?.ip() {
+                    IpAddr::V6(src) => Some(pseudo_header_sum_v6(
+                        &src,
+                        &dst,
+                        (8 + PARIS_ICMP_PAYLOAD_SIZE) as u32,
+                    )),
+                    IpAddr::V4(_) => None,
+                }
+            }
+            _ => None,
+        };
+        Ok(Self {
+            index,
+            udp_socket,
+            tcp_src_port: 0,
+            pseudo_header_sum,
+        })
+    }
+
+    /// ICMP探测包固定的校验和
+    fn icmp_checksum(&self) -> u16 {
+        PARIS_ICMP_CHECKSUM_BASE.wrapping_add(self.index)
+    }
+}
+
+/// 路由追踪
+///
+/// 普通模式下每次追踪同时探测所有的跳，每一跳按顺序发送 `count` 个TTL受限的探测包，
+/// 中间路由器回复的 Time Exceeded 由共享的ICMP原始套接字接收并分发过来。
+///
+/// 多路径(Paris)模式下同时追踪 `flows` 条流，每条流逐跳顺序探测，
+/// 保证同一条流同一时刻只有一个在途的探测包，回复不会因为流标识相同而混淆。
+/// 连续 `MAX_SILENT_HOPS` 跳没有回复时这条流停止探测。
+///
+/// 每次追踪的总耗时不超过超时时间的 `DEADLINE_TIMEOUTS` 倍，避免拖慢同组的其它任务。
+#[derive(Clone)]
+pub struct TraceroutePing {
+    id: u16,
+    settings: TracerouteSettings,
+}
+
+impl TraceroutePing {
+    pub fn new(settings: TracerouteSettings) -> Self {
+        Self {
+            id: std::process::id() as u16,
+            settings,
+        }
+    }
+
+    /// 追踪到目标的路径
+    ///
+    /// # 返回值
+    /// 按跳数排列的每一跳的结果，路径在第一个到达目标或目标不可达的跳结束
+    pub async fn trace(&self, dst_ip: IpAddr, timeout: Duration) -> Result<Vec<Hop>, PingError> {
+        trace!("traceroute {} ....", dst_ip);
+        // 接收中间路由器的回复必须使用原始套接字，创建失败时每一跳都会失败，直接返回错误
+        IcmpSocket::get(&dst_ip, IcmpSocketMode::Raw)?;
+
+        let deadline = Instant::now() + timeout * DEADLINE_TIMEOUTS;
+        let mut join_set = JoinSet::new();
+        for ttl in 1..=self.settings.max_hops {
+            let traceroute_ping = self.clone();
+            join_set.spawn(async move {
+                traceroute_ping
+                    .probe_hop(dst_ip, ttl, timeout, deadline, None)
+                    .await
+            });
+        }
+        let mut hops = join_set.join_all().await;
+        hops.sort_by_key(|hop| hop.ttl);
+        if let Some(last) = hops.iter().position(Hop::is_last) {
+            hops.truncate(last + 1);
+        }
+
+        trace!("traceroute {} finished in {} hops", dst_ip, hops.len());
+        Ok(hops)
+    }
+
+    /// 以多路径(Paris)模式追踪到目标的所有流
+    ///
+    /// # 返回值
+    /// 每条流的序号及其按跳数排列的每一跳的结果
+    pub async fn trace_flows(
+        &self,
+        dst_ip: IpAddr,
+        timeout: Duration,
+    ) -> Result<Vec<(u16, Vec<Hop>)>, PingError> {
+        trace!(
+            "paris traceroute {} with {} flows ....",
+            dst_ip,
+            self.settings.flows
+        );
+        IcmpSocket::get(&dst_ip, IcmpSocketMode::Raw)?;
+
+        let deadline = Instant::now() + timeout * DEADLINE_TIMEOUTS;
+        let mut join_set = JoinSet::new();
+        for index in 0..self.settings.flows {
+            let traceroute_ping = self.clone();
+            join_set.spawn(async move {
+                let hops = traceroute_ping
+                    .trace_flow(dst_ip, index, timeout, deadline)
+                    .await;
+                (index, hops)
+            });
+        }
+        let mut flows = vec![];
+        for (index, hops) in join_set.join_all().await {
+            flows.push((index, hops?));
+        }
+        flows.sort_by_key(|(index, _)| *index);
+        Ok(flows)
+    }
+
+    /// 追踪一条流的路径，逐跳顺序探测，直到到达目标、目标不可达、
+    /// 连续多跳没有回复或者超过了期限
+    async fn trace_flow(
+        &self,
+        dst_ip: IpAddr,
+        index: u16,
+        timeout: Duration,
+        deadline: Instant,
+    ) -> Result<Vec<Hop>, PingError> {
+        let mut flow = ParisFlow::new(index, self.settings.protocol, dst_ip).await?;
+        let mut hops = vec![];
+        let mut silent_hops = 0;
+        for ttl in 1..=self.settings.max_hops {
+            if Instant::now() >= deadline {
+                debug!(
+                    "traceroute {} 流{} 超过期限，停止于第{}跳",
+                    dst_ip, index, ttl
+                );
+                break;
+            }
+            let hop = self
+                .probe_hop(dst_ip, ttl, timeout, deadline, Some(&mut flow))
+                .await;
+            silent_hops = match hop.responder() {
+                Some(_) => 0,
+                None => silent_hops + 1,
+            };
+            let is_last = hop.is_last();
+            hops.push(hop);
+            if is_last {
+                break;
+            }
+            if silent_hops >= MAX_SILENT_HOPS {
+                debug!(
+                    "traceroute {} 流{} 连续{}跳没有回复，停止于第{}跳",
+                    dst_ip, index, silent_hops, ttl
+                );
+                break;
+            }
+        }
+        Ok(hops)
+    }
+
+    /// 探测某一跳
+    ///
+    /// # 参数
+    /// * `deadline` - 整次追踪的期限，到期后不再发送探测包
+    /// * `flow` - Paris模式下探测包所属的流
+    async fn probe_hop(
+        &self,
+        dst_ip: IpAddr,
+        ttl: u8,
+        timeout: Duration,
+        deadline: Instant,
+        mut flow: Option<&mut ParisFlow>,
+    ) -> Hop {
+        let mut replies = Vec::with_capacity(self.settings.count as usize);
+        for _ in 0..self.settings.count {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                replies.push(None);
+                continue;
+            }
+            let reply = match self
+                .probe(dst_ip, ttl, timeout.min(remaining), flow.as_deref_mut())
+                .await
+            {
+                Ok(reply) => reply,
+                Err(e) => {
+                    debug!("traceroute {} 第{}跳探测失败: {}", dst_ip, ttl, e);
+                    None
+                }
+            };
+            replies.push(reply);
+        }
+        Hop { ttl, replies }
+    }
+
+    /// 发送一个TTL受限的探测包并等待回复
+    ///
+    /// # 参数
+    /// * `flow` - Paris模式下探测包所属的流，为None时每个探测包使用不同的流标识
+    ///
+    /// # 返回值
+    /// 超时未收到回复时返回Ok(None)
+    async fn probe(
+        &self,
+        dst_ip: IpAddr,
+        ttl: u8,
+        timeout: Duration,
+        flow: Option<&mut ParisFlow>,
+    ) -> Result<Option<HopReply>, PingError> {
+        match self.settings.protocol {
+            TracerouteProtocol::Icmp => self.probe_icmp(dst_ip, ttl, timeout, flow).await,
+            TracerouteProtocol::Udp => self.probe_udp(dst_ip, ttl, timeout, flow).await,
+            TracerouteProtocol::Tcp => self.probe_tcp(dst_ip, ttl, timeout, flow).await,
+        }
+    }
+
+    /// 发送ICMP Echo Request探测包
+    async fn probe_icmp(
+        &self,
+        dst_ip: IpAddr,
+        ttl: u8,
+        timeout: Duration,
+        flow: Option<&mut ParisFlow>,
+    ) -> Result<Option<HopReply>, PingError> {
+        let socket = IcmpSocket::get(&dst_ip, IcmpSocketMode::Raw)?;
+        let seq = IcmpSocket::next_seq();
+        let packet = match (flow, dst_ip) {
+            (Some(flow), _) => {
+                build_paris_icmp_echo(self.id, seq, flow.icmp_checksum(), flow.pseudo_header_sum)
+            }
+            (None, IpAddr::V4(_)) => build_icmp_v4_echo(self.id, seq, 0),
+            (None, IpAddr::V6(_)) => build_icmp_v6_echo(self.id, seq, 0),
+        };
+        let pending = socket.register(ProbeKey::Echo {
+            id: self.id,
+            seq,
+            addr: dst_ip,
+        });
+        let send_time = Instant::now();
+        socket
+            .send_to(&packet, dst_ip, Some(ttl as u32), false)
+            .await?;
+        Ok(Self::wait_reply(pending, dst_ip, send_time, timeout).await)
+    }
+
+    /// 发送UDP探测包，到达目标后由目标回复端口不可达
+    ///
+    /// Paris模式下整条流共用一个套接字，不同的流使用不同的目标端口
+    async fn probe_udp(
+        &self,
+        dst_ip: IpAddr,
+        ttl: u8,
+        timeout: Duration,
+        flow: Option<&mut ParisFlow>,
+    ) -> Result<Option<HopReply>, PingError> {
+        let icmp_socket = IcmpSocket::get(&dst_ip, IcmpSocketMode::Raw)?;
+        let new_socket;
+        let (socket, dst_port) = match flow.as_ref().and_then(|f| f.udp_socket.as_ref()) {
+            Some(socket) => (
+                socket,
+                self.settings
+                    .port()
+                    .wrapping_add(flow.as_ref().map_or(0, |f| f.index)),
+            ),
+            None => {
+                new_socket = UdpSocket::bind(unspecified_addr(&dst_ip)).await?;
+                (&new_socket, self.settings.port())
+            }
+        };
+        set_socket_ttl(SockRef::from(socket), dst_ip.is_ipv6(), ttl as u32)?;
+        let pending = icmp_socket.register(ProbeKey::Udp {
+            src_port: socket.local_addr()?.port(),
+            dst_port,
+            addr: dst_ip,
+        });
+        let send_time = Instant::now();
+        socket
+            .send_to(&[0; UDP_PAYLOAD_SIZE], SocketAddr::new(dst_ip, dst_port))
+            .await?;
+        Ok(Self::wait_reply(pending, dst_ip, send_time, timeout).await)
+    }
+
+    /// 发送TCP SYN探测包，到达目标后由目标回复 SYN-ACK 或 RST
+    ///
+    /// Paris模式下整条流复用同一个源端口，关闭时直接发送RST，避免占用 TIME_WAIT
+    async fn probe_tcp(
+        &self,
+        dst_ip: IpAddr,
+        ttl: u8,
+        timeout: Duration,
+        flow: Option<&mut ParisFlow>,
+    ) -> Result<Option<HopReply>, PingError> {
+        let icmp_socket = IcmpSocket::get(&dst_ip, IcmpSocketMode::Raw)?;
+        let socket = match dst_ip {
+            IpAddr::V4(_) => TcpSocket::new_v4()?,
+            IpAddr::V6(_) => TcpSocket::new_v6()?,
+        };
+        set_socket_ttl(SockRef::from(&socket), dst_ip.is_ipv6(), ttl as u32)?;
+        let src_port = match flow {
+            Some(flow) => {
+                socket.set_reuseaddr(true)?;
+                SockRef::from(&socket).set_linger(Some(Duration::ZERO))?;
+                let mut addr = unspecified_addr(&dst_ip);
+                addr.set_port(flow.tcp_src_port);
+                socket.bind(addr)?;
+                flow.tcp_src_port = socket.local_addr()?.port();
+                flow.tcp_src_port
+            }
+            None => {
+                socket.bind(unspecified_addr(&dst_ip))?;
+                socket.local_addr()?.port()
+            }
+        };
+        let dst_port = self.settings.port();
+        let mut pending = icmp_socket.register(ProbeKey::Tcp {
+            src_port,
+            dst_port,
+            addr: dst_ip,
+        });
+        let send_time = Instant::now();
+        let connect = socket.connect(SocketAddr::new(dst_ip, dst_port));
+        tokio::pin!(connect);
+
+        // 收到 SYN-ACK 或 RST 都说明到达了目标
+        let reached = || HopReply {
+            responder: dst_ip,
+            rtt: send_time.elapsed(),
+            kind: HopReplyKind::Reached,
+        };
+        let reply = tokio::time::timeout(timeout, async {
+            let mut connect_failed = false;
+            loop {
+                tokio::select! {
+                    result = &mut connect, if !connect_failed => match result {
+                        Ok(_) => return Some(reached()),
+                        Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+                            return Some(reached());
+                        }
+                        // 其它错误通常由ICMP差错报文引起，继续等待分发过来的回复
+                        Err(_) => connect_failed = true,
+                    },
+                    reply = pending.recv() => {
+                        return reply.map(|reply| Self::to_hop_reply(reply, dst_ip, send_time));
+                    }
+                }
+            }
+        })
+        .await
+        .ok()
+        .flatten();
+        Ok(reply)
+    }
+
+    /// 等待分发过来的回复，超时返回None
+    async fn wait_reply(
+        mut pending: PendingReply,
+        dst_ip: IpAddr,
+        send_time: Instant,
+        timeout: Duration,
+    ) -> Option<HopReply> {
+        tokio::time::timeout(timeout, pending.recv())
+            .await
+            .ok()
+            .flatten()
+            .map(|reply| Self::to_hop_reply(reply, dst_ip, send_time))
+    }
+
+    /// 将ICMP回包转换成跳的回复
+    fn to_hop_reply(reply: IcmpReply, dst_ip: IpAddr, send_time: Instant) -> HopReply {
+        let kind = match reply.message {
+            IcmpMessage::EchoReply { .. } => HopReplyKind::Reached,
+            IcmpMessage::TimeExceeded { .. } => HopReplyKind::TimeExceeded,
+            // 由目标回复的不可达(如UDP的端口不可达)说明已经到达了目标
+            IcmpMessage::DestinationUnreachable { .. } if reply.source == dst_ip => {
+                HopReplyKind::Reached
+            }
+            IcmpMessage::DestinationUnreachable { code, .. } => HopReplyKind::Unreachable(code),
+            // 包过大统一按ICMPv4的"需要分片"代码记录
+            IcmpMessage::PacketTooBig { .. } => {
+                HopReplyKind::Unreachable(ICMP_V4_FRAGMENTATION_NEEDED)
+            }
+        };
+        HopReply {
+            responder: reply.source,
+            rtt: reply.recv_time.duration_since(send_time),
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::icmp::icmp_packet::checksum_v4;
+
+    #[test]
+    fn paris_icmp_echo_v4_keeps_checksum() {
+        for (seq, checksum) in [(0, 0x8000), (1, 0x8001), (0x1234, 0x0001), (0xffff, 0xfffe)] {
+            let buf = build_paris_icmp_echo(0xbeef, seq, checksum, None);
+            assert_eq!(buf.len(), 8 + PARIS_ICMP_PAYLOAD_SIZE);
+            assert_eq!(buf[..2], [8, 0]);
+            assert_eq!(buf[2..4], checksum.to_be_bytes());
+            assert_eq!(buf[4..6], 0xbeef_u16.to_be_bytes());
+            assert_eq!(buf[6..8], seq.to_be_bytes());
+            // 补偿后整个包的校验和是合法的
+            assert_eq!(checksum_v4(&buf), 0);
+        }
+    }
+
+    #[test]
+    fn paris_icmp_echo_v6_compensates_kernel_checksum() {
+        let src = "2001:db8::1".parse().unwrap();
+        let dst = "2001:db8::2".parse().unwrap();
+        let length = (8 + PARIS_ICMP_PAYLOAD_SIZE) as u32;
+        let pseudo_header_sum = pseudo_header_sum_v6(&src, &dst, length);
+        for (seq, checksum) in [(0, 0x8000), (7, 0x8003), (0xffff, 0x1234)] {
+            let buf = build_paris_icmp_echo(0xbeef, seq, checksum, Some(pseudo_header_sum));
+            assert_eq!(buf[0], 128);
+            // 校验和字段留给内核计算
+            assert_eq!(buf[2..4], [0, 0]);
+            assert_eq!(buf[6..8], seq.to_be_bytes());
+            // 内核按伪头部和整个包计算出的校验和
+            assert_eq!(
+                !ones_complement_sum(pseudo_header_sum as u32, &buf),
+                checksum
+            );
+        }
+    }
+}
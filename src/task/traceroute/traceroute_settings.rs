@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+/// 路由追踪任务配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct TracerouteSettings {
+    /// 探测包使用的协议
+    #[serde(default)]
+    pub protocol: TracerouteProtocol,
+    /// 目标端口，仅UDP和TCP有效，默认UDP为33434，TCP为80
+    pub port: Option<u16>,
+    /// 最大跳数
+    #[serde(default = "max_hops_default")]
+    pub max_hops: u8,
+    /// 每一跳发送的探测包数
+    #[serde(default = "count_default")]
+    pub count: u16,
+    /// 是否启用多路径(Paris)模式，同一条流的探测包保持相同的流标识，
+    /// 以发现负载均衡(ECMP)下的多条路径
+    #[serde(default)]
+    pub multipath: bool,
+    /// 多路径模式下追踪的流数
+    #[serde(default = "flows_default")]
+    pub flows: u16,
+}
+
+fn max_hops_default() -> u8 {
+    30 // 默认 30 跳
+}
+fn count_default() -> u16 {
+    3 // 默认每跳 3 个包
+}
+fn flows_default() -> u16 {
+    16 // 默认追踪 16 条流
+}
+
+impl Default for TracerouteSettings {
+    fn default() -> Self {
+        Self {
+            protocol: TracerouteProtocol::default(),
+            port: None,
+            max_hops: max_hops_default(),
+            count: count_default(),
+            multipath: false,
+            flows: flows_default(),
+        }
+    }
+}
+
+impl TracerouteSettings {
+    /// 获取目标端口
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.protocol {
+            TracerouteProtocol::Icmp => 0,
+            TracerouteProtocol::Udp => 33434,
+            TracerouteProtocol::Tcp => 80,
+        })
+    }
+}
+
+/// 路由追踪探测包的协议
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TracerouteProtocol {
+    /// ICMP Echo Request
+    #[default]
+    Icmp,
+    /// UDP 数据报，到达目标后由目标回复端口不可达
+    Udp,
+    /// TCP SYN，到达目标后由目标回复 SYN-ACK 或 RST
+    Tcp,
+}
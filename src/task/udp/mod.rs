@@ -0,0 +1,3 @@
+pub mod udp_executor;
+pub mod udp_ping;
+pub mod udp_settings;
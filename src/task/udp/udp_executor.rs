@@ -0,0 +1,89 @@
+use crate::executor::{ExecReport, Executor};
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::{split_host_port, TargetResolver};
+use crate::task::udp::udp_ping::{expect_matched_metric, UdpPing, UdpReplyState};
+use crate::task::udp::udp_settings::UdpSettings;
+use async_trait::async_trait;
+use log::trace;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct UdpExecutor {
+    resolver: TargetResolver,
+    port: u16,
+    udp_ping: UdpPing,
+    timeout: Duration,
+}
+
+impl UdpExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `host_port` - 要ping的主机名及端口号
+    /// * `timeout` - 一个 `Duration`，表示等待回复的超时时间
+    /// * `settings` - UDP任务配置
+    /// * `resolve` - 解析主机名的配置
+    ///
+    /// # Panics
+    /// 目标缺少端口号或配置错误时会触发panic，配置在加载时已经检查过
+    pub fn new(
+        host_port: String,
+        timeout: Duration,
+        settings: UdpSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        let (host, port) = split_host_port(&host_port).unwrap();
+        Self {
+            resolver: TargetResolver::new(&host, resolve),
+            port,
+            udp_ping: UdpPing::new(&settings),
+            timeout,
+        }
+    }
+
+    /// 探测一个地址
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        let socket_addr = SocketAddr::new(ip_addr, self.port);
+        trace!("开始执行 UDP 任务: ping {}", socket_addr);
+        let result = self.udp_ping.ping(socket_addr, self.timeout).await;
+        // 无法归入任何状态的错误(如本地资源不足)直接返回
+        let Some(state) = UdpReplyState::of(&result) else {
+            return result.map(|_| ExecReport::default());
+        };
+        trace!("ping {} {}", socket_addr, state);
+        let mut metrics = state.to_metrics();
+        if self.udp_ping.has_expect() {
+            metrics.push(expect_matched_metric(result.is_ok()));
+        }
+        // 不要求回复且没有收到回复时，以执行任务的总耗时作为耗时
+        match result {
+            Ok(rtt) => Ok(ExecReport {
+                elapsed: rtt,
+                error: None,
+                metrics,
+            }),
+            Err(e) => Ok(ExecReport {
+                elapsed: None,
+                error: Some(e),
+                metrics,
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl Executor for UdpExecutor {
+    fn get_name(&self) -> String {
+        String::from("UDP")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move { executor.exec_addr(ip_addr).await }
+            })
+            .await
+    }
+}
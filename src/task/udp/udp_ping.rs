@@ -0,0 +1,184 @@
+use crate::metrics::metrics_cst::{
+    STATE_PROMETHEUS_METRIC_LABEL_NAME, UDP_EXPECT_MATCHED_PROMETHEUS_METRIC_DESC,
+    UDP_EXPECT_MATCHED_PROMETHEUS_METRIC_NAME, UDP_REPLY_STATE_PROMETHEUS_METRIC_DESC,
+    UDP_REPLY_STATE_PROMETHEUS_METRIC_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::task::udp::udp_settings::UdpSettings;
+use log::trace;
+use regex::bytes::Regex;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use strum_macros::Display;
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+/// 错误信息中最多展示的回复字节数
+const REPLY_PREVIEW_SIZE: usize = 64;
+
+/// UDP探测的结果
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum UdpReplyState {
+    /// 收到了回复
+    Replied,
+    /// 超时未收到回复，端口可能开放，也可能被防火墙丢弃
+    Silent,
+    /// 主机回复ICMP端口不可达，端口没有监听
+    Refused,
+    /// 主机或网络不可达
+    Unreachable,
+}
+
+impl UdpReplyState {
+    /// 所有的状态
+    const ALL: [UdpReplyState; 4] = [
+        UdpReplyState::Replied,
+        UdpReplyState::Silent,
+        UdpReplyState::Refused,
+        UdpReplyState::Unreachable,
+    ];
+
+    /// 根据探测的结果判断状态，不属于任何一种状态的错误返回None
+    pub fn of(result: &Result<Option<Duration>, PingError>) -> Option<Self> {
+        match result {
+            Ok(Some(_)) | Err(PingError::InvalidReply(_)) => Some(UdpReplyState::Replied),
+            Ok(None) | Err(PingError::Timeout) => Some(UdpReplyState::Silent),
+            Err(PingError::ConnectionRefused) => Some(UdpReplyState::Refused),
+            Err(PingError::Unreachable(_)) => Some(UdpReplyState::Unreachable),
+            Err(_) => None,
+        }
+    }
+
+    /// 转换成探测指标，当前状态为1，其余状态为0
+    pub fn to_metrics(self) -> Vec<ProbeMetric> {
+        Self::ALL
+            .iter()
+            .map(|state| {
+                ProbeMetric::new(
+                    UDP_REPLY_STATE_PROMETHEUS_METRIC_NAME,
+                    UDP_REPLY_STATE_PROMETHEUS_METRIC_DESC,
+                    if *state == self { 1.0 } else { 0.0 },
+                )
+                .with_label(STATE_PROMETHEUS_METRIC_LABEL_NAME, state)
+            })
+            .collect()
+    }
+}
+
+/// 期望回复是否匹配的探测指标
+pub fn expect_matched_metric(matched: bool) -> ProbeMetric {
+    ProbeMetric::new(
+        UDP_EXPECT_MATCHED_PROMETHEUS_METRIC_NAME,
+        UDP_EXPECT_MATCHED_PROMETHEUS_METRIC_DESC,
+        if matched { 1.0 } else { 0.0 },
+    )
+}
+
+#[derive(Clone)]
+pub struct UdpPing {
+    /// 发送的数据
+    send: Vec<u8>,
+    /// 期望回复匹配的正则表达式
+    expect: Option<Regex>,
+    /// 是否要求收到回复
+    require_reply: bool,
+    /// 回复的最大字节数
+    max_read: usize,
+}
+
+impl UdpPing {
+    /// 构造函数
+    ///
+    /// # Panics
+    /// 发送的数据或期望回复的格式错误时会触发panic，配置在加载时已经检查过
+    pub fn new(settings: &UdpSettings) -> Self {
+        let expect = settings.expect_regex().unwrap();
+        UdpPing {
+            send: settings.send_bytes().unwrap(),
+            require_reply: settings.require_reply || expect.is_some(),
+            expect,
+            max_read: settings.max_read,
+        }
+    }
+
+    /// 是否配置了期望的回复
+    pub fn has_expect(&self) -> bool {
+        self.expect.is_some()
+    }
+
+    /// 发送数据报并等待回复
+    ///
+    /// 使用已连接的套接字，主机回复的ICMP端口不可达会以 `ECONNREFUSED` 报告给套接字
+    ///
+    /// # 返回值
+    /// 收到匹配的回复时返回往返时间，不要求回复且超时未收到回复时返回None。
+    /// 端口不可达、主机不可达分别返回 `ConnectionRefused` 和 `Unreachable` 错误，
+    /// 要求回复而超时未收到时返回 `Timeout` 错误，收到的回复都不匹配时返回 `InvalidReply` 错误
+    pub async fn ping(
+        &self,
+        socket_addr: SocketAddr,
+        timeout: Duration,
+    ) -> Result<Option<Duration>, PingError> {
+        trace!("ping {} ....", socket_addr);
+        let local: IpAddr = if socket_addr.is_ipv4() {
+            Ipv4Addr::UNSPECIFIED.into()
+        } else {
+            Ipv6Addr::UNSPECIFIED.into()
+        };
+        let socket = UdpSocket::bind(SocketAddr::new(local, 0)).await?;
+        socket.connect(socket_addr).await.map_err(map_error)?;
+
+        let start = Instant::now();
+        socket.send(&self.send).await.map_err(map_error)?;
+        let mut buf = vec![0; self.max_read];
+        let mut last_reply: Option<Vec<u8>> = None;
+        let result = tokio::time::timeout(timeout, async {
+            loop {
+                let size = socket.recv(&mut buf).await.map_err(map_error)?;
+                let rtt = start.elapsed();
+                match &self.expect {
+                    Some(expect) if !expect.is_match(&buf[..size]) => {
+                        trace!("ping {} ignored an unexpected reply", socket_addr);
+                        last_reply = Some(buf[..size].to_vec());
+                    }
+                    _ => return Ok(rtt),
+                }
+            }
+        })
+        .await;
+        match result {
+            Ok(Ok(rtt)) => {
+                trace!("ping {} replied in {:?}", socket_addr, rtt);
+                Ok(Some(rtt))
+            }
+            Ok(Err(e)) => Err(e),
+            Err(_) => match last_reply {
+                Some(reply) => {
+                    let preview =
+                        String::from_utf8_lossy(&reply[..reply.len().min(REPLY_PREVIEW_SIZE)]);
+                    Err(PingError::InvalidReply(format!(
+                        "回复未匹配 /{}/: {:?}",
+                        self.expect.as_ref().map_or("", |expect| expect.as_str()),
+                        preview
+                    )))
+                }
+                None if self.require_reply => Err(PingError::Timeout),
+                None => Ok(None),
+            },
+        }
+    }
+}
+
+/// 将UDP套接字的错误转换成探测的错误
+pub fn map_error(e: io::Error) -> PingError {
+    match e.kind() {
+        io::ErrorKind::ConnectionRefused => PingError::ConnectionRefused,
+        io::ErrorKind::HostUnreachable | io::ErrorKind::NetworkUnreachable => {
+            PingError::Unreachable(e)
+        }
+        _ => PingError::Io(e),
+    }
+}
@@ -0,0 +1,77 @@
+use crate::task::tcp::tcp_settings::{decode_hex, unescape};
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+
+/// UDP任务配置
+///
+/// 向目标发送一个数据报，再等待匹配 `expect` 或 `expect-hex` 的回复，不匹配的回复会被忽略。
+/// 目标端口没有监听时，主机回复的ICMP端口不可达会使探测失败。
+/// 没有配置期望的回复时，超时未收到回复也视为成功，适用于 syslog 等不回复的服务。
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct UdpSettings {
+    /// 发送的数据，支持 `\r`、`\n`、`\t`、`\0`、`\\` 和 `\xHH` 转义，默认发送空的数据报
+    pub send: Option<String>,
+    /// 发送的数据的十六进制表示，与 `send` 二选一
+    pub send_hex: Option<String>,
+    /// 期望回复匹配的正则表达式，按字节匹配
+    pub expect: Option<String>,
+    /// 期望回复包含的字节的十六进制表示，与 `expect` 二选一
+    pub expect_hex: Option<String>,
+    /// 没有配置期望的回复时，是否仍然要求收到回复
+    #[serde(default)]
+    pub require_reply: bool,
+    /// 回复的最大字节数，超过的部分被截断
+    #[serde(default = "max_read_default")]
+    pub max_read: usize,
+}
+
+fn max_read_default() -> usize {
+    65535 // 默认UDP数据报的最大长度
+}
+
+impl Default for UdpSettings {
+    fn default() -> Self {
+        Self {
+            send: None,
+            send_hex: None,
+            expect: None,
+            expect_hex: None,
+            require_reply: false,
+            max_read: max_read_default(),
+        }
+    }
+}
+
+impl UdpSettings {
+    /// 获取要发送的数据
+    ///
+    /// # 返回值
+    /// 转义或十六进制格式错误时返回错误信息
+    pub fn send_bytes(&self) -> Result<Vec<u8>, String> {
+        match (&self.send, &self.send_hex) {
+            (Some(_), Some(_)) => Err(String::from("send和send-hex不能同时配置")),
+            (Some(text), None) => unescape(text),
+            (None, Some(hex)) => decode_hex(hex),
+            (None, None) => Ok(vec![]),
+        }
+    }
+
+    /// 编译期望回复匹配的正则表达式，`expect-hex` 转换成匹配这些字节的正则表达式
+    pub fn expect_regex(&self) -> Result<Option<Regex>, String> {
+        match (&self.expect, &self.expect_hex) {
+            (Some(_), Some(_)) => Err(String::from("expect和expect-hex不能同时配置")),
+            (Some(expect), None) => Regex::new(expect)
+                .map(Some)
+                .map_err(|e| format!("expect不是合法的正则表达式: {}", e)),
+            (None, Some(hex)) => {
+                let pattern: String = decode_hex(hex)?
+                    .iter()
+                    .map(|b| format!("\\x{:02x}", b))
+                    .collect();
+                Ok(Some(Regex::new(&format!("(?-u){}", pattern)).unwrap()))
+            }
+            (None, None) => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod websocket_executor;
+pub mod websocket_ping;
+pub mod websocket_settings;
@@ -0,0 +1,72 @@
+use crate::executor::{ExecReport, Executor};
+use crate::ping_error::PingError;
+use crate::resolver::resolve_settings::ResolveSettings;
+use crate::resolver::target_resolver::TargetResolver;
+use crate::task::websocket::websocket_ping::WebsocketPing;
+use crate::task::websocket::websocket_settings::WebsocketSettings;
+use async_trait::async_trait;
+use log::trace;
+use std::net::IpAddr;
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct WebsocketExecutor {
+    url: String,
+    resolver: TargetResolver,
+    websocket_ping: WebsocketPing,
+    timeout: Duration,
+}
+
+impl WebsocketExecutor {
+    /// 构造函数
+    /// # 参数
+    /// * `url` - WebSocket地址，如 `wss://example.com/ws`
+    /// * `timeout` - 一个 `Duration`，表示超时时间
+    /// * `settings` - WebSocket任务配置
+    /// * `resolve` - 解析主机名的配置
+    ///
+    /// # Panics
+    /// 配置错误时会触发panic，配置在加载时已经检查过
+    pub fn new(
+        url: String,
+        timeout: Duration,
+        settings: WebsocketSettings,
+        resolve: ResolveSettings,
+    ) -> Self {
+        let websocket_ping = WebsocketPing::new(&url, &settings).unwrap();
+        Self {
+            resolver: TargetResolver::new(websocket_ping.host(), resolve),
+            websocket_ping,
+            url,
+            timeout,
+        }
+    }
+
+    /// 探测一个地址，以握手及消息往返的耗时之和作为耗时
+    async fn exec_addr(&self, ip_addr: IpAddr) -> Result<ExecReport, PingError> {
+        trace!("开始执行 WebSocket 任务: open {} via {}", self.url, ip_addr);
+        let reply = self.websocket_ping.ping(ip_addr, self.timeout).await?;
+        let metrics = reply.to_metrics();
+        Ok(ExecReport {
+            elapsed: Some(reply.handshake + reply.round_trip.unwrap_or_default()),
+            error: reply.error,
+            metrics,
+        })
+    }
+}
+
+#[async_trait]
+impl Executor for WebsocketExecutor {
+    fn get_name(&self) -> String {
+        String::from("WebSocket")
+    }
+
+    async fn exec(&self) -> Result<ExecReport, PingError> {
+        self.resolver
+            .exec(|ip_addr| {
+                let executor = self.clone();
+                async move { executor.exec_addr(ip_addr).await }
+            })
+            .await
+    }
+}
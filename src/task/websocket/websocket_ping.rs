@@ -0,0 +1,295 @@
+use crate::metrics::metrics_cst::{
+    WEBSOCKET_HANDSHAKE_PROMETHEUS_METRIC_DESC, WEBSOCKET_HANDSHAKE_PROMETHEUS_METRIC_NAME,
+    WEBSOCKET_ROUND_TRIP_PROMETHEUS_METRIC_DESC, WEBSOCKET_ROUND_TRIP_PROMETHEUS_METRIC_NAME,
+    WEBSOCKET_STATUS_CODE_PROMETHEUS_METRIC_DESC, WEBSOCKET_STATUS_CODE_PROMETHEUS_METRIC_NAME,
+};
+use crate::metrics::probe_metric::ProbeMetric;
+use crate::ping_error::PingError;
+use crate::task::http::http_client::client_config;
+use crate::task::tcp::tcp_ping::TcpPing;
+use crate::task::tcp::tcp_settings::TcpSettings;
+use crate::task::websocket::websocket_settings::WebsocketSettings;
+use bytes::Bytes;
+use futures_util::{SinkExt, StreamExt};
+use http::header::{HeaderName, HeaderValue};
+use http::{HeaderMap, Uri};
+use log::trace;
+use regex::bytes::Regex;
+use rustls::pki_types::ServerName;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
+use tokio_rustls::TlsConnector;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::{client_async, WebSocketStream};
+
+/// Ping帧携带的数据
+const PING_PAYLOAD: &[u8] = b"pong-rs";
+
+/// 一次WebSocket探测的结果
+#[derive(Debug)]
+pub struct WebsocketReply {
+    /// 握手响应的HTTP状态码，升级成功时为101
+    pub status_code: u16,
+    /// 建立连接、TLS握手及升级握手的总耗时
+    pub handshake: Duration,
+    /// 发出消息到收到期望回复的耗时，没有配置收发消息时为None
+    pub round_trip: Option<Duration>,
+    /// 服务器拒绝升级或收发消息失败的原因
+    pub error: Option<PingError>,
+}
+
+impl WebsocketReply {
+    /// 转换成探测指标
+    pub fn to_metrics(&self) -> Vec<ProbeMetric> {
+        let mut metrics = vec![
+            ProbeMetric::new(
+                WEBSOCKET_STATUS_CODE_PROMETHEUS_METRIC_NAME,
+                WEBSOCKET_STATUS_CODE_PROMETHEUS_METRIC_DESC,
+                self.status_code as f64,
+            ),
+            ProbeMetric::new(
+                WEBSOCKET_HANDSHAKE_PROMETHEUS_METRIC_NAME,
+                WEBSOCKET_HANDSHAKE_PROMETHEUS_METRIC_DESC,
+                self.handshake.as_secs_f64() * 1000.0,
+            ),
+        ];
+        if let Some(round_trip) = self.round_trip {
+            metrics.push(ProbeMetric::new(
+                WEBSOCKET_ROUND_TRIP_PROMETHEUS_METRIC_NAME,
+                WEBSOCKET_ROUND_TRIP_PROMETHEUS_METRIC_DESC,
+                round_trip.as_secs_f64() * 1000.0,
+            ));
+        }
+        metrics
+    }
+}
+
+#[derive(Clone)]
+pub struct WebsocketPing {
+    uri: Uri,
+    /// 主机名，IPv6地址带方括号
+    host: String,
+    port: u16,
+    /// 握手请求附加的请求头
+    headers: HeaderMap,
+    /// TLS连接器及握手时发送的SNI，ws连接时为None
+    tls: Option<(TlsConnector, ServerName<'static>)>,
+    /// 握手后发送的消息
+    send: Option<Message>,
+    /// 是否发送Ping帧并等待Pong帧
+    ping: bool,
+    /// 期望回复匹配的正则表达式
+    expect: Option<Regex>,
+}
+
+impl WebsocketPing {
+    /// 构造函数
+    /// # 参数
+    /// * `url` - WebSocket地址，如 `wss://example.com/ws`
+    /// * `settings` - WebSocket任务配置
+    ///
+    /// # 返回值
+    /// 地址、请求头、发送的消息或期望回复的正则表达式格式错误时返回错误信息
+    pub fn new(url: &str, settings: &WebsocketSettings) -> Result<Self, String> {
+        let uri = Uri::from_str(url).map_err(|e| format!("错误的地址({}): {}", url, e))?;
+        let secure = match uri.scheme_str() {
+            Some("ws") => false,
+            Some("wss") => true,
+            _ => return Err(format!("地址须以ws://或wss://开头: {}", url)),
+        };
+        let host = uri
+            .host()
+            .ok_or_else(|| format!("地址中缺少主机名: {}", url))?
+            .to_string();
+        let port = uri.port_u16().unwrap_or(if secure { 443 } else { 80 });
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in settings.headers.iter() {
+            headers.insert(
+                HeaderName::from_str(name).map_err(|e| format!("错误的请求头({}): {}", name, e))?,
+                HeaderValue::from_str(value)
+                    .map_err(|e| format!("错误的请求头({}): {}", name, e))?,
+            );
+        }
+
+        let tls = if secure {
+            let tls_name = host.trim_start_matches('[').trim_end_matches(']');
+            let server_name = ServerName::try_from(tls_name.to_string())
+                .map_err(|e| format!("错误的主机名({}): {}", tls_name, e))?;
+            Some((tls_connector(settings.insecure)?, server_name))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            uri,
+            host,
+            port,
+            headers,
+            tls,
+            send: settings.send_message()?,
+            ping: settings.ping,
+            expect: settings.expect_regex()?,
+        })
+    }
+
+    /// 主机名，IPv6地址带方括号
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// 连接目标的一个地址并完成升级握手，再按配置收发消息，结束后关闭连接
+    ///
+    /// # 返回值
+    /// 服务器拒绝升级或收发消息失败不会返回错误，失败原因记录在结果中
+    pub async fn ping(
+        &self,
+        ip_addr: IpAddr,
+        timeout: Duration,
+    ) -> Result<WebsocketReply, PingError> {
+        let socket_addr = SocketAddr::new(ip_addr, self.port);
+        trace!("websocket {} via {} ....", self.uri, socket_addr);
+        let start = Instant::now();
+        let (stream, _) = TcpPing::new(socket_addr, &TcpSettings::default())
+            .connect(timeout)
+            .await?;
+        let reply = match &self.tls {
+            Some((connector, server_name)) => {
+                let stream = tokio::time::timeout(
+                    timeout.saturating_sub(start.elapsed()),
+                    connector.connect(server_name.clone(), stream),
+                )
+                .await
+                .map_err(|_| PingError::Timeout)?
+                .map_err(|e| PingError::Tls(e.to_string()))?;
+                self.open(stream, start, timeout).await?
+            }
+            None => self.open(stream, start, timeout).await?,
+        };
+        trace!("websocket {} via {} {:?}", self.uri, socket_addr, reply);
+        Ok(reply)
+    }
+
+    /// 在已建立的连接上完成升级握手并收发消息
+    async fn open<S>(
+        &self,
+        stream: S,
+        start: Instant,
+        timeout: Duration,
+    ) -> Result<WebsocketReply, PingError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut request = self
+            .uri
+            .clone()
+            .into_client_request()
+            .map_err(map_ws_error)?;
+        request.headers_mut().extend(self.headers.clone());
+        let result = tokio::time::timeout(
+            timeout.saturating_sub(start.elapsed()),
+            client_async(request, stream),
+        )
+        .await
+        .map_err(|_| PingError::Timeout)?;
+        let (mut ws, response) = match result {
+            Ok(result) => result,
+            // 服务器拒绝升级时仍然报告响应的状态码
+            Err(WsError::Http(response)) => {
+                return Ok(WebsocketReply {
+                    status_code: response.status().as_u16(),
+                    handshake: start.elapsed(),
+                    round_trip: None,
+                    error: Some(PingError::InvalidReply(format!(
+                        "升级失败，HTTP状态码为 {}",
+                        response.status()
+                    ))),
+                });
+            }
+            Err(e) => return Err(map_ws_error(e)),
+        };
+        let handshake = start.elapsed();
+
+        let result =
+            tokio::time::timeout(timeout.saturating_sub(handshake), self.exchange(&mut ws))
+                .await
+                .unwrap_or_else(|_| Err(PingError::InvalidReply(String::from("等待回复超时"))));
+        // 发出关闭帧即可，不等待服务器的确认
+        let _ = tokio::time::timeout(timeout.saturating_sub(start.elapsed()), ws.close(None)).await;
+
+        let (round_trip, error) = match result {
+            Ok(round_trip) => (round_trip, None),
+            Err(e) => (None, Some(e)),
+        };
+        Ok(WebsocketReply {
+            status_code: response.status().as_u16(),
+            handshake,
+            round_trip,
+            error,
+        })
+    }
+
+    /// 发出配置的消息或Ping帧，再等待期望的回复或Pong帧
+    ///
+    /// # 返回值
+    /// 收到回复的耗时，没有配置收发消息时返回None
+    async fn exchange<S>(&self, ws: &mut WebSocketStream<S>) -> Result<Option<Duration>, PingError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let start = Instant::now();
+        if self.ping {
+            ws.send(Message::Ping(Bytes::from_static(PING_PAYLOAD)))
+                .await
+                .map_err(map_ws_error)?;
+        } else if let Some(send) = &self.send {
+            ws.send(send.clone()).await.map_err(map_ws_error)?;
+        } else if self.expect.is_none() {
+            return Ok(None);
+        }
+
+        while let Some(message) = ws.next().await {
+            let matched = match message.map_err(map_ws_error)? {
+                Message::Pong(_) => self.ping,
+                Message::Text(text) => !self.ping && self.is_expected(text.as_bytes()),
+                Message::Binary(data) => !self.ping && self.is_expected(&data),
+                Message::Close(frame) => {
+                    return Err(PingError::InvalidReply(match frame {
+                        Some(frame) => format!("连接已被服务器关闭: {}", frame),
+                        None => String::from("连接已被服务器关闭"),
+                    }));
+                }
+                _ => false,
+            };
+            if matched {
+                return Ok(Some(start.elapsed()));
+            }
+        }
+        Err(PingError::InvalidReply(String::from("连接已被关闭")))
+    }
+
+    /// 消息是否匹配期望的回复，没有配置期望的回复时任何消息都匹配
+    fn is_expected(&self, payload: &[u8]) -> bool {
+        self.expect
+            .as_ref()
+            .is_none_or(|expect| expect.is_match(payload))
+    }
+}
+
+/// 构造wss连接使用的TLS连接器
+fn tls_connector(insecure: bool) -> Result<TlsConnector, String> {
+    Ok(TlsConnector::from(Arc::new(client_config(insecure, &[])?)))
+}
+
+/// WebSocket的错误转换成探测错误，底层的IO错误保持原样
+fn map_ws_error(e: WsError) -> PingError {
+    match e {
+        WsError::Io(e) => PingError::Io(e),
+        e => PingError::InvalidReply(e.to_string()),
+    }
+}
@@ -0,0 +1,61 @@
+use crate::task::tcp::tcp_settings::decode_hex;
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio_tungstenite::tungstenite::Message;
+
+/// WebSocket任务配置
+///
+/// 默认只检查能否完成升级握手。配置了 `send` 或 `send-hex` 时，握手后发送一条消息，
+/// 再等待匹配 `expect` 的回复，只配置 `expect` 时等待服务器主动推送的消息；
+/// 配置了 `ping` 时发送Ping帧并等待Pong帧。不匹配的消息会被忽略。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct WebsocketSettings {
+    /// 握手请求附加的请求头，如认证的令牌
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// 握手后发送的文本消息
+    pub send: Option<String>,
+    /// 握手后发送的二进制消息的十六进制表示，与 `send` 二选一
+    pub send_hex: Option<String>,
+    /// 期望回复匹配的正则表达式，文本和二进制消息都按字节匹配
+    pub expect: Option<String>,
+    /// 是否发送Ping帧并等待Pong帧，不能与 `send` 同时配置
+    #[serde(default)]
+    pub ping: bool,
+    /// wss连接时是否跳过证书校验，用于自签名证书的服务
+    #[serde(default)]
+    pub insecure: bool,
+}
+
+impl WebsocketSettings {
+    /// 获取握手后要发送的消息
+    ///
+    /// # 返回值
+    /// 未配置时返回Ok(None)，十六进制格式错误或与 `ping` 同时配置时返回错误信息
+    pub fn send_message(&self) -> Result<Option<Message>, String> {
+        let message = match (&self.send, &self.send_hex) {
+            (Some(_), Some(_)) => return Err(String::from("send和send-hex不能同时配置")),
+            (Some(text), None) => Some(Message::text(text.clone())),
+            (None, Some(hex)) => Some(Message::binary(decode_hex(hex)?)),
+            (None, None) => None,
+        };
+        if self.ping && message.is_some() {
+            return Err(String::from("ping和send不能同时配置"));
+        }
+        Ok(message)
+    }
+
+    /// 编译期望回复匹配的正则表达式
+    pub fn expect_regex(&self) -> Result<Option<Regex>, String> {
+        if self.ping && self.expect.is_some() {
+            return Err(String::from("ping和expect不能同时配置"));
+        }
+        self.expect
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("expect不是合法的正则表达式: {}", e))
+    }
+}
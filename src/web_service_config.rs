@@ -24,6 +24,7 @@ async fn metrics(
     debug!("获取所有目标状态耗时: {}ms", elapsed);
     for (host, status) in statuses {
         // 更新不同标签的Gauge值
+        prometheus_metrics.update_probe_metrics(&host, &status.metrics);
         prometheus_metrics.update_metric(host, status.elapsed);
     }
 